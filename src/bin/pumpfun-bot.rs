@@ -1,32 +1,87 @@
 use {
     anyhow::Result,
     clap::Parser,
-    solana_client::rpc_client::RpcClient,
+    solana_client::nonblocking::rpc_client::RpcClient,
     solana_sdk::{
+        address_lookup_table::AddressLookupTableAccount,
         commitment_config::CommitmentConfig,
+        instruction::Instruction,
+        message::Message,
         pubkey::Pubkey,
+        signature::Keypair,
         signer::Signer,
-        system_program,
+        transaction::Transaction,
     },
     spl_associated_token_account::instruction::create_associated_token_account_idempotent,
     std::str::FromStr,
     solana_pump_bot::{
-        cli::cli::{Cli, Commands},
+        cli::cli::{BuildCommands, BuildOptions, Cli, Commands, LookupTableCommands, NonceCommands, PoolCommands, PoolTradeOptions, PoolWalletSource, WalletCommands},
         core::{
-            instructions::{create_buy_instruction, create_sell_instruction},
-            token_price::{calculate_sol_to_get, get_token_price_info, get_bonding_curve_data, calculate_tokens_to_get_bonding_curve},
-            transaction::{send_transaction, create_compute_budget_instructions},
-            wallet::{load_wallet, print_wallet_info},
+            blockhash::{BlockhashCache, DEFAULT_BLOCKHASH_MAX_AGE, DEFAULT_BLOCKHASH_REFRESH_INTERVAL},
+            broadcast::{print_endpoint_stats, Broadcaster},
+            confirmation::{websocket_url, ConfirmationTracker},
+            jito::JitoClient,
+            keystore::Keystore,
+            lookup_table::{
+                create_address_lookup_table, extend_address_lookup_table, fetch_lookup_tables,
+                lookup_table_addresses_from_env, static_pump_accounts,
+            },
+            nonce::{create_nonce_account, send_presigned, DurableNonce, PresignedTransaction},
+            offline::{send_offline, OfflineTransaction, PumpInstructionSummary},
+            fees::{estimate_compute_unit_price, FeeConfig, FeeSchedule, DEFAULT_COMPUTE_UNIT_PRICE},
+            global::GlobalAccount,
+            simulation::{print_simulation_report, simulate_transaction, SimulationAccounts},
+            snapshot::{TradeAddresses, TradeSnapshot},
+            swarm::{execute_swarm, fill_price, shared_nonce_authority, LegReport, LegStatus, SwarmBuy, SwarmPlan},
+            treasury::{
+                batch_fee, batch_instructions, plan_fund, plan_sweep, sign_batch, treasury_source_from_env, Transfer,
+                TreasuryError, FUND_TRANSFERS_PER_TRANSACTION, RENT_EXEMPT_MINIMUM_LAMPORTS, SWEEP_TRANSFERS_PER_TRANSACTION,
+            },
+            token_price::{resolve_creator_accounts, BondingCurveAccount, Quote, QuoteError},
+            transaction::{
+                send_transaction_tracked, create_compute_budget_instructions, AttemptTracker,
+                ComputeUnitPlan, ComputeUnitPlanner, ComputeUnitSource, SubmissionBackend, TxOutcome,
+            },
+            wallet::{keypair_from_base58, keypair_from_json, load_signer, load_wallet, print_wallet_balance, print_wallet_info, read_password, WalletSource},
+            wallet_pool::{fetch_balances, fetch_token_holdings, load_pool_wallets, split_by_weight, PoolEntry, WalletRegistry},
         },
         utils::{
-            config::{token_program_id, find_bonding_curve_pda, find_associated_bonding_curve_pda, 
-                     global_pda, fee_account, event_authority, pump_program_id},
-            utils::get_token_account,
+            config::{token_program_id, find_bonding_curve_pda},
+            utils::{format_sol_amount, get_token_account},
         },
     },
-    std::time::Instant,
+    std::{
+        fs,
+        path::{Path, PathBuf},
+        sync::Arc,
+        time::{Instant, SystemTime, UNIX_EPOCH},
+    },
+    zeroize::Zeroizing,
 };
 
+// Options shared by buy and sell
+struct TradeOptions {
+    creator_address: Option<String>,
+    creator_vault_address: Option<String>,
+    priority_fee: Option<u64>,
+    slippage_bps: Option<u64>,
+    nonce_account: Option<String>,
+    dry_run: bool,
+}
+
+// Trade an unsigned transaction is built for
+enum BuildTrade {
+    Buy { amount_in_lamports: u64 },
+    Sell { amount_str: String },
+}
+
+// 1% tolerance on top of the exact quote
+const DEFAULT_SLIPPAGE_BPS: u64 = 100;
+
+// Order sizes shown by the quote command
+const QUOTE_BUY_LADDER_LAMPORTS: [u64; 4] = [100_000_000, 500_000_000, 1_000_000_000, 5_000_000_000];
+const QUOTE_SELL_LADDER_PERCENT: [u64; 3] = [10, 50, 100];
+
 #[tokio::main]
 async fn main() -> Result<()> {
     println!("\n🚀 Solana Pump Bot");
@@ -35,247 +90,1397 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Buy { token_address, creator_address, creator_vault_address, amount, priority_fee } => {
-            execute_buy(token_address, creator_address, creator_vault_address, amount, priority_fee).await?
+        Commands::Buy { token_address, creator_address, creator_vault_address, amount, priority_fee, slippage_bps, nonce_account } => {
+            let options = TradeOptions { creator_address, creator_vault_address, priority_fee, slippage_bps, nonce_account, dry_run: cli.dry_run };
+            execute_buy(token_address, amount, options).await?
+        },
+        Commands::Sell { token_address, creator_address, creator_vault_address, amount, priority_fee, slippage_bps, nonce_account } => {
+            let options = TradeOptions { creator_address, creator_vault_address, priority_fee, slippage_bps, nonce_account, dry_run: cli.dry_run };
+            execute_sell(token_address, amount, options).await?
+        },
+        Commands::Quote { token_address, wallet } => {
+            execute_quote(token_address, wallet).await?
+        },
+        Commands::Nonce { command: NonceCommands::Create { authority } } => {
+            execute_nonce_create(authority).await?
+        },
+        Commands::Nonce { command: NonceCommands::Show { address } } => {
+            execute_nonce_show(address).await?
+        },
+        Commands::PresignSell { token_address, amount, nonce_account, priority_fee, slippage_bps, out } => {
+            execute_presign_sell(token_address, amount, nonce_account, priority_fee, slippage_bps, out).await?
+        },
+        Commands::Fire { file } => {
+            execute_fire(file).await?
+        },
+        Commands::Build { trade: BuildCommands::Buy { token_address, amount, options } } => {
+            execute_build(token_address, BuildTrade::Buy { amount_in_lamports: amount }, options).await?
+        },
+        Commands::Build { trade: BuildCommands::Sell { token_address, amount, options } } => {
+            execute_build(token_address, BuildTrade::Sell { amount_str: amount }, options).await?
+        },
+        Commands::Sign { file, out } => {
+            execute_sign(file, out)?
+        },
+        Commands::Broadcast { file } => {
+            execute_broadcast(file).await?
+        },
+        Commands::LookupTable { command: LookupTableCommands::Create { wallets } } => {
+            execute_lookup_table_create(wallets).await?
+        },
+        Commands::LookupTable { command: LookupTableCommands::Extend { address, wallets } } => {
+            execute_lookup_table_extend(address, wallets).await?
+        },
+        Commands::LookupTable { command: LookupTableCommands::Show { address } } => {
+            execute_lookup_table_show(address).await?
+        },
+        Commands::Wallet { command: WalletCommands::Import { keystore, keypair_file } } => {
+            execute_wallet_import(keystore, keypair_file)?
+        },
+        Commands::Wallet { command: WalletCommands::ExportPublic } => {
+            execute_wallet_export_public()?
+        },
+        Commands::Wallet { command: WalletCommands::Rotate { keystore, password_only } } => {
+            execute_wallet_rotate(keystore, password_only)?
+        },
+        Commands::Pool { command: PoolCommands::List { mint } } => {
+            execute_pool_list(mint).await?
+        },
+        Commands::Pool { command: PoolCommands::Add { label, source, weight } } => {
+            execute_pool_add(label, source, weight)?
+        },
+        Commands::Pool { command: PoolCommands::Remove { label } } => {
+            execute_pool_remove(label)?
+        },
+        Commands::Pool { command: PoolCommands::Buy { token_address, amount, options } } => {
+            execute_pool_buy(token_address, amount, options, cli.dry_run).await?
+        },
+        Commands::Pool { command: PoolCommands::Sell { token_address, amount, options } } => {
+            execute_pool_sell(token_address, amount, options, cli.dry_run).await?
+        },
+        Commands::Swarm { token_address, budget, max_fills, nonce_accounts, options } => {
+            execute_swarm_buy(token_address, budget, max_fills, nonce_accounts, options, cli.dry_run).await?
         },
-        Commands::Sell { token_address, creator_address, creator_vault_address, amount, priority_fee } => {
-            execute_sell(token_address, creator_address, creator_vault_address, amount, priority_fee).await?
+        Commands::Fund { target, options } => {
+            execute_fund(target, options, cli.dry_run).await?
+        },
+        Commands::Sweep { floor, sell_mints, options } => {
+            execute_sweep(floor, sell_mints, options, cli.dry_run).await?
+        },
+        Commands::SignerDaemon { socket } => {
+            execute_signer_daemon(socket).await?
         },
     }
 
     Ok(())
 }
 
-async fn execute_buy(token_mint: String, creator_address: String, creator_vault_address: String, amount_in_lamports: u64, priority_fee: Option<u64>) -> Result<()> {
-    let start_time = Instant::now();
-    dotenv::dotenv().ok();
+// `--priority-fee` fixes the starting price, otherwise it is estimated from recent fees
+async fn plan_priority_fee(rpc_client: &RpcClient, priority_fee: Option<u64>, writable_accounts: &[Pubkey]) -> Result<FeeSchedule> {
+    let config = FeeConfig::from_env()?;
+    let base_price = match priority_fee {
+        Some(price) => price,
+        None => estimate_compute_unit_price(rpc_client, writable_accounts, &config).await.unwrap_or_else(|e| {
+            println!("⚠️ Priority fee estimation failed ({}), using {}", e, DEFAULT_COMPUTE_UNIT_PRICE);
+            DEFAULT_COMPUTE_UNIT_PRICE
+        }),
+    };
+    println!("💸 Starting priority fee: {} micro-lamports per CU", base_price);
+    if let Some(max_total) = config.max_total_fee_lamports {
+        println!("💸 Total fee cap: {} lamports", max_total);
+    }
+    Ok(FeeSchedule { base_price, config })
+}
 
-    // Initialize RPC client
-    let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set");
-    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+// One websocket connection for all confirmations, `WS_URL` defaults to the one next to `RPC_URL`
+async fn confirmation_tracker(rpc_url: &str) -> Result<Arc<ConfirmationTracker>> {
+    let ws_url = std::env::var("WS_URL").unwrap_or_else(|_| websocket_url(rpc_url));
+    let commitment = match std::env::var("CONFIRMATION_COMMITMENT") {
+        Ok(level) => CommitmentConfig::from_str(&level)?,
+        Err(_) => CommitmentConfig::confirmed(),
+    };
+    Ok(Arc::new(ConfirmationTracker::connect(&ws_url, commitment).await))
+}
 
-    // Load wallet
-    let keypair = load_wallet()?;
-    print_wallet_info(&rpc_client, &keypair)?;
+// Tables from `LOOKUP_TABLE_ADDRESSES`, attempts stay legacy transactions without any
+async fn load_lookup_tables(rpc_client: &RpcClient) -> Result<Vec<AddressLookupTableAccount>> {
+    let lookup_tables = fetch_lookup_tables(rpc_client, &lookup_table_addresses_from_env()?).await?;
+    if !lookup_tables.is_empty() {
+        let addresses: usize = lookup_tables.iter().map(|table| table.addresses.len()).sum();
+        println!("🗂️ Compiling v0 transactions against {} lookup table(s), {} addresses", lookup_tables.len(), addresses);
+    }
+    Ok(lookup_tables)
+}
+
+// Optional `--nonce-account`, which the wallet must be the authority of
+async fn load_durable_nonce(rpc_client: &RpcClient, nonce_account: Option<&str>, payer: &Pubkey) -> Result<Option<DurableNonce>> {
+    let Some(nonce_account) = nonce_account else {
+        return Ok(None);
+    };
+    let nonce = DurableNonce::fetch(rpc_client, &Pubkey::from_str(nonce_account)?).await?;
+    nonce.ensure_authority(payer)?;
+    println!("🔐 Signing against durable nonce {} ({})", nonce.account, nonce.nonce);
+    Ok(Some(nonce))
+}
+
+fn print_durable_nonce(nonce: &DurableNonce) {
+    println!("🔐 Nonce account: {}", nonce.account);
+    println!("👤 Authority: {}", nonce.authority);
+    println!("#️⃣ Nonce: {}", nonce.nonce);
+    println!("🧾 Fee: {} lamports per signature", nonce.lamports_per_signature);
+}
+
+// A configured block engine takes precedence over send endpoints
+fn submission_backend() -> Result<SubmissionBackend> {
+    if let Some(jito) = JitoClient::from_env()? {
+        println!("📦 Submitting Jito bundles, starting tip {} lamports", jito.tip_policy.tip_for_attempt(1));
+        return Ok(SubmissionBackend::Bundle(Arc::new(jito)));
+    }
+    if let Some(broadcaster) = Broadcaster::from_env()? {
+        println!("📡 Broadcasting to {} send endpoint(s)", broadcaster.stats().len());
+        return Ok(SubmissionBackend::Broadcast(Arc::new(broadcaster)));
+    }
+    Ok(SubmissionBackend::Rpc)
+}
+
+// Named accounts of a pump buy or sell instruction
+fn print_instruction_accounts(instruction: &Instruction) {
+    let Some(summary) = PumpInstructionSummary::from_instruction(instruction) else {
+        return;
+    };
+    println!("\n📋 Instruction accounts for verification:");
+    for (index, account) in summary.accounts.iter().enumerate() {
+        println!("#{} - {}: {}", index + 1, account.name, account.address);
+    }
+}
+
+// Pre-signed and swarm transactions have no tip instruction, so they never go out as bundles
+fn untipped_broadcaster(backend: &SubmissionBackend) -> Option<&Broadcaster> {
+    match backend {
+        SubmissionBackend::Broadcast(broadcaster) => Some(broadcaster.as_ref()),
+        SubmissionBackend::Bundle(_) => {
+            println!("⚠️ These transactions carry no Jito tip, sending through RPC_URL");
+            None
+        }
+        SubmissionBackend::Rpc => None,
+    }
+}
+
+fn print_compute_unit_plan(plan: &ComputeUnitPlan) {
+    match plan.source {
+        ComputeUnitSource::Simulated { units_consumed } => {
+            println!("⚙️ Compute unit limit: {} (simulation used {})", plan.compute_units, units_consumed)
+        }
+        ComputeUnitSource::Cached => println!("⚙️ Compute unit limit: {} (cached)", plan.compute_units),
+        ComputeUnitSource::Fallback => {
+            println!("⚙️ Compute unit limit: {} (simulation unavailable, conservative default)", plan.compute_units)
+        }
+    }
+}
+
+// Connections shared by every trade a command places
+struct TradeContext {
+    rpc_client: Arc<RpcClient>,
+    confirmations: Arc<ConfirmationTracker>,
+    blockhash_cache: BlockhashCache,
+    backend: SubmissionBackend,
+    lookup_tables: Vec<AddressLookupTableAccount>,
+}
+
+impl TradeContext {
+    async fn connect() -> Result<Self> {
+        dotenv::dotenv().ok();
+
+        // Initialize RPC client
+        let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set");
+        let confirmations = confirmation_tracker(&rpc_url).await?;
+        let rpc_client = Arc::new(RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed()));
+        // Prefetch blockhashes while trades are prepared
+        let blockhash_cache = BlockhashCache::spawn(rpc_client.clone(), DEFAULT_BLOCKHASH_REFRESH_INTERVAL, DEFAULT_BLOCKHASH_MAX_AGE);
+        // Jito bundles or extra send endpoints, RPC_URL keeps serving reads
+        let backend = submission_backend()?;
+        let lookup_tables = load_lookup_tables(&rpc_client).await?;
+        Ok(Self { rpc_client, confirmations, blockhash_cache, backend, lookup_tables })
+    }
+
+    // A fresh tracker per trade, its attempts must not be confused with another wallet's
+    fn tracker(&self, fee_schedule: FeeSchedule) -> AttemptTracker {
+        AttemptTracker::new()
+            .with_fee_schedule(fee_schedule)
+            .with_blockhash_cache(self.blockhash_cache.clone())
+            .with_backend(self.backend.clone())
+            .with_confirmation_tracker(self.confirmations.clone())
+            .with_lookup_tables(self.lookup_tables.clone())
+    }
+
+    fn print_endpoint_stats(&self) {
+        if let SubmissionBackend::Broadcast(broadcaster) = &self.backend {
+            print_endpoint_stats(broadcaster);
+        }
+    }
+}
+
+async fn execute_buy(token_mint: String, amount_in_lamports: u64, options: TradeOptions) -> Result<()> {
+    let context = TradeContext::connect().await?;
+
+    // Load wallet, or connect to the signer daemon
+    let signer = load_signer()?;
 
     // Parse token mint address
     let token_mint = Pubkey::from_str(&token_mint)?;
+    let result = buy_with_wallet(&context, signer.as_ref(), &token_mint, amount_in_lamports, &options).await;
+    context.print_endpoint_stats();
+    result.map(|_| ())
+}
+
+// Buys with one wallet, `None` when it was only simulated
+async fn buy_with_wallet(
+    context: &TradeContext,
+    signer: &dyn Signer,
+    token_mint: &Pubkey,
+    amount_in_lamports: u64,
+    options: &TradeOptions,
+) -> Result<Option<TxOutcome>> {
+    let TradeOptions { creator_address, creator_vault_address, priority_fee, slippage_bps, nonce_account, dry_run } = options;
+    let start_time = Instant::now();
+    let rpc_client = &context.rpc_client;
     println!("\n🟢 Buying token: {}", token_mint);
     
-    // Parse creator address
-    let token_creator = Pubkey::from_str(&creator_address)?;
-    println!("👤 Token creator: {}", token_creator);
-    
-    // Parse creator vault address
-    let creator_vault = Pubkey::from_str(&creator_vault_address)?;
-    println!("💰 Creator vault: {}", creator_vault);
+    // Parse optional creator overrides, the defaults come from the bonding curve
+    let creator_override = creator_address.as_deref().map(Pubkey::from_str).transpose()?;
+    let creator_vault_override = creator_vault_address.as_deref().map(Pubkey::from_str).transpose()?;
 
-    // Get or create associated token account
-    let associated_token_account = get_token_account(&keypair.pubkey(), &token_mint);
+    // Curve, Global account (fees, fee recipient), token account and wallet in one round trip
+    let mut snapshot = TradeSnapshot::fetch(rpc_client, &signer.pubkey(), token_mint).await?;
+    let TradeAddresses { token_account: associated_token_account, bonding_curve, .. } = snapshot.addresses;
+    let global = snapshot.global;
+    print_wallet_balance(&signer.pubkey(), snapshot.wallet_lamports);
+    println!("🧾 Fees: {} bps protocol + {} bps creator", global.fee_basis_points, global.creator_fee_basis_points);
 
-    // Derive PDAs dynamically
-    let (bonding_curve, _) = find_bonding_curve_pda(&token_mint);
-    let (associated_bonding_curve, _) = find_associated_bonding_curve_pda(&token_mint, &bonding_curve);
-
-    // Get token price info
-    let price_info = get_token_price_info(&rpc_client, &bonding_curve)?;
-    let price_per_token = price_info.token_price as f64 / price_info.token_supply as f64;
-    println!("📊 Token Price: {} SOL", price_per_token);
-    
     // Convert input amount from lamports to SOL
     let sol_amount = amount_in_lamports as f64 / 1_000_000_000.0;
     println!("💰 Amount: {} SOL", sol_amount);
     
-    // Get bonding curve data (for accurate buy quote)
-    let curve_data = get_bonding_curve_data(&rpc_client, &bonding_curve)?;
+    let slippage_bps = slippage_bps.unwrap_or(DEFAULT_SLIPPAGE_BPS);
+    println!("🎯 Slippage tolerance: {} bps", slippage_bps);
+
+    // Creator is stored in the bonding curve, the vault is derived from it
+    let (token_creator, creator_vault) = resolve_creator_accounts(&snapshot.curve, creator_override, creator_vault_override);
+    println!("👤 Token creator: {}", token_creator);
+    println!("💰 Creator vault: {}", creator_vault);
     
     let mut retries = 3;
     let mut attempt = 1;
-    let mut slippage = 0.98; // Start with 2% slippage
+    let fee_schedule = plan_priority_fee(rpc_client, *priority_fee, &[bonding_curve, global.fee_recipient]).await?;
+
+    // Shared across re-quotes so an earlier broadcast can never execute alongside a new one,
+    // and so the priority fee keeps escalating across them
+    let mut tracker = context.tracker(fee_schedule);
+    if let Some(nonce) = load_durable_nonce(rpc_client, nonce_account.as_deref(), &signer.pubkey()).await? {
+        tracker = tracker.with_durable_nonce(nonce);
+    }
+    let mut planner = ComputeUnitPlanner::default();
     
     loop {
         println!("\n🔄 Attempt {} of {}", attempt, retries + 1);
         
-        // Calculate tokens to get using bonding curve math with current slippage
-        let tokens_to_get = calculate_tokens_to_get_bonding_curve(
-            amount_in_lamports,
-            &curve_data,
-            slippage,
-        );
+        println!("📊 Token Price: {} SOL", snapshot.curve.spot_price() / 1_000.0);
+        let quote = Quote::buy_exact_sol_in(&snapshot.curve, &global, amount_in_lamports)?;
+        let tokens_to_get = quote.token_amount;
+        let max_sol_cost = quote.max_sol_cost(slippage_bps);
         
         println!("📈 Expected tokens: {}", tokens_to_get as f64 / 1_000_000.0);
+        println!("🧾 Fees: {} lamports, price impact: {:.2}%", quote.protocol_fee + quote.creator_fee, quote.price_impact_pct);
+        println!("🛡️ Max SOL cost: {} SOL", max_sol_cost as f64 / 1_000_000_000.0);
         let mut instructions = vec![];
-        // Add compute budget instructions
-        instructions.extend(create_compute_budget_instructions(63665, priority_fee.unwrap_or(2)));
         // Create ATA
         instructions.push(
             create_associated_token_account_idempotent(
-                &keypair.pubkey(),
-                &keypair.pubkey(),
-                &token_mint,
+                &signer.pubkey(),
+                &signer.pubkey(),
+                token_mint,
                 &token_program_id(),
             ),
         );
         
-        // Use the provided creator vault instead of deriving it
-        // No longer using: let creator_vault = find_creator_vault(&token_creator);
-        
-        // Log all account addresses for verification
-        println!("\n📋 Instruction accounts for verification:");
-        println!("#1 - Global PDA: {}", global_pda());
-        println!("#2 - Fee account: {}", fee_account());
-        println!("#3 - Token mint: {}", token_mint);
-        println!("#4 - Bonding curve: {}", bonding_curve);
-        println!("#5 - Associated bonding curve: {}", associated_bonding_curve);
-        println!("#6 - User token account: {}", associated_token_account);
-        println!("#7 - User (signer): {}", keypair.pubkey());
-        println!("#8 - System program: {}", system_program::id());
-        println!("#9 - Token program: {}", token_program_id());
-        println!("#10 - Creator vault: {}", creator_vault);
-        println!("#11 - Event authority: {}", event_authority());
-        println!("#12 - Program ID: {}", pump_program_id());
-        
-        // Add buy instruction
-        instructions.push(create_buy_instruction(
-            &keypair.pubkey(),
-            &token_mint,
-            &associated_token_account,
-            &bonding_curve,
-            &associated_bonding_curve,
-            &creator_vault,
-            tokens_to_get,
-            amount_in_lamports,
-        )?);
-        let result = send_transaction(&rpc_client, &keypair, instructions).await;
+        // Add buy instruction, logging all account addresses for verification
+        let buy = snapshot.buy_instruction(&creator_vault, tokens_to_get, max_sol_cost)?;
+        print_instruction_accounts(&buy);
+        instructions.push(buy);
+        // Size the compute budget from a simulation of this instruction shape
+        let plan = planner.plan(rpc_client, &signer.pubkey(), &instructions).await;
+        print_compute_unit_plan(&plan);
+        instructions.splice(0..0, create_compute_budget_instructions(plan.compute_units, fee_schedule.price_for_attempt(1, plan.compute_units, 1, 0)));
+        if *dry_run {
+            let accounts = SimulationAccounts {
+                wallet: signer.pubkey(),
+                token_account: associated_token_account,
+                bonding_curve,
+            };
+            let report = simulate_transaction(rpc_client, &signer.pubkey(), &instructions, &accounts).await?;
+            print_simulation_report(&report);
+            break Ok(None);
+        }
+        let result = send_transaction_tracked(rpc_client, signer, instructions, &mut tracker).await;
         match result {
-            Ok(_) => {
-                println!("\n✅ Transaction completed in {:.2?}", start_time.elapsed());
-                break Ok(());
+            Ok(outcome) => {
+                println!("\n✅ Transaction landed in slot {} (fee: {} lamports)", outcome.slot, outcome.fee);
+                println!("✅ Executed by broadcast attempt {}: {}", outcome.attempt, outcome.signature);
+                println!("✅ Transaction completed in {:.2?}", start_time.elapsed());
+                break Ok(Some(outcome));
             }
             Err(e) => {
-                if retries > 1 {
-                    slippage = slippage * 0.95; // Reduce slippage by 5% for next attempt
+                if retries > 1 && e.is_retryable() {
+                    // Re-quote against a fresh snapshot on the next attempt
+                    snapshot = TradeSnapshot::fetch(rpc_client, &signer.pubkey(), token_mint).await?;
                     retries -= 1;
                     attempt += 1;
                 } else {
                     println!("\n❌ Transaction failed: {}", e);
-                    break Err(e);
+                    break Err(e.into());
                 }
             }
         }
     }
 }
 
-async fn execute_sell(token_mint: String, creator_address: String, creator_vault_address: String, amount_str: String, priority_fee: Option<u64>) -> Result<()> {
-    let start_time = Instant::now();
-    dotenv::dotenv().ok();
-
-    // Initialize RPC client
-    let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set");
-    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+async fn execute_sell(token_mint: String, amount_str: String, options: TradeOptions) -> Result<()> {
+    let context = TradeContext::connect().await?;
 
-    // Load wallet
-    let keypair = load_wallet()?;
-    print_wallet_info(&rpc_client, &keypair)?;
+    // Load wallet, or connect to the signer daemon
+    let signer = load_signer()?;
 
     // Parse token mint address
     let token_mint = Pubkey::from_str(&token_mint)?;
+    let result = sell_with_wallet(&context, signer.as_ref(), &token_mint, &amount_str, &options).await;
+    context.print_endpoint_stats();
+    result.map(|_| ())
+}
+
+// Sells from one wallet, `None` when it was only simulated or holds nothing
+async fn sell_with_wallet(
+    context: &TradeContext,
+    signer: &dyn Signer,
+    token_mint: &Pubkey,
+    amount_str: &str,
+    options: &TradeOptions,
+) -> Result<Option<TxOutcome>> {
+    let TradeOptions { creator_address, creator_vault_address, priority_fee, slippage_bps, nonce_account, dry_run } = options;
+    let start_time = Instant::now();
+    let rpc_client = &context.rpc_client;
     println!("\n🔴 Selling token: {}", token_mint);
     
-    // Parse creator address
-    let token_creator = Pubkey::from_str(&creator_address)?;
-    println!("👤 Token creator: {}", token_creator);
-    
-    // Parse creator vault address
-    let creator_vault = Pubkey::from_str(&creator_vault_address)?;
-    println!("💰 Creator vault: {}", creator_vault);
+    // Parse optional creator overrides, the defaults come from the bonding curve
+    let creator_override = creator_address.as_deref().map(Pubkey::from_str).transpose()?;
+    let creator_vault_override = creator_vault_address.as_deref().map(Pubkey::from_str).transpose()?;
 
-    // Get token account
-    let token_account = get_token_account(&keypair.pubkey(), &token_mint);
+    // Curve, Global account (fees, fee recipient), token balance and wallet in one round trip
+    let snapshot = TradeSnapshot::fetch(rpc_client, &signer.pubkey(), token_mint).await?;
+    let TradeAddresses { token_account, bonding_curve, .. } = snapshot.addresses;
+    let TradeSnapshot { curve, global, .. } = snapshot;
+    print_wallet_balance(&signer.pubkey(), snapshot.wallet_lamports);
 
-    // Get token balance
-    let token_balance = rpc_client.get_token_account_balance(&token_account)?;
-    let total_token_amount = token_balance.amount.parse::<u64>()?;
+    // A missing token account means nothing is held
+    let total_token_amount = snapshot.token_balance.unwrap_or_default();
     println!("💰 Balance: {} tokens", total_token_amount as f64 / 1_000_000.0);
 
     if total_token_amount == 0 {
         println!("❌ No tokens to sell!");
-        return Ok(());
+        return Ok(None);
     }
 
-    // Calculate sell amount based on input
-    let sell_amount = if amount_str.ends_with('%') {
+    let sell_amount = parse_sell_amount(amount_str, total_token_amount)?;
+
+    println!("🧾 Fees: {} bps protocol + {} bps creator", global.fee_basis_points, global.creator_fee_basis_points);
+    println!("📈 Token Price: {} SOL", curve.spot_price() / 1_000.0);
+
+    // Creator is stored in the bonding curve, the vault is derived from it
+    let (token_creator, creator_vault) = resolve_creator_accounts(&curve, creator_override, creator_vault_override);
+    println!("👤 Token creator: {}", token_creator);
+    println!("💰 Creator vault: {}", creator_vault);
+
+    // Calculate expected SOL amount
+    let quote = Quote::sell_exact_tokens_in(&curve, &global, sell_amount)?;
+    println!("💰 Expected return: {} SOL", quote.net_sol as f64 / 1_000_000_000.0);
+    println!("🧾 Fees: {} lamports, price impact: {:.2}%", quote.protocol_fee + quote.creator_fee, quote.price_impact_pct);
+
+    let slippage_bps = slippage_bps.unwrap_or(DEFAULT_SLIPPAGE_BPS);
+    let min_sol_output = quote.min_sol_output(slippage_bps);
+    println!("🛡️ Min SOL output: {} SOL ({} bps slippage)", min_sol_output as f64 / 1_000_000_000.0, slippage_bps);
+
+    let fee_schedule = plan_priority_fee(rpc_client, *priority_fee, &[bonding_curve, global.fee_recipient]).await?;
+
+    // Add sell instruction, logging all account addresses for verification
+    let sell = snapshot.sell_instruction(&creator_vault, sell_amount, min_sol_output)?;
+    print_instruction_accounts(&sell);
+    let mut instructions = vec![sell];
+
+    // Size the compute budget from a simulation of the sell
+    let plan = ComputeUnitPlanner::default().plan(rpc_client, &signer.pubkey(), &instructions).await;
+    print_compute_unit_plan(&plan);
+    instructions.splice(0..0, create_compute_budget_instructions(plan.compute_units, fee_schedule.price_for_attempt(1, plan.compute_units, 1, 0)));
+
+    if *dry_run {
+        let accounts = SimulationAccounts {
+            wallet: signer.pubkey(),
+            token_account,
+            bonding_curve,
+        };
+        let report = simulate_transaction(rpc_client, &signer.pubkey(), &instructions, &accounts).await?;
+        print_simulation_report(&report);
+        return Ok(None);
+    }
+
+    // Send transaction
+    let mut tracker = context.tracker(fee_schedule);
+    if let Some(nonce) = load_durable_nonce(rpc_client, nonce_account.as_deref(), &signer.pubkey()).await? {
+        tracker = tracker.with_durable_nonce(nonce);
+    }
+    let outcome = send_transaction_tracked(rpc_client, signer, instructions, &mut tracker).await?;
+    println!("\n✅ Transaction landed in slot {} (fee: {} lamports)", outcome.slot, outcome.fee);
+    println!("✅ Executed by broadcast attempt {}: {}", outcome.attempt, outcome.signature);
+    println!("✅ Transaction completed in {:.2?}", start_time.elapsed());
+
+    Ok(Some(outcome))
+} 
+
+// A percentage of `total_token_amount` like "50%", or a whole number of tokens
+fn parse_sell_amount(amount_str: &str, total_token_amount: u64) -> Result<u64> {
+    if amount_str.ends_with('%') {
         let percentage = amount_str.trim_end_matches('%').parse::<f64>()?;
         if percentage <= 0.0 || percentage > 100.0 {
             return Err(anyhow::anyhow!("Percentage must be between 0 and 100"));
         }
         let amount = (total_token_amount as f64 * (percentage / 100.0)) as u64;
         println!("📊 Selling {}% ({} tokens)", percentage, amount as f64 / 1_000_000.0);
-        amount
+        Ok(amount)
     } else {
         let amount = 1_000_000 * amount_str.parse::<u64>()?;
         if amount > total_token_amount {
             return Err(anyhow::anyhow!("Sell amount exceeds token balance"));
         }
         println!("📊 Selling {} tokens", amount as f64 / 1_000_000.0);
-        amount
-    };
+        Ok(amount)
+    }
+}
+
+async fn execute_quote(token_mint: String, wallet: Option<String>) -> Result<()> {
+    dotenv::dotenv().ok();
+
+    // Initialize RPC client, no keypair is needed for quoting
+    let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set");
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+
+    // Parse token mint address
+    let token_mint = Pubkey::from_str(&token_mint)?;
+    println!("\n📊 Quoting token: {}", token_mint);
 
-    // Derive PDAs dynamically
     let (bonding_curve, _) = find_bonding_curve_pda(&token_mint);
-    let (associated_bonding_curve, _) = find_associated_bonding_curve_pda(&token_mint, &bonding_curve);
+    let (global, curve) = tokio::try_join!(
+        GlobalAccount::fetch(&rpc_client),
+        BondingCurveAccount::fetch(&rpc_client, &bonding_curve),
+    )?;
+
+    println!("👤 Token creator: {}", curve.creator);
+    println!("📈 Marginal price: {:.12} SOL", curve.spot_price() / 1_000.0);
+    println!("🧾 Fees: {} bps protocol + {} bps creator", global.fee_basis_points, global.creator_fee_basis_points);
+    println!("💧 Real reserves: {} tokens, {} SOL",
+        curve.real_token_reserves as f64 / 1_000_000.0,
+        curve.real_sol_reserves as f64 / 1_000_000_000.0);
+    if curve.complete {
+        println!("❌ Bonding curve is complete, the token has migrated");
+        return Ok(());
+    }
 
-    // Get token price info
-    let price_info = get_token_price_info(&rpc_client, &bonding_curve)?;
-    let price_per_token = price_info.token_price as f64 / price_info.token_supply as f64;
-    println!("📈 Token Price: {} SOL", price_per_token);
+    println!("\n🟢 Buys");
+    print_quote_header("SOL in");
+    for lamports in QUOTE_BUY_LADDER_LAMPORTS {
+        let size = format!("{} SOL", lamports as f64 / 1_000_000_000.0);
+        print_quote_row(&size, Quote::buy_exact_sol_in(&curve, &global, lamports));
+    }
 
-    // Calculate expected SOL amount
-    let expected_sol = calculate_sol_to_get(
-        sell_amount,
-        price_info.token_supply,
-        price_info.token_price,
+    let Some(wallet) = wallet else {
+        println!("\nℹ️ Pass --wallet <address> to quote sells against a holding");
+        return Ok(());
+    };
+    let wallet = Pubkey::from_str(&wallet)?;
+    let token_account = get_token_account(&wallet, &token_mint);
+    // A missing token account means nothing is held
+    let holding = rpc_client
+        .get_token_account_balance(&token_account)
+        .await
+        .map(|balance| balance.amount.parse::<u64>())
+        .unwrap_or(Ok(0))?;
+
+    println!("\n🔴 Sells (holding: {} tokens)", holding as f64 / 1_000_000.0);
+    if holding == 0 {
+        println!("❌ Wallet holds no tokens");
+        return Ok(());
+    }
+    print_quote_header("Sold");
+    for percent in QUOTE_SELL_LADDER_PERCENT {
+        let tokens = (holding as u128 * percent as u128 / 100) as u64;
+        print_quote_row(&format!("{}%", percent), Quote::sell_exact_tokens_in(&curve, &global, tokens));
+    }
+
+    Ok(())
+}
+
+fn print_quote_header(size_label: &str) {
+    println!("{:>10} | {:>18} | {:>14} | {:>16} | {:>16} | {:>8} | {:>12}",
+        size_label, "Tokens", "SOL", "Avg price (SOL)", "Price after", "Impact", "Fees (SOL)");
+}
+
+fn print_quote_row(size: &str, quote: Result<Quote, QuoteError>) {
+    match quote {
+        Ok(quote) => println!("{:>10} | {:>18.6} | {:>14.9} | {:>16.12} | {:>16.12} | {:>7.2}% | {:>12.9}",
+            size,
+            quote.token_amount as f64 / 1_000_000.0,
+            quote.net_sol as f64 / 1_000_000_000.0,
+            quote.average_price() / 1_000.0,
+            quote.new_reserves.spot_price() / 1_000.0,
+            quote.price_impact_pct,
+            (quote.protocol_fee + quote.creator_fee) as f64 / 1_000_000_000.0),
+        Err(err) => println!("{:>10} | {}", size, err),
+    }
+}
+
+async fn execute_nonce_create(authority: Option<String>) -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set");
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let keypair = load_wallet()?;
+    let authority = match authority {
+        Some(authority) => Pubkey::from_str(&authority)?,
+        None => keypair.pubkey(),
+    };
+
+    // Only the authority is needed afterwards, the account keypair just signs its creation
+    let nonce_keypair = Keypair::new();
+    println!("\n🔐 Creating nonce account {}", nonce_keypair.pubkey());
+    let signature = create_nonce_account(&rpc_client, &keypair, &nonce_keypair, &authority).await?;
+    println!("✅ Created: {}", signature);
+    print_durable_nonce(&DurableNonce::fetch(&rpc_client, &nonce_keypair.pubkey()).await?);
+    Ok(())
+}
+
+async fn execute_nonce_show(address: String) -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set");
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    print_durable_nonce(&DurableNonce::fetch(&rpc_client, &Pubkey::from_str(&address)?).await?);
+    Ok(())
+}
+
+async fn execute_presign_sell(token_mint: String, amount_str: String, nonce_account: String, priority_fee: Option<u64>, slippage_bps: u64, out: PathBuf) -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set");
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let keypair = load_wallet()?;
+
+    let token_mint = Pubkey::from_str(&token_mint)?;
+    let nonce_account = Pubkey::from_str(&nonce_account)?;
+    println!("\n✍️ Pre-signing sell of token: {}", token_mint);
+
+    let payer = keypair.pubkey();
+    let (snapshot, nonce) = tokio::try_join!(
+        TradeSnapshot::fetch(&rpc_client, &payer, &token_mint),
+        DurableNonce::fetch(&rpc_client, &nonce_account),
     )?;
-    println!("💰 Expected return: {} SOL", expected_sol as f64 / 1_000_000_000.0);
+    nonce.ensure_authority(&keypair.pubkey())?;
+    print_durable_nonce(&nonce);
 
-    let mut instructions = vec![];
+    let total_token_amount = snapshot.token_balance.unwrap_or_default();
+    if total_token_amount == 0 {
+        println!("❌ No tokens to sell!");
+        return Ok(());
+    }
+    let sell_amount = parse_sell_amount(&amount_str, total_token_amount)?;
 
-    // Add compute budget instructions
-    instructions.extend(create_compute_budget_instructions(34848, priority_fee.unwrap_or(2)));
+    let (_, creator_vault) = resolve_creator_accounts(&snapshot.curve, None, None);
+    let quote = Quote::sell_exact_tokens_in(&snapshot.curve, &snapshot.global, sell_amount)?;
+    let min_sol_output = quote.min_sol_output(slippage_bps);
+    println!("💰 Expected return now: {} SOL", quote.net_sol as f64 / 1_000_000_000.0);
+    println!("🛡️ Min SOL output: {} SOL ({} bps slippage)", min_sol_output as f64 / 1_000_000_000.0, slippage_bps);
 
-    // Use the provided creator vault instead of deriving it
-    // No longer using: let creator_vault = find_creator_vault(&token_creator);
-    
-    // Log all account addresses for verification
-    println!("\n📋 Instruction accounts for verification:");
-    println!("#1 - Global PDA: {}", global_pda());
-    println!("#2 - Fee account: {}", fee_account());
-    println!("#3 - Token mint: {}", token_mint);
-    println!("#4 - Bonding curve: {}", bonding_curve);
-    println!("#5 - Associated bonding curve: {}", associated_bonding_curve);
-    println!("#6 - User token account: {}", token_account);
-    println!("#7 - User (signer): {}", keypair.pubkey());
-    println!("#8 - System program: {}", system_program::id());
-    println!("#9 - Token program: {}", token_program_id());
-    println!("#10 - Creator vault: {}", creator_vault);
-    println!("#11 - Event authority: {}", event_authority());
-    println!("#12 - Program ID: {}", pump_program_id());
-
-    // Add sell instruction
-    instructions.push(create_sell_instruction(
-        &keypair.pubkey(),
-        &token_mint,
-        &token_account,
-        &bonding_curve,
-        &associated_bonding_curve,
-        &creator_vault,
-        sell_amount,
-        0,
-    )?);
+    let fee_schedule = plan_priority_fee(&rpc_client, priority_fee, &[snapshot.addresses.bonding_curve, snapshot.global.fee_recipient]).await?;
+    let sell = snapshot.sell_instruction(&creator_vault, sell_amount, min_sol_output)?;
+    // The nonce advance costs far less than the planner's margin
+    let plan = ComputeUnitPlanner::default().plan(&rpc_client, &keypair.pubkey(), std::slice::from_ref(&sell)).await;
+    print_compute_unit_plan(&plan);
+    let mut instructions = create_compute_budget_instructions(plan.compute_units, fee_schedule.price_for_attempt(1, plan.compute_units, 1, 0));
+    instructions.push(sell);
+    let transaction = nonce.sign(&keypair, &instructions)?;
 
-    // Send transaction
-    send_transaction(&rpc_client, &keypair, instructions).await?;
-    println!("\n✅ Transaction completed in {:.2?}", start_time.elapsed());
+    // Check the exact signed transaction once, it is sent without preflight later
+    let simulation = rpc_client.simulate_transaction(&transaction).await?.value;
+    if let Some(err) = simulation.err {
+        for log in simulation.logs.unwrap_or_default() {
+            println!("{}", log);
+        }
+        return Err(anyhow::anyhow!("Pre-signed sell fails simulation: {}", err));
+    }
+
+    let description = format!("sell {} tokens of {} for at least {} SOL", sell_amount as f64 / 1_000_000.0, token_mint, min_sol_output as f64 / 1_000_000_000.0);
+    PresignedTransaction::new(description, &nonce, &transaction)?.save(&out)?;
+    println!("\n✅ Pre-signed {} into {}", transaction.signatures[0], out.display());
+    println!("🔥 Fire it with: pumpfun-bot fire {}", out.display());
+    println!("⚠️ Any other transaction using nonce account {} invalidates it", nonce.account);
+    Ok(())
+}
+
+async fn execute_fire(file: PathBuf) -> Result<()> {
+    let start_time = Instant::now();
+    dotenv::dotenv().ok();
+
+    let presigned = PresignedTransaction::load(&file)?;
+    let transaction = presigned.transaction()?;
+    let nonce_account = presigned.nonce_account()?;
+
+    let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set");
+    let confirmations = confirmation_tracker(&rpc_url).await?;
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let backend = submission_backend()?;
+    let broadcaster = untipped_broadcaster(&backend);
+
+    println!("\n🔥 Firing: {}", presigned.description);
+    let result = send_presigned(&rpc_client, &confirmations, broadcaster, &transaction, &nonce_account).await;
+    if let Some(broadcaster) = broadcaster {
+        print_endpoint_stats(broadcaster);
+    }
+    let slot = result?;
+    println!("\n✅ Transaction landed in slot {}: {}", slot, presigned.signature);
+    println!("✅ Fired in {:.2?}", start_time.elapsed());
+    Ok(())
+}
+
+async fn execute_build(token_mint: String, trade: BuildTrade, options: BuildOptions) -> Result<()> {
+    let BuildOptions { payer, priority_fee, slippage_bps, nonce_account, out } = options;
+    dotenv::dotenv().ok();
+
+    // Only public data is needed, the key stays on the signing machine
+    let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set");
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let payer = Pubkey::from_str(&payer)?;
+    let token_mint = Pubkey::from_str(&token_mint)?;
+    println!("\n🧱 Building unsigned transaction for payer {}", payer);
+
+    let snapshot = TradeSnapshot::fetch(&rpc_client, &payer, &token_mint).await?;
+    let (_, creator_vault) = resolve_creator_accounts(&snapshot.curve, None, None);
+    let slippage_bps = slippage_bps.unwrap_or(DEFAULT_SLIPPAGE_BPS);
+
+    let (description, mut instructions) = match trade {
+        BuildTrade::Buy { amount_in_lamports } => {
+            let quote = Quote::buy_exact_sol_in(&snapshot.curve, &snapshot.global, amount_in_lamports)?;
+            let max_sol_cost = quote.max_sol_cost(slippage_bps);
+            println!("📈 Expected tokens: {}", quote.token_amount as f64 / 1_000_000.0);
+            println!("🛡️ Max SOL cost: {} SOL ({} bps slippage)", max_sol_cost as f64 / 1_000_000_000.0, slippage_bps);
+            let create_ata = create_associated_token_account_idempotent(&payer, &payer, &token_mint, &token_program_id());
+            let description = format!("buy {} tokens of {} for at most {} SOL", quote.token_amount as f64 / 1_000_000.0, token_mint, max_sol_cost as f64 / 1_000_000_000.0);
+            (description, vec![create_ata, snapshot.buy_instruction(&creator_vault, quote.token_amount, max_sol_cost)?])
+        }
+        BuildTrade::Sell { amount_str } => {
+            let total_token_amount = snapshot.token_balance.unwrap_or_default();
+            if total_token_amount == 0 {
+                println!("❌ No tokens to sell!");
+                return Ok(());
+            }
+            let sell_amount = parse_sell_amount(&amount_str, total_token_amount)?;
+            let quote = Quote::sell_exact_tokens_in(&snapshot.curve, &snapshot.global, sell_amount)?;
+            let min_sol_output = quote.min_sol_output(slippage_bps);
+            println!("🛡️ Min SOL output: {} SOL ({} bps slippage)", min_sol_output as f64 / 1_000_000_000.0, slippage_bps);
+            let description = format!("sell {} tokens of {} for at least {} SOL", sell_amount as f64 / 1_000_000.0, token_mint, min_sol_output as f64 / 1_000_000_000.0);
+            (description, vec![snapshot.sell_instruction(&creator_vault, sell_amount, min_sol_output)?])
+        }
+    };
+
+    let fee_schedule = plan_priority_fee(&rpc_client, priority_fee, &[snapshot.addresses.bonding_curve, snapshot.global.fee_recipient]).await?;
+    let plan = ComputeUnitPlanner::default().plan(&rpc_client, &payer, &instructions).await;
+    print_compute_unit_plan(&plan);
+    instructions.splice(0..0, create_compute_budget_instructions(plan.compute_units, fee_schedule.price_for_attempt(1, plan.compute_units, 1, 0)));
+
+    // A nonce keeps the transaction valid for as long as signing takes
+    let nonce = load_durable_nonce(&rpc_client, nonce_account.as_deref(), &payer).await?;
+    let (blockhash, last_valid_block_height) = match &nonce {
+        Some(nonce) => {
+            instructions.insert(0, nonce.advance_instruction());
+            (nonce.nonce, None)
+        }
+        None => {
+            let (blockhash, last_valid_block_height) = rpc_client.get_latest_blockhash_with_commitment(rpc_client.commitment()).await?;
+            println!("⚠️ Signed against a recent blockhash, it must be broadcast before block height {}", last_valid_block_height);
+            (blockhash, Some(last_valid_block_height))
+        }
+    };
+    let transaction = Transaction::new_unsigned(Message::new_with_blockhash(&instructions, Some(&payer), &blockhash));
+
+    let offline = OfflineTransaction::new(description, &transaction, nonce.as_ref().map(|nonce| &nonce.account), last_valid_block_height)?;
+    print_offline_transaction(&offline)?;
+    println!("\n📦 Unsigned transaction (base64):\n{}", offline.transaction);
+    offline.save(&out)?;
+    println!("\n✅ Wrote {}", out.display());
+    println!("✍️ Sign it with: pumpfun-bot sign {}", out.display());
+    Ok(())
+}
 
+fn print_offline_transaction(offline: &OfflineTransaction) -> Result<()> {
+    println!("\n📝 {}", offline.description);
+    println!("{}", serde_json::to_string_pretty(&offline.summary)?);
     Ok(())
-} 
\ No newline at end of file
+}
+
+// Runs on the signing machine, so nothing here touches the network
+fn execute_sign(file: PathBuf, out: Option<PathBuf>) -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let mut offline = OfflineTransaction::load(&file)?;
+    // Reviewed from the transaction bytes, an edited summary is refused
+    offline.verified_transaction()?;
+    print_offline_transaction(&offline)?;
+    if let Some(last_valid_block_height) = offline.last_valid_block_height {
+        println!("⚠️ Must be broadcast before block height {}", last_valid_block_height);
+    }
+
+    let keypair = load_wallet()?;
+    offline.sign(&keypair)?;
+    let out = out.unwrap_or(file);
+    offline.save(&out)?;
+    println!("\n✅ Signed by {} into {}", keypair.pubkey(), out.display());
+    if !offline.is_signed()? {
+        println!("✍️ More signatures are required before broadcasting");
+    }
+    println!("📡 Broadcast it with: pumpfun-bot broadcast {}", out.display());
+    Ok(())
+}
+
+async fn execute_broadcast(file: PathBuf) -> Result<()> {
+    let start_time = Instant::now();
+    dotenv::dotenv().ok();
+
+    let offline = OfflineTransaction::load(&file)?;
+    let signature = offline.transaction()?.signatures[0];
+
+    let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set");
+    let confirmations = confirmation_tracker(&rpc_url).await?;
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let backend = submission_backend()?;
+    let broadcaster = untipped_broadcaster(&backend);
+
+    println!("\n📡 Broadcasting: {}", offline.description);
+    let result = send_offline(&rpc_client, &confirmations, broadcaster, &offline).await;
+    if let Some(broadcaster) = broadcaster {
+        print_endpoint_stats(broadcaster);
+    }
+    let slot = result?;
+    println!("\n✅ Transaction landed in slot {}: {}", slot, signature);
+    println!("✅ Broadcast in {:.2?}", start_time.elapsed());
+    Ok(())
+}
+
+// Static pump accounts, the wallet and any `--wallet` addresses
+async fn lookup_table_addresses(rpc_client: &RpcClient, wallet: &Pubkey, wallets: &[String]) -> Result<Vec<Pubkey>> {
+    let global = GlobalAccount::fetch(rpc_client).await?;
+    let mut addresses = static_pump_accounts(&global);
+    addresses.push(*wallet);
+    for extra in wallets {
+        addresses.push(Pubkey::from_str(extra)?);
+    }
+    Ok(addresses)
+}
+
+async fn execute_lookup_table_create(wallets: Vec<String>) -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set");
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let keypair = load_wallet()?;
+    let addresses = lookup_table_addresses(&rpc_client, &keypair.pubkey(), &wallets).await?;
+
+    let address = create_address_lookup_table(&rpc_client, &keypair).await?;
+    println!("\n🗂️ Created lookup table {}", address);
+    let table = AddressLookupTableAccount { key: address, addresses: vec![] };
+    let added = extend_address_lookup_table(&rpc_client, &keypair, &table, &addresses).await?;
+    println!("✅ Stored {} addresses", added.len());
+    println!("📝 Add LOOKUP_TABLE_ADDRESSES={} to your .env file", address);
+    Ok(())
+}
+
+async fn execute_lookup_table_extend(address: String, wallets: Vec<String>) -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set");
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let keypair = load_wallet()?;
+    let addresses = lookup_table_addresses(&rpc_client, &keypair.pubkey(), &wallets).await?;
+
+    let table = fetch_lookup_tables(&rpc_client, &[Pubkey::from_str(&address)?]).await?.remove(0);
+    let added = extend_address_lookup_table(&rpc_client, &keypair, &table, &addresses).await?;
+    if added.is_empty() {
+        println!("\n✅ Lookup table {} already holds every address", table.key);
+    } else {
+        println!("\n✅ Added {} addresses to {}", added.len(), table.key);
+    }
+    Ok(())
+}
+
+async fn execute_lookup_table_show(address: String) -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set");
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let table = fetch_lookup_tables(&rpc_client, &[Pubkey::from_str(&address)?]).await?.remove(0);
+    println!("\n🗂️ Lookup table {} holds {} addresses", table.key, table.addresses.len());
+    for (index, address) in table.addresses.iter().enumerate() {
+        println!("#{} - {}", index, address);
+    }
+    Ok(())
+}
+
+// Asks for a new keystore password twice, `KEYSTORE_PASSWORD` skips the prompt
+fn new_keystore_password() -> Result<Zeroizing<String>> {
+    if let Ok(password) = std::env::var("KEYSTORE_PASSWORD") {
+        return Ok(Zeroizing::new(password));
+    }
+    let password = Zeroizing::new(rpassword::prompt_password("🔑 New keystore password: ")?);
+    if password.is_empty() {
+        return Err(anyhow::anyhow!("Keystore password cannot be empty"));
+    }
+    let confirmation = Zeroizing::new(rpassword::prompt_password("🔑 Repeat the password: ")?);
+    if *password != *confirmation {
+        return Err(anyhow::anyhow!("Passwords do not match"));
+    }
+    Ok(password)
+}
+
+// Keypair file when given, then PRIVATE_KEY, then a hidden prompt for a base58 secret key
+fn import_keypair(keypair_file: Option<PathBuf>) -> Result<Keypair> {
+    if let Some(path) = keypair_file {
+        return keypair_from_json(&Zeroizing::new(fs::read_to_string(path)?));
+    }
+    if let Ok(encoded) = std::env::var("PRIVATE_KEY") {
+        return keypair_from_base58(&Zeroizing::new(encoded));
+    }
+    keypair_from_base58(&Zeroizing::new(rpassword::prompt_password("🔑 Base58 secret key: ")?))
+}
+
+fn execute_wallet_import(keystore: PathBuf, keypair_file: Option<PathBuf>) -> Result<()> {
+    dotenv::dotenv().ok();
+
+    if keystore.exists() {
+        return Err(anyhow::anyhow!("{} already exists, use `wallet rotate` to replace it", keystore.display()));
+    }
+    let keypair = import_keypair(keypair_file)?;
+    let password = new_keystore_password()?;
+    Keystore::encrypt(&keypair, password.as_bytes())?.save(&keystore)?;
+    println!("\n🔐 Imported {} into {}", keypair.pubkey(), keystore.display());
+    println!("📝 Set WALLET_KEYSTORE={} and remove PRIVATE_KEY from your .env file", keystore.display());
+    Ok(())
+}
+
+fn execute_wallet_export_public() -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let source = WalletSource::from_env()?;
+    println!("\n👛 Wallet source: {:?}", source);
+    println!("{}", source.pubkey()?);
+    Ok(())
+}
+
+// The old keystore is kept next to the new one, it may still control funds
+fn keystore_backup_path(keystore: &Path) -> Result<PathBuf> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let mut name = keystore.file_name().ok_or_else(|| anyhow::anyhow!("{} is not a file", keystore.display()))?.to_os_string();
+    name.push(format!(".{}.bak", timestamp));
+    Ok(keystore.with_file_name(name))
+}
+
+fn execute_wallet_rotate(keystore: PathBuf, password_only: bool) -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let old_keypair = Keystore::load(&keystore)?.decrypt(read_password("🔑 Current keystore password: ")?.as_bytes())?;
+    let keypair = if password_only { old_keypair.insecure_clone() } else { Keypair::new() };
+    let password = new_keystore_password()?;
+    let rotated = Keystore::encrypt(&keypair, password.as_bytes())?;
+
+    let backup = keystore_backup_path(&keystore)?;
+    fs::copy(&keystore, &backup)?;
+    rotated.save(&keystore)?;
+    println!("\n💾 Previous keystore kept at {}", backup.display());
+    if password_only {
+        println!("🔐 Re-encrypted {} under the new password", keypair.pubkey());
+    } else {
+        println!("🔐 Rotated {} -> {}", old_keypair.pubkey(), keypair.pubkey());
+        println!("⚠️ Move funds and token balances from {} to {}, then delete the backup", old_keypair.pubkey(), keypair.pubkey());
+    }
+    Ok(())
+}
+
+fn pool_trade_options(options: &PoolTradeOptions, dry_run: bool) -> TradeOptions {
+    TradeOptions {
+        creator_address: None,
+        creator_vault_address: None,
+        priority_fee: options.priority_fee,
+        slippage_bps: options.slippage_bps,
+        nonce_account: None,
+        dry_run,
+    }
+}
+
+// Holds the wallet key so trading processes can run with only SIGNER_SOCKET set
+#[cfg(unix)]
+async fn execute_signer_daemon(socket: Option<PathBuf>) -> Result<()> {
+    use solana_pump_bot::core::remote_signer::{SignerDaemon, SignerPolicy, DEFAULT_SIGNER_SOCKET, DEFAULT_SIGNER_SPEND_FILE};
+
+    dotenv::dotenv().ok();
+
+    let socket = socket
+        .or_else(|| std::env::var("SIGNER_SOCKET").ok().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SIGNER_SOCKET));
+    let ledger_path = std::env::var("SIGNER_SPEND_FILE").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from(DEFAULT_SIGNER_SPEND_FILE));
+    let policy = SignerPolicy::from_env()?;
+    let daemon = Arc::new(SignerDaemon::new(load_wallet()?, policy, Some(ledger_path.clone()))?);
+
+    println!("\n🔏 Signer daemon for {}", daemon.pubkey());
+    println!("🛡️ Limits: {} per transfer, {} per transaction, {} per day",
+        format_sol_amount(policy.max_transfer_lamports),
+        format_sol_amount(policy.max_transaction_lamports),
+        format_sol_amount(policy.daily_limit_lamports),
+    );
+    println!("📒 Signed today: {} (ledger {})", format_sol_amount(daemon.spent_today()), ledger_path.display());
+    println!("🔌 Listening on {}", socket.display());
+    daemon.serve(&socket).await
+}
+
+#[cfg(not(unix))]
+async fn execute_signer_daemon(_socket: Option<PathBuf>) -> Result<()> {
+    Err(anyhow::anyhow!("The signer daemon needs Unix domain sockets"))
+}
+
+// One line per wallet, fails when any wallet's trade failed
+fn print_pool_summary(results: &[(String, Pubkey, Result<Option<TxOutcome>>)]) -> Result<()> {
+    println!("\n📋 Pool summary:");
+    let mut failed = 0;
+    for (label, wallet, result) in results {
+        match result {
+            Ok(Some(outcome)) => println!("✅ {} ({}): slot {}, {}", label, wallet, outcome.slot, outcome.signature),
+            Ok(None) => println!("➖ {} ({}): nothing sent", label, wallet),
+            Err(e) => {
+                failed += 1;
+                println!("❌ {} ({}): {}", label, wallet, e);
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} of {} wallet trades failed", failed, results.len()));
+    }
+    Ok(())
+}
+
+async fn execute_pool_list(mint: Option<String>) -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set");
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let path = WalletRegistry::path_from_env();
+    let registry = WalletRegistry::load(&path)?;
+    let entries = registry.select(&[])?;
+    // Keystores hold their public key in the clear, listing needs no password
+    let wallets = entries.iter().map(|entry| entry.source.pubkey()).collect::<Result<Vec<_>>>()?;
+    let holdings = match mint.as_deref().map(Pubkey::from_str).transpose()? {
+        Some(mint) => Some(fetch_token_holdings(&rpc_client, &wallets, &mint).await?),
+        None => None,
+    };
+
+    println!("\n👛 Wallet pool {} ({} wallets)", path.display(), entries.len());
+    let weight_sum: u64 = entries.iter().map(|entry| entry.weight).sum();
+    for (index, (entry, wallet)) in entries.iter().zip(&wallets).enumerate() {
+        let share = if weight_sum == 0 { 0.0 } else { entry.weight as f64 * 100.0 / weight_sum as f64 };
+        println!("\n🏷️ {} (weight {}, {:.1}% of buys)", entry.label, entry.weight, share);
+        print_wallet_info(&rpc_client, wallet).await?;
+        if let Some(holdings) = &holdings {
+            println!("Tokens: {}", holdings[index] as f64 / 1_000_000.0);
+        }
+    }
+    Ok(())
+}
+
+fn execute_pool_add(label: String, source: PoolWalletSource, weight: u64) -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let source = match source {
+        PoolWalletSource { keystore: Some(path), .. } => WalletSource::Keystore(path),
+        PoolWalletSource { keypair_file: Some(path), .. } => WalletSource::KeypairFile(path),
+        PoolWalletSource { env: Some(name), .. } => WalletSource::Env(name),
+        _ => return Err(anyhow::anyhow!("A keystore, keypair file or environment variable is required")),
+    };
+    // Resolving the public key checks the key is readable before it is registered
+    let wallet = source.pubkey()?;
+    let path = WalletRegistry::path_from_env();
+    let mut registry = WalletRegistry::load_or_default(&path)?;
+    registry.add(PoolEntry { label: label.clone(), source, weight })?;
+    registry.save(&path)?;
+    println!("\n➕ Added {} ({}) with weight {} to {}", label, wallet, weight, path.display());
+    Ok(())
+}
+
+fn execute_pool_remove(label: String) -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let path = WalletRegistry::path_from_env();
+    let mut registry = WalletRegistry::load(&path)?;
+    let entry = registry.remove(&label)?;
+    registry.save(&path)?;
+    println!("\n➖ Removed {} from {}, {:?} was left untouched", entry.label, path.display(), entry.source);
+    Ok(())
+}
+
+async fn execute_pool_buy(token_mint: String, amount_in_lamports: u64, options: PoolTradeOptions, dry_run: bool) -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let token_mint = Pubkey::from_str(&token_mint)?;
+    let registry = WalletRegistry::load(&WalletRegistry::path_from_env())?;
+    // Zero weight wallets take no part in buys, so they are not unlocked either
+    let entries: Vec<&PoolEntry> = registry.select(&options.wallets)?.into_iter().filter(|entry| entry.weight > 0).collect();
+    let amounts = split_by_weight(amount_in_lamports, &entries.iter().map(|entry| entry.weight).collect::<Vec<_>>())?;
+    let wallets = load_pool_wallets(&entries)?;
+
+    println!("\n🟢 Splitting {} across {} wallets:", format_sol_amount(amount_in_lamports), wallets.len());
+    for (wallet, amount) in wallets.iter().zip(&amounts) {
+        println!("🏷️ {} ({}): {}", wallet.label, wallet.keypair.pubkey(), format_sol_amount(*amount));
+    }
+
+    let context = TradeContext::connect().await?;
+    let trade_options = pool_trade_options(&options, dry_run);
+    let mut results = Vec::with_capacity(wallets.len());
+    for (wallet, amount) in wallets.iter().zip(amounts) {
+        let result = if amount == 0 {
+            Ok(None)
+        } else {
+            println!("\n━━━━━━━━━━ 🏷️ {} ━━━━━━━━━━", wallet.label);
+            buy_with_wallet(&context, &wallet.keypair, &token_mint, amount, &trade_options).await
+        };
+        results.push((wallet.label.clone(), wallet.keypair.pubkey(), result));
+    }
+    context.print_endpoint_stats();
+    print_pool_summary(&results)
+}
+
+async fn execute_pool_sell(token_mint: String, amount_str: String, options: PoolTradeOptions, dry_run: bool) -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set");
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let token_mint = Pubkey::from_str(&token_mint)?;
+    let registry = WalletRegistry::load(&WalletRegistry::path_from_env())?;
+    let entries = registry.select(&options.wallets)?;
+
+    // Only wallets holding the token are unlocked
+    let pubkeys = entries.iter().map(|entry| entry.source.pubkey()).collect::<Result<Vec<_>>>()?;
+    let holdings = fetch_token_holdings(&rpc_client, &pubkeys, &token_mint).await?;
+    println!("\n🔴 Pool holdings of {}:", token_mint);
+    let mut holders = vec![];
+    for ((entry, wallet), holding) in entries.iter().zip(&pubkeys).zip(&holdings) {
+        println!("🏷️ {} ({}): {} tokens", entry.label, wallet, *holding as f64 / 1_000_000.0);
+        if *holding > 0 {
+            holders.push(*entry);
+        }
+    }
+    if holders.is_empty() {
+        println!("❌ No pool wallet holds the token");
+        return Ok(());
+    }
+    let wallets = load_pool_wallets(&holders)?;
+
+    let context = TradeContext::connect().await?;
+    let trade_options = pool_trade_options(&options, dry_run);
+    let mut results = Vec::with_capacity(wallets.len());
+    for wallet in &wallets {
+        println!("\n━━━━━━━━━━ 🏷️ {} ━━━━━━━━━━", wallet.label);
+        let result = sell_with_wallet(&context, &wallet.keypair, &token_mint, &amount_str, &trade_options).await;
+        results.push((wallet.label.clone(), wallet.keypair.pubkey(), result));
+    }
+    context.print_endpoint_stats();
+    print_pool_summary(&results)
+}
+
+fn print_swarm_report(reports: &[LegReport], labels: &[String], plan: &SwarmPlan) {
+    println!("\n🐝 Swarm report:");
+    let mut spent = 0;
+    for (report, label) in reports.iter().zip(labels) {
+        let prefix = format!("{} ({}, group {})", label, report.leg.wallet, report.leg.group);
+        match &report.status {
+            LegStatus::Filled { slot, fill: Some(fill) } => {
+                spent += fill.sol_amount;
+                println!("✅ {}: filled in slot {}, {} tokens for {} at {:.12} SOL per token",
+                    prefix, slot, fill.token_amount as f64 / 1_000_000.0, format_sol_amount(fill.sol_amount), fill_price(fill));
+            }
+            LegStatus::Filled { slot, fill: None } => println!("✅ {}: filled in slot {} (trade event unavailable)", prefix, slot),
+            LegStatus::Superseded => println!("➖ {}: superseded, another leg advanced the nonce", prefix),
+            LegStatus::Failed(err) => println!("❌ {}: {}", prefix, err),
+            LegStatus::Unsettled { nonce_account } => {
+                println!("⚠️ {}: nonce {} could not be advanced, this leg may still land", prefix, nonce_account)
+            }
+        }
+        println!("   {}", report.signature);
+    }
+    let fills = reports.iter().filter(|report| report.is_filled()).count();
+    println!("🐝 {} of at most {} fills, {} spent of a {} budget", fills, plan.max_fills, format_sol_amount(spent), format_sol_amount(plan.budget_lamports));
+}
+
+async fn execute_swarm_buy(
+    token_mint: String,
+    budget_lamports: u64,
+    max_fills: Option<usize>,
+    nonce_accounts: Vec<String>,
+    options: PoolTradeOptions,
+    dry_run: bool,
+) -> Result<()> {
+    let context = TradeContext::connect().await?;
+    let rpc_client = &context.rpc_client;
+    let token_mint = Pubkey::from_str(&token_mint)?;
+    println!("\n🐝 Swarm buying token: {}", token_mint);
+
+    let mut nonces = Vec::with_capacity(nonce_accounts.len());
+    for nonce_account in &nonce_accounts {
+        nonces.push(DurableNonce::fetch(rpc_client, &Pubkey::from_str(nonce_account)?).await?);
+    }
+    // Checked before any keystore is unlocked
+    let nonce_authority = shared_nonce_authority(&nonces)?;
+
+    let registry = WalletRegistry::load(&WalletRegistry::path_from_env())?;
+    let entries: Vec<&PoolEntry> = registry.select(&options.wallets)?.into_iter().filter(|entry| entry.weight > 0).collect();
+    let wallets = load_pool_wallets(&entries)?;
+    // Every leg sharing a nonce is co-signed by its authority, a pool wallet or the configured wallet
+    let nonce_authority = match nonce_authority {
+        Some(authority) => match wallets.iter().find(|wallet| wallet.keypair.pubkey() == authority) {
+            Some(wallet) => Some(wallet.keypair.insecure_clone()),
+            None => Some(load_wallet()?),
+        },
+        None => None,
+    };
+
+    let slippage_bps = options.slippage_bps.unwrap_or(DEFAULT_SLIPPAGE_BPS);
+    let weighted: Vec<(Pubkey, u64)> = wallets.iter().map(|wallet| (wallet.keypair.pubkey(), wallet.weight)).collect();
+    let plan = SwarmPlan::new(budget_lamports, slippage_bps, &weighted, nonces, max_fills)?;
+    println!("🎯 At most {} fills from a {} budget", plan.max_fills, format_sol_amount(budget_lamports));
+    for nonce in &plan.nonces {
+        println!("🔐 Group nonce account: {}", nonce.account);
+    }
+    for (leg, wallet) in plan.legs.iter().zip(&wallets) {
+        println!("🏷️ {} ({}): group {}, {}", wallet.label, leg.wallet, leg.group, format_sol_amount(leg.amount_in_lamports));
+    }
+
+    // One snapshot of the curve for every leg, all of them race against the same state
+    let snapshot = TradeSnapshot::fetch(rpc_client, &plan.legs[0].wallet, &token_mint).await?;
+    let (_, creator_vault) = resolve_creator_accounts(&snapshot.curve, None, None);
+    println!("📊 Token Price: {} SOL", snapshot.curve.spot_price() / 1_000.0);
+    println!("🎯 Slippage tolerance: {} bps", slippage_bps);
+
+    // Legs share one instruction shape, so the first one sizes the compute budget for all
+    let quote = Quote::buy_exact_sol_in(&snapshot.curve, &snapshot.global, plan.legs[0].amount_in_lamports)?;
+    let shape = vec![
+        create_associated_token_account_idempotent(&plan.legs[0].wallet, &plan.legs[0].wallet, &token_mint, &token_program_id()),
+        snapshot.buy_instruction(&creator_vault, quote.token_amount, quote.max_sol_cost(slippage_bps))?,
+    ];
+    let compute_plan = ComputeUnitPlanner::default().plan(rpc_client, &plan.legs[0].wallet, &shape).await;
+    print_compute_unit_plan(&compute_plan);
+    let fee_schedule = plan_priority_fee(rpc_client, options.priority_fee, &[snapshot.addresses.bonding_curve, snapshot.global.fee_recipient]).await?;
+    // Legs carry no tip, and with nonces the nonce authority co-signs each one
+    let signatures = 1 + nonce_authority.is_some() as usize;
+    let compute_budget = create_compute_budget_instructions(compute_plan.compute_units, fee_schedule.price_for_attempt(1, compute_plan.compute_units, signatures, 0));
+
+    let buy = SwarmBuy { snapshot, creator_vault, compute_budget };
+    let (recent_blockhash, last_valid_block_height) = context.blockhash_cache.get(rpc_client, None).await?;
+    let keypairs: Vec<&Keypair> = wallets.iter().map(|wallet| &wallet.keypair).collect();
+    let legs = plan.sign_legs(&keypairs, nonce_authority.as_ref(), &buy, recent_blockhash)?;
+    println!("💰 Worst case spend {} of {}, with every group filling at full slippage", format_sol_amount(plan.worst_case_spend(&legs)), format_sol_amount(budget_lamports));
+    let labels: Vec<String> = wallets.iter().map(|wallet| wallet.label.clone()).collect();
+
+    if dry_run {
+        println!("\n🧪 Simulating every leg (nothing was sent)");
+        for (leg, label) in legs.iter().zip(&labels) {
+            let result = rpc_client.simulate_transaction(&leg.transaction).await?.value;
+            match result.err {
+                Some(err) => println!("❌ {}: {}", label, err),
+                None => println!("✅ {}: {} tokens expected, {} CU", label, leg.quote.token_amount as f64 / 1_000_000.0, result.units_consumed.unwrap_or_default()),
+            }
+        }
+        return Ok(());
+    }
+
+    let transactions: Vec<Transaction> = legs.into_iter().map(|leg| leg.transaction).collect();
+    println!("\n🚀 Broadcasting {} legs at once", transactions.len());
+    let reports = execute_swarm(
+        rpc_client,
+        &context.confirmations,
+        untipped_broadcaster(&context.backend),
+        &plan,
+        nonce_authority.as_ref(),
+        &transactions,
+        last_valid_block_height,
+    )
+    .await;
+    print_swarm_report(&reports, &labels, &plan);
+    context.print_endpoint_stats();
+    if !reports.iter().any(LegReport::is_filled) {
+        return Err(anyhow::anyhow!("No swarm leg filled"));
+    }
+    Ok(())
+}
+
+// One batch of transfers and the source wallets co-signing it, the treasury always pays
+type TransferBatch<'a> = (&'a [Transfer], Vec<&'a Keypair>);
+
+// Every batch is simulated before any is sent, so a bad plan sends nothing
+async fn send_transfer_batches(rpc_client: &RpcClient, treasury: &Keypair, batches: &[TransferBatch<'_>], compute_unit_price: u64, dry_run: bool) -> Result<()> {
+    let recent_blockhash = rpc_client.get_latest_blockhash().await?;
+    println!("\n🧪 Simulating {} transaction(s)", batches.len());
+    for (index, (transfers, signers)) in batches.iter().enumerate() {
+        let transaction = sign_batch(&batch_instructions(transfers, compute_unit_price), treasury, signers, recent_blockhash)?;
+        let simulation = rpc_client.simulate_transaction(&transaction).await?.value;
+        if let Some(err) = simulation.err {
+            for log in simulation.logs.unwrap_or_default() {
+                println!("{}", log);
+            }
+            return Err(anyhow::anyhow!("Batch {} fails simulation: {}", index + 1, err));
+        }
+        println!("✅ Batch {}: {} transfer(s), {} CU", index + 1, transfers.len(), simulation.units_consumed.unwrap_or_default());
+    }
+    if dry_run {
+        println!("\n🧪 Dry run, nothing was sent");
+        return Ok(());
+    }
+
+    println!("\n🚀 Sending {} transaction(s)", batches.len());
+    for (index, (transfers, signers)) in batches.iter().enumerate() {
+        // Signed just before sending, confirming earlier batches takes a while
+        let recent_blockhash = rpc_client.get_latest_blockhash().await?;
+        let transaction = sign_batch(&batch_instructions(transfers, compute_unit_price), treasury, signers, recent_blockhash)?;
+        match rpc_client.send_and_confirm_transaction(&transaction).await {
+            Ok(signature) => println!("✅ Batch {} of {}: {}", index + 1, batches.len(), signature),
+            Err(e) => {
+                println!("❌ Batch {} failed: {}", index + 1, e);
+                return Err(anyhow::anyhow!("{} of {} batches sent, run the command again to finish", index, batches.len()));
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn execute_fund(target: u64, options: PoolTradeOptions, dry_run: bool) -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set");
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let registry = WalletRegistry::load(&WalletRegistry::path_from_env())?;
+    let entries = registry.select(&options.wallets)?;
+    // Funded wallets only receive, their keys stay locked
+    let pubkeys = entries.iter().map(|entry| entry.source.pubkey()).collect::<Result<Vec<_>>>()?;
+    println!("🔓 Unlocking the treasury");
+    let treasury = treasury_source_from_env()?.load()?;
+
+    let balances: Vec<(Pubkey, u64)> = pubkeys.iter().copied().zip(fetch_balances(&rpc_client, &pubkeys).await?).collect();
+    let transfers = plan_fund(&treasury.pubkey(), &balances, target)?;
+    println!("\n💸 Funding to {} per wallet from treasury {}", format_sol_amount(target), treasury.pubkey());
+    for (entry, (wallet, balance)) in entries.iter().zip(&balances) {
+        match transfers.iter().find(|transfer| transfer.to == *wallet) {
+            Some(transfer) => println!("➕ {} ({}): {} + {}", entry.label, wallet, format_sol_amount(*balance), format_sol_amount(transfer.lamports)),
+            None => println!("➖ {} ({}): {}, nothing to add", entry.label, wallet, format_sol_amount(*balance)),
+        }
+    }
+    if transfers.is_empty() {
+        println!("\n✅ Every wallet is at or above the target");
+        return Ok(());
+    }
+
+    let compute_unit_price = plan_priority_fee(&rpc_client, options.priority_fee, &[treasury.pubkey()]).await?.base_price;
+    let batches: Vec<TransferBatch> = transfers.chunks(FUND_TRANSFERS_PER_TRANSACTION).map(|chunk| (chunk, vec![])).collect();
+    let fees: u64 = batches.iter().map(|(chunk, _)| batch_fee(chunk.len(), 1, compute_unit_price)).sum();
+    let total: u64 = transfers.iter().map(|transfer| transfer.lamports).sum();
+    println!("💰 Total: {} in {} transaction(s), fees up to {}", format_sol_amount(total), batches.len(), format_sol_amount(fees));
+
+    // The treasury itself has to stay rent exempt
+    let available = rpc_client.get_balance(&treasury.pubkey()).await?;
+    let required = total + fees + RENT_EXEMPT_MINIMUM_LAMPORTS;
+    println!("🏦 Treasury balance: {}", format_sol_amount(available));
+    if available < required {
+        return Err(TreasuryError::InsufficientFunds { available, required }.into());
+    }
+    send_transfer_batches(&rpc_client, &treasury, &batches, compute_unit_price, dry_run).await
+}
+
+async fn execute_sweep(floor: u64, sell_mints: Vec<String>, options: PoolTradeOptions, dry_run: bool) -> Result<()> {
+    dotenv::dotenv().ok();
+
+    let rpc_url = std::env::var("RPC_URL").expect("RPC_URL must be set");
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let sell_mints = sell_mints.iter().map(|mint| Pubkey::from_str(mint)).collect::<Result<Vec<_>, _>>()?;
+    let registry = WalletRegistry::load(&WalletRegistry::path_from_env())?;
+    // Every swept wallet co-signs its transfer
+    let wallets = load_pool_wallets(&registry.select(&options.wallets)?)?;
+    let pubkeys: Vec<Pubkey> = wallets.iter().map(|wallet| wallet.keypair.pubkey()).collect();
+    println!("🔓 Unlocking the treasury");
+    let treasury = treasury_source_from_env()?.load()?;
+
+    if !sell_mints.is_empty() {
+        let context = TradeContext::connect().await?;
+        let trade_options = pool_trade_options(&options, dry_run);
+        let mut results = vec![];
+        for token_mint in &sell_mints {
+            let holdings = fetch_token_holdings(&rpc_client, &pubkeys, token_mint).await?;
+            for (wallet, holding) in wallets.iter().zip(holdings) {
+                if holding == 0 {
+                    continue;
+                }
+                println!("\n━━━━━━━━━━ 🏷️ {} ━━━━━━━━━━", wallet.label);
+                let result = sell_with_wallet(&context, &wallet.keypair, token_mint, "100%", &trade_options).await;
+                results.push((format!("{} {}", wallet.label, token_mint), wallet.keypair.pubkey(), result));
+            }
+        }
+        context.print_endpoint_stats();
+        // Sweeping after a failed sell would leave tokens behind without SOL to sell them
+        print_pool_summary(&results)?;
+        if dry_run {
+            println!("⚠️ The sells were only simulated, the sweep below uses the balances before them");
+        }
+    }
+
+    let balances: Vec<(Pubkey, u64)> = pubkeys.iter().copied().zip(fetch_balances(&rpc_client, &pubkeys).await?).collect();
+    let transfers = plan_sweep(&treasury.pubkey(), &balances, floor)?;
+    println!("\n🧹 Sweeping everything above {} to treasury {}", format_sol_amount(floor), treasury.pubkey());
+    for (wallet, (pubkey, balance)) in wallets.iter().zip(&balances) {
+        match transfers.iter().find(|transfer| transfer.from == *pubkey) {
+            Some(transfer) => println!("↩️ {} ({}): {} - {}", wallet.label, pubkey, format_sol_amount(*balance), format_sol_amount(transfer.lamports)),
+            None => println!("➖ {} ({}): {}, nothing to sweep", wallet.label, pubkey, format_sol_amount(*balance)),
+        }
+    }
+    if transfers.is_empty() {
+        println!("\n✅ No wallet holds more than the floor");
+        return Ok(());
+    }
+
+    let compute_unit_price = plan_priority_fee(&rpc_client, options.priority_fee, &[treasury.pubkey()]).await?.base_price;
+    let batches: Vec<TransferBatch> = transfers
+        .chunks(SWEEP_TRANSFERS_PER_TRANSACTION)
+        .map(|chunk| {
+            let signers = chunk
+                .iter()
+                .filter_map(|transfer| wallets.iter().find(|wallet| wallet.keypair.pubkey() == transfer.from))
+                .map(|wallet| &wallet.keypair)
+                .collect();
+            (chunk, signers)
+        })
+        .collect();
+    let fees: u64 = batches.iter().map(|(chunk, _)| batch_fee(chunk.len(), chunk.len() + 1, compute_unit_price)).sum();
+    let total: u64 = transfers.iter().map(|transfer| transfer.lamports).sum();
+    println!("💰 Total: {} in {} transaction(s), fees up to {} paid by the treasury", format_sol_amount(total), batches.len(), format_sol_amount(fees));
+
+    let available = rpc_client.get_balance(&treasury.pubkey()).await?;
+    if available < fees {
+        return Err(TreasuryError::InsufficientFunds { available, required: fees }.into());
+    }
+    send_transfer_batches(&rpc_client, &treasury, &batches, compute_unit_price, dry_run).await
+}
@@ -1,10 +1,16 @@
-use clap::{Parser, Subcommand};
+use {
+    clap::{Args, Parser, Subcommand},
+    std::path::PathBuf,
+};
 
 #[derive(Parser)]
 #[command(author, version, about = "A Solana bot for interacting with pump.fun tokens", long_about = None)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+    /// Simulate the exact transaction instead of sending it
+    #[arg(long, global = true, help = "Build and simulate the transaction, print the decoded result and send nothing")]
+    pub dry_run: bool,
 }
 
 #[derive(Subcommand)]
@@ -14,36 +20,335 @@ pub enum Commands {
         /// Token mint address (e.g., 8LbkTskkCx212Tm2LCuAeThZDVBQsxk8hKqkUxhspump)
         #[arg(help = "The pump.fun token address to buy")]
         token_address: String,
-        /// Creator address of the token
-        #[arg(help = "The creator address of the token")]
-        creator_address: String,
-        /// Creator vault address 
-        #[arg(help = "The creator vault address (fee recipient)")]
-        creator_vault_address: String,
+        /// Creator address of the token (default: read from the bonding curve)
+        #[arg(long = "creator", help = "Override the creator address read from the bonding curve")]
+        creator_address: Option<String>,
+        /// Creator vault address (default: derived from the creator)
+        #[arg(long = "creator-vault", help = "Override the creator vault derived from the creator")]
+        creator_vault_address: Option<String>,
         /// Amount in lamports (1 SOL = 1,000,000,000 lamports)
         #[arg(help = "Amount of SOL to spend in lamports (e.g., 16837852 for 0.016837852 SOL)")]
         amount: u64,
-        /// Priority fee in lamports per compute unit (default: 2)
-        #[arg(short, long, help = "Priority fee in lamports per compute unit")]
+        /// Starting priority fee in micro-lamports per compute unit (default: estimated)
+        #[arg(short, long, help = "Starting priority fee in micro-lamports per compute unit")]
         priority_fee: Option<u64>,
+        /// Slippage tolerance in basis points (default: 100 = 1%)
+        #[arg(long, value_parser = clap::value_parser!(u64).range(0..=10_000), help = "Maximum extra SOL to pay over the quote, in basis points")]
+        slippage_bps: Option<u64>,
+        /// Durable nonce account to sign against instead of a recent blockhash
+        #[arg(long, help = "Sign against this durable nonce account (the wallet must be its authority)")]
+        nonce_account: Option<String>,
     },
     /// Sell tokens for SOL
     Sell {
         /// Token mint address (e.g., 8LbkTskkCx212Tm2LCuAeThZDVBQsxk8hKqkUxhspump)
         #[arg(help = "The pump.fun token address to sell")]
         token_address: String,
-        /// Creator address of the token
-        #[arg(help = "The creator address of the token")]
-        creator_address: String,
-        /// Creator vault address 
-        #[arg(help = "The creator vault address (fee recipient)")]
-        creator_vault_address: String,
+        /// Creator address of the token (default: read from the bonding curve)
+        #[arg(long = "creator", help = "Override the creator address read from the bonding curve")]
+        creator_address: Option<String>,
+        /// Creator vault address (default: derived from the creator)
+        #[arg(long = "creator-vault", help = "Override the creator vault derived from the creator")]
+        creator_vault_address: Option<String>,
         /// Amount to sell (e.g., "50%" for half of balance, or "31000" for 31,000 tokens)
         #[arg(help = "Amount to sell: either a percentage of your balance (e.g., '50%') or a specific number of tokens (e.g., '31000' for 31,000 tokens)")]
         amount: String,
-        /// Priority fee in lamports per compute unit (default: 1.65)
-        #[arg(short, long, help = "Priority fee in lamports per compute unit")]
+        /// Starting priority fee in micro-lamports per compute unit (default: estimated)
+        #[arg(short, long, help = "Starting priority fee in micro-lamports per compute unit")]
         priority_fee: Option<u64>,
+        /// Slippage tolerance in basis points (default: 100 = 1%)
+        #[arg(long, value_parser = clap::value_parser!(u64).range(0..=10_000), help = "Maximum SOL shortfall from the quote to accept, in basis points")]
+        slippage_bps: Option<u64>,
+        /// Durable nonce account to sign against instead of a recent blockhash
+        #[arg(long, help = "Sign against this durable nonce account (the wallet must be its authority)")]
+        nonce_account: Option<String>,
+    },
+    /// Quote buys and sells against the bonding curve without trading
+    Quote {
+        /// Token mint address (e.g., 8LbkTskkCx212Tm2LCuAeThZDVBQsxk8hKqkUxhspump)
+        #[arg(help = "The pump.fun token address to quote")]
+        token_address: String,
+        /// Wallet whose token holding sizes the sell quotes (no keypair needed)
+        #[arg(long, help = "Wallet address whose token balance sizes the sell quotes")]
+        wallet: Option<String>,
+    },
+    /// Create and inspect durable nonce accounts
+    Nonce {
+        #[command(subcommand)]
+        command: NonceCommands,
+    },
+    /// Sign a sell against a durable nonce now and save it to fire later. A fired sell that fails,
+    /// e.g. on slippage, still advances the nonce and uses the pre-signed transaction up
+    PresignSell {
+        /// Token mint address (e.g., 8LbkTskkCx212Tm2LCuAeThZDVBQsxk8hKqkUxhspump)
+        #[arg(help = "The pump.fun token address to sell")]
+        token_address: String,
+        /// Amount to sell, as for `sell`
+        #[arg(help = "Amount to sell: either a percentage of your balance (e.g., '50%') or a specific number of tokens")]
+        amount: String,
+        /// Durable nonce account the sell is signed against
+        #[arg(long, help = "Durable nonce account to sign against (the wallet must be its authority)")]
+        nonce_account: String,
+        /// Priority fee in micro-lamports per compute unit (default: estimated now)
+        #[arg(short, long, help = "Priority fee in micro-lamports per compute unit")]
+        priority_fee: Option<u64>,
+        /// Slippage tolerance in basis points, required: the sell fires after the price has moved
+        #[arg(long, value_parser = clap::value_parser!(u64).range(0..=10_000), help = "Maximum SOL shortfall from the current quote to accept when fired, in basis points (e.g. 5000 for an exit at half the price)")]
+        slippage_bps: u64,
+        /// File the signed transaction is written to
+        #[arg(long, default_value = "presigned-sell.json", help = "File to write the pre-signed transaction to")]
+        out: PathBuf,
+    },
+    /// Send a pre-signed transaction as is
+    Fire {
+        /// File written by `presign-sell`
+        #[arg(help = "Pre-signed transaction file")]
+        file: PathBuf,
+    },
+    /// Build an unsigned buy or sell for offline signing, no private key needed
+    Build {
+        #[command(subcommand)]
+        trade: BuildCommands,
+    },
+    /// Sign a transaction written by `build` with the wallet, no RPC access needed
+    Sign {
+        /// File written by `build`
+        #[arg(help = "Unsigned transaction file")]
+        file: PathBuf,
+        /// File the signed transaction is written to (default: overwrite the input)
+        #[arg(long, help = "File to write the signed transaction to, defaults to the input file")]
+        out: Option<PathBuf>,
+    },
+    /// Send a transaction signed by `sign`
+    Broadcast {
+        /// File written by `sign`
+        #[arg(help = "Signed transaction file")]
+        file: PathBuf,
+    },
+    /// Manage the address lookup table trades are compiled against
+    LookupTable {
+        #[command(subcommand)]
+        command: LookupTableCommands,
+    },
+    /// Manage encrypted wallet keystores
+    Wallet {
+        #[command(subcommand)]
+        command: WalletCommands,
+    },
+    /// Trade from the labeled wallets in the wallet pool (`WALLET_POOL_FILE`)
+    Pool {
+        #[command(subcommand)]
+        command: PoolCommands,
+    },
+    /// Buy the same token from many pool wallets at once, with a budget that holds however many land
+    Swarm {
+        #[arg(help = "The pump.fun token address to buy")]
+        token_address: String,
+        #[arg(help = "Most SOL all legs together may spend, in lamports")]
+        budget: u64,
+        /// At most this many legs fill (default: one per nonce account, or every wallet)
+        #[arg(long, help = "Most legs that may fill, fewer than the wallets needs as many --nonce-account")]
+        max_fills: Option<usize>,
+        /// Nonce accounts shared by groups of legs, at most one leg per nonce can land
+        #[arg(long = "nonce-account", help = "Durable nonce account shared by one group of legs (repeatable)")]
+        nonce_accounts: Vec<String>,
+        #[command(flatten)]
+        options: PoolTradeOptions,
+    },
+    /// Top pool wallets up from the treasury to a target balance, many transfers per transaction
+    Fund {
+        #[arg(help = "Balance every selected wallet is topped up to, in lamports")]
+        target: u64,
+        #[command(flatten)]
+        options: PoolTradeOptions,
+    },
+    /// Return SOL above a floor from pool wallets to the treasury, optionally selling tokens first
+    Sweep {
+        /// Balance left in every wallet (default: 0, emptying it)
+        #[arg(long, default_value_t = 0, help = "Balance left in every wallet, in lamports")]
+        floor: u64,
+        /// Tokens sold in full from every wallet holding them before the sweep
+        #[arg(long = "sell", help = "Sell the whole holding of this token first (repeatable)")]
+        sell_mints: Vec<String>,
+        #[command(flatten)]
+        options: PoolTradeOptions,
+    },
+    /// Hold the wallet key and sign policy checked trades for other processes over a Unix socket
+    SignerDaemon {
+        /// Socket to listen on (default: SIGNER_SOCKET or pumpfun-signer.sock)
+        #[arg(long, help = "Unix socket the trading process connects to")]
+        socket: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PoolCommands {
+    /// Print every pool wallet with its weight and balance
+    List {
+        /// Also print each wallet's holding of this token
+        #[arg(long, help = "Token mint whose holdings to print")]
+        mint: Option<String>,
+    },
+    /// Add a wallet to the pool
+    Add {
+        #[arg(help = "Unique label of the wallet")]
+        label: String,
+        #[command(flatten)]
+        source: PoolWalletSource,
+        /// Share of pool buys relative to the other wallets
+        #[arg(long, default_value_t = 1, help = "Weight of the wallet when splitting buys, 0 keeps it out of buys")]
+        weight: u64,
+    },
+    /// Remove a wallet from the pool, its key material is left untouched
+    Remove {
+        #[arg(help = "Label of the wallet")]
+        label: String,
+    },
+    /// Split a buy across the pool wallets by weight
+    Buy {
+        #[arg(help = "The pump.fun token address to buy")]
+        token_address: String,
+        #[arg(help = "Total amount of SOL to spend across the wallets, in lamports")]
+        amount: u64,
+        #[command(flatten)]
+        options: PoolTradeOptions,
+    },
+    /// Sell from every pool wallet holding the token, or from the selected ones
+    Sell {
+        #[arg(help = "The pump.fun token address to sell")]
+        token_address: String,
+        #[arg(help = "Amount each wallet sells: a percentage of its balance (e.g., '50%') or a number of tokens")]
+        amount: String,
+        #[command(flatten)]
+        options: PoolTradeOptions,
+    },
+}
+
+/// Where the key of a new pool wallet lives, exactly one is required
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+pub struct PoolWalletSource {
+    #[arg(long, help = "Encrypted keystore written by `wallet import`")]
+    pub keystore: Option<PathBuf>,
+    #[arg(long, help = "Solana CLI JSON keypair file")]
+    pub keypair_file: Option<PathBuf>,
+    #[arg(long, help = "Environment variable holding a base58 secret key")]
+    pub env: Option<String>,
+}
+
+/// Options shared by the commands that act on pool wallets
+#[derive(Args)]
+pub struct PoolTradeOptions {
+    /// Wallets to trade from (default: all)
+    #[arg(long = "wallet", help = "Label of a pool wallet to trade from (repeatable, default: all)")]
+    pub wallets: Vec<String>,
+    /// Starting priority fee in micro-lamports per compute unit (default: estimated)
+    #[arg(short, long, help = "Starting priority fee in micro-lamports per compute unit")]
+    pub priority_fee: Option<u64>,
+    /// Slippage tolerance in basis points (default: 100 = 1%)
+    #[arg(long, value_parser = clap::value_parser!(u64).range(0..=10_000), help = "Slippage tolerance against the current quote, in basis points")]
+    pub slippage_bps: Option<u64>,
+}
+
+#[derive(Subcommand)]
+pub enum WalletCommands {
+    /// Encrypt a keypair into a password protected keystore
+    Import {
+        #[arg(help = "Keystore file to write")]
+        keystore: PathBuf,
+        /// Solana CLI keypair file to import (default: PRIVATE_KEY or a prompt)
+        #[arg(long, help = "Solana CLI JSON keypair file to import")]
+        keypair_file: Option<PathBuf>,
+    },
+    /// Print the public key of the configured wallet, no password needed for keystores
+    ExportPublic,
+    /// Replace the keypair in a keystore with a new one, keeping the old file as a backup
+    Rotate {
+        #[arg(help = "Keystore file to rotate")]
+        keystore: PathBuf,
+        /// Keep the keypair and only change the password
+        #[arg(long, help = "Only re-encrypt the existing keypair under a new password")]
+        password_only: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum LookupTableCommands {
+    /// Create a lookup table holding the static pump accounts and the wallets
+    Create {
+        /// Extra wallets to add besides the configured one
+        #[arg(long = "wallet", help = "Additional wallet address to store in the table (repeatable)")]
+        wallets: Vec<String>,
+    },
+    /// Add the static pump accounts and wallets missing from an existing table
+    Extend {
+        #[arg(help = "Lookup table address")]
+        address: String,
+        /// Extra wallets to add besides the configured one
+        #[arg(long = "wallet", help = "Additional wallet address to store in the table (repeatable)")]
+        wallets: Vec<String>,
+    },
+    /// Print the addresses a lookup table holds
+    Show {
+        #[arg(help = "Lookup table address")]
+        address: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BuildCommands {
+    /// Build a buy paying `amount` lamports
+    Buy {
+        #[arg(help = "The pump.fun token address to buy")]
+        token_address: String,
+        #[arg(help = "Amount of SOL to spend in lamports")]
+        amount: u64,
+        #[command(flatten)]
+        options: BuildOptions,
+    },
+    /// Build a sell of the payer's tokens
+    Sell {
+        #[arg(help = "The pump.fun token address to sell")]
+        token_address: String,
+        #[arg(help = "Amount to sell: either a percentage of the payer's balance (e.g., '50%') or a specific number of tokens")]
+        amount: String,
+        #[command(flatten)]
+        options: BuildOptions,
+    },
+}
+
+/// Options shared by `build buy` and `build sell`
+#[derive(Args)]
+pub struct BuildOptions {
+    /// Wallet that will sign and pay, only its public key is needed here
+    #[arg(long, help = "Public key of the wallet that will sign the transaction")]
+    pub payer: String,
+    /// Priority fee in micro-lamports per compute unit (default: estimated now)
+    #[arg(short, long, help = "Priority fee in micro-lamports per compute unit")]
+    pub priority_fee: Option<u64>,
+    /// Slippage tolerance in basis points (default: 100 = 1%)
+    #[arg(long, value_parser = clap::value_parser!(u64).range(0..=10_000), help = "Slippage tolerance against the current quote, in basis points")]
+    pub slippage_bps: Option<u64>,
+    /// Durable nonce account, so signing is not limited by blockhash expiry
+    #[arg(long, help = "Build against this durable nonce account (the payer must be its authority)")]
+    pub nonce_account: Option<String>,
+    /// File the unsigned transaction is written to
+    #[arg(long, default_value = "unsigned.json", help = "File to write the unsigned transaction to")]
+    pub out: PathBuf,
+}
+
+#[derive(Subcommand)]
+pub enum NonceCommands {
+    /// Create a nonce account funded by the wallet
+    Create {
+        /// Nonce authority (default: the wallet)
+        #[arg(long, help = "Authority allowed to advance the nonce, defaults to the wallet")]
+        authority: Option<String>,
+    },
+    /// Print a nonce account's authority and current nonce
+    Show {
+        #[arg(help = "Nonce account address")]
+        address: String,
     },
 }
 
@@ -54,81 +359,359 @@ mod tests {
     #[test]
     fn test_basic_cli_parsing() {
         // Test buy command
-        let args = vec!["pumpfun-bot", "buy", "token123", "creator123", "vault123", "1000000"];
+        let args = vec!["pumpfun-bot", "buy", "token123", "1000000"];
         let cli = Cli::parse_from(args);
         match cli.command {
-            Commands::Buy { token_address, creator_address, creator_vault_address, amount, priority_fee } => {
+            Commands::Buy { token_address, creator_address, creator_vault_address, amount, priority_fee, slippage_bps, nonce_account } => {
                 assert_eq!(token_address, "token123");
-                assert_eq!(creator_address, "creator123");
-                assert_eq!(creator_vault_address, "vault123");
+                assert_eq!(creator_address, None);
+                assert_eq!(creator_vault_address, None);
                 assert_eq!(amount, 1000000);
                 assert_eq!(priority_fee, None);
+                assert_eq!(slippage_bps, None);
+                assert_eq!(nonce_account, None);
             }
             _ => panic!("Expected Buy command"),
         }
 
-        // Test buy command with priority fee
-        let args = vec!["pumpfun-bot", "buy", "token123", "creator123", "vault123", "1000000", "--priority-fee", "3"];
+        // Test buy command with priority fee and creator overrides
+        let args = vec!["pumpfun-bot", "buy", "token123", "1000000", "--priority-fee", "3", "--creator", "creator123", "--creator-vault", "vault123"];
         let cli = Cli::parse_from(args);
         match cli.command {
-            Commands::Buy { token_address, creator_address, creator_vault_address, amount, priority_fee } => {
+            Commands::Buy { token_address, creator_address, creator_vault_address, amount, priority_fee, slippage_bps, nonce_account } => {
                 assert_eq!(token_address, "token123");
-                assert_eq!(creator_address, "creator123");
-                assert_eq!(creator_vault_address, "vault123");
+                assert_eq!(creator_address.as_deref(), Some("creator123"));
+                assert_eq!(creator_vault_address.as_deref(), Some("vault123"));
                 assert_eq!(amount, 1000000);
                 assert_eq!(priority_fee, Some(3));
+                assert_eq!(slippage_bps, None);
+                assert_eq!(nonce_account, None);
             }
             _ => panic!("Expected Buy command"),
         }
 
         // Test sell command with percentage
-        let args = vec!["pumpfun-bot", "sell", "token123", "creator123", "vault123", "50%"];
+        let args = vec!["pumpfun-bot", "sell", "token123", "50%"];
         let cli = Cli::parse_from(args);
         match cli.command {
-            Commands::Sell { token_address, creator_address, creator_vault_address, amount, priority_fee } => {
+            Commands::Sell { token_address, creator_address, creator_vault_address, amount, priority_fee, slippage_bps, nonce_account } => {
                 assert_eq!(token_address, "token123");
-                assert_eq!(creator_address, "creator123");
-                assert_eq!(creator_vault_address, "vault123");
+                assert_eq!(creator_address, None);
+                assert_eq!(creator_vault_address, None);
                 assert_eq!(amount, "50%");
                 assert_eq!(priority_fee, None);
+                assert_eq!(slippage_bps, None);
+                assert_eq!(nonce_account, None);
             }
             _ => panic!("Expected Sell command"),
         }
 
-        // Test sell command with specific token amount
-        let args = vec!["pumpfun-bot", "sell", "token123", "creator123", "vault123", "30000"];
+        // Test sell command with specific token amount and creator overrides
+        let args = vec!["pumpfun-bot", "sell", "token123", "30000", "--creator", "creator123", "--creator-vault", "vault123"];
         let cli = Cli::parse_from(args);
         match cli.command {
-            Commands::Sell { token_address, creator_address, creator_vault_address, amount, priority_fee } => {
+            Commands::Sell { token_address, creator_address, creator_vault_address, amount, priority_fee, slippage_bps, nonce_account } => {
                 assert_eq!(token_address, "token123");
-                assert_eq!(creator_address, "creator123");
-                assert_eq!(creator_vault_address, "vault123");
+                assert_eq!(creator_address.as_deref(), Some("creator123"));
+                assert_eq!(creator_vault_address.as_deref(), Some("vault123"));
                 assert_eq!(amount, "30000");
                 assert_eq!(priority_fee, None);
+                assert_eq!(slippage_bps, None);
+                assert_eq!(nonce_account, None);
             }
             _ => panic!("Expected Sell command"),
         }
 
         // Test sell command with priority fee
-        let args = vec!["pumpfun-bot", "sell", "token123", "creator123", "vault123", "30000", "--priority-fee", "3"];
+        let args = vec!["pumpfun-bot", "sell", "token123", "30000", "--priority-fee", "3", "--creator", "creator123", "--creator-vault", "vault123"];
         let cli = Cli::parse_from(args);
         match cli.command {
-            Commands::Sell { token_address, creator_address, creator_vault_address, amount, priority_fee } => {
+            Commands::Sell { token_address, creator_address, creator_vault_address, amount, priority_fee, slippage_bps, nonce_account } => {
                 assert_eq!(token_address, "token123");
-                assert_eq!(creator_address, "creator123");
-                assert_eq!(creator_vault_address, "vault123");
+                assert_eq!(creator_address.as_deref(), Some("creator123"));
+                assert_eq!(creator_vault_address.as_deref(), Some("vault123"));
                 assert_eq!(amount, "30000");
                 assert_eq!(priority_fee, Some(3));
+                assert_eq!(slippage_bps, None);
+                assert_eq!(nonce_account, None);
             }
             _ => panic!("Expected Sell command"),
         }
     }
 
+    #[test]
+    fn test_slippage_bps_parsing() {
+        let args = vec!["pumpfun-bot", "buy", "token123", "1000000", "--slippage-bps", "250"];
+        match Cli::parse_from(args).command {
+            Commands::Buy { slippage_bps, .. } => assert_eq!(slippage_bps, Some(250)),
+            _ => panic!("Expected Buy command"),
+        }
+
+        let args = vec!["pumpfun-bot", "sell", "token123", "50%", "--slippage-bps", "50"];
+        match Cli::parse_from(args).command {
+            Commands::Sell { slippage_bps, .. } => assert_eq!(slippage_bps, Some(50)),
+            _ => panic!("Expected Sell command"),
+        }
+
+        // More than 100% is rejected
+        let args = vec!["pumpfun-bot", "sell", "token123", "50%", "--slippage-bps", "10001"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_quote_parsing() {
+        let args = vec!["pumpfun-bot", "quote", "token123"];
+        match Cli::parse_from(args).command {
+            Commands::Quote { token_address, wallet } => {
+                assert_eq!(token_address, "token123");
+                assert_eq!(wallet, None);
+            }
+            _ => panic!("Expected Quote command"),
+        }
+
+        let args = vec!["pumpfun-bot", "quote", "token123", "--wallet", "wallet123"];
+        match Cli::parse_from(args).command {
+            Commands::Quote { wallet, .. } => assert_eq!(wallet.as_deref(), Some("wallet123")),
+            _ => panic!("Expected Quote command"),
+        }
+    }
+
+    #[test]
+    fn test_nonce_parsing() {
+        let args = vec!["pumpfun-bot", "sell", "token123", "50%", "--nonce-account", "nonce123"];
+        match Cli::parse_from(args).command {
+            Commands::Sell { nonce_account, .. } => assert_eq!(nonce_account.as_deref(), Some("nonce123")),
+            _ => panic!("Expected Sell command"),
+        }
+
+        let args = vec!["pumpfun-bot", "nonce", "create"];
+        match Cli::parse_from(args).command {
+            Commands::Nonce { command: NonceCommands::Create { authority } } => assert_eq!(authority, None),
+            _ => panic!("Expected nonce create command"),
+        }
+
+        let args = vec!["pumpfun-bot", "nonce", "show", "nonce123"];
+        match Cli::parse_from(args).command {
+            Commands::Nonce { command: NonceCommands::Show { address } } => assert_eq!(address, "nonce123"),
+            _ => panic!("Expected nonce show command"),
+        }
+
+        let args = vec!["pumpfun-bot", "presign-sell", "token123", "100%", "--nonce-account", "nonce123", "--slippage-bps", "5000"];
+        match Cli::parse_from(args).command {
+            Commands::PresignSell { amount, nonce_account, slippage_bps, out, .. } => {
+                assert_eq!(amount, "100%");
+                assert_eq!(nonce_account, "nonce123");
+                assert_eq!(slippage_bps, 5_000);
+                assert_eq!(out, PathBuf::from("presigned-sell.json"));
+            }
+            _ => panic!("Expected presign-sell command"),
+        }
+
+        // The nonce account and an explicit slippage tolerance are required to presign
+        assert!(Cli::try_parse_from(vec!["pumpfun-bot", "presign-sell", "token123", "100%", "--slippage-bps", "5000"]).is_err());
+        assert!(Cli::try_parse_from(vec!["pumpfun-bot", "presign-sell", "token123", "100%", "--nonce-account", "nonce123"]).is_err());
+
+        let args = vec!["pumpfun-bot", "fire", "exit.json"];
+        match Cli::parse_from(args).command {
+            Commands::Fire { file } => assert_eq!(file, PathBuf::from("exit.json")),
+            _ => panic!("Expected fire command"),
+        }
+    }
+
+    #[test]
+    fn test_offline_signing_parsing() {
+        let args = vec!["pumpfun-bot", "build", "buy", "token123", "1000000", "--payer", "payer123"];
+        match Cli::parse_from(args).command {
+            Commands::Build { trade: BuildCommands::Buy { token_address, amount, options } } => {
+                assert_eq!(token_address, "token123");
+                assert_eq!(amount, 1000000);
+                assert_eq!(options.payer, "payer123");
+                assert_eq!(options.nonce_account, None);
+                assert_eq!(options.out, PathBuf::from("unsigned.json"));
+            }
+            _ => panic!("Expected build buy command"),
+        }
+
+        let args = vec!["pumpfun-bot", "build", "sell", "token123", "50%", "--payer", "payer123", "--nonce-account", "nonce123", "--out", "exit.json"];
+        match Cli::parse_from(args).command {
+            Commands::Build { trade: BuildCommands::Sell { amount, options, .. } } => {
+                assert_eq!(amount, "50%");
+                assert_eq!(options.nonce_account.as_deref(), Some("nonce123"));
+                assert_eq!(options.out, PathBuf::from("exit.json"));
+            }
+            _ => panic!("Expected build sell command"),
+        }
+
+        // The payer is required, there is no wallet to read it from
+        assert!(Cli::try_parse_from(vec!["pumpfun-bot", "build", "buy", "token123", "1000000"]).is_err());
+
+        let args = vec!["pumpfun-bot", "sign", "unsigned.json", "--out", "signed.json"];
+        match Cli::parse_from(args).command {
+            Commands::Sign { file, out } => {
+                assert_eq!(file, PathBuf::from("unsigned.json"));
+                assert_eq!(out, Some(PathBuf::from("signed.json")));
+            }
+            _ => panic!("Expected sign command"),
+        }
+
+        let args = vec!["pumpfun-bot", "broadcast", "signed.json"];
+        match Cli::parse_from(args).command {
+            Commands::Broadcast { file } => assert_eq!(file, PathBuf::from("signed.json")),
+            _ => panic!("Expected broadcast command"),
+        }
+    }
+
+    #[test]
+    fn test_lookup_table_parsing() {
+        let args = vec!["pumpfun-bot", "lookup-table", "create", "--wallet", "wallet1", "--wallet", "wallet2"];
+        match Cli::parse_from(args).command {
+            Commands::LookupTable { command: LookupTableCommands::Create { wallets } } => assert_eq!(wallets, vec!["wallet1", "wallet2"]),
+            _ => panic!("Expected lookup-table create command"),
+        }
+
+        let args = vec!["pumpfun-bot", "lookup-table", "extend", "table123"];
+        match Cli::parse_from(args).command {
+            Commands::LookupTable { command: LookupTableCommands::Extend { address, wallets } } => {
+                assert_eq!(address, "table123");
+                assert!(wallets.is_empty());
+            }
+            _ => panic!("Expected lookup-table extend command"),
+        }
+
+        let args = vec!["pumpfun-bot", "lookup-table", "show", "table123"];
+        match Cli::parse_from(args).command {
+            Commands::LookupTable { command: LookupTableCommands::Show { address } } => assert_eq!(address, "table123"),
+            _ => panic!("Expected lookup-table show command"),
+        }
+    }
+
+    #[test]
+    fn test_wallet_parsing() {
+        let args = vec!["pumpfun-bot", "wallet", "import", "wallet.json", "--keypair-file", "id.json"];
+        match Cli::parse_from(args).command {
+            Commands::Wallet { command: WalletCommands::Import { keystore, keypair_file } } => {
+                assert_eq!(keystore, PathBuf::from("wallet.json"));
+                assert_eq!(keypair_file, Some(PathBuf::from("id.json")));
+            }
+            _ => panic!("Expected wallet import command"),
+        }
+
+        let args = vec!["pumpfun-bot", "wallet", "export-public"];
+        assert!(matches!(Cli::parse_from(args).command, Commands::Wallet { command: WalletCommands::ExportPublic }));
+
+        let args = vec!["pumpfun-bot", "wallet", "rotate", "wallet.json", "--password-only"];
+        match Cli::parse_from(args).command {
+            Commands::Wallet { command: WalletCommands::Rotate { keystore, password_only } } => {
+                assert_eq!(keystore, PathBuf::from("wallet.json"));
+                assert!(password_only);
+            }
+            _ => panic!("Expected wallet rotate command"),
+        }
+    }
+
+    #[test]
+    fn test_pool_parsing() {
+        let args = vec!["pumpfun-bot", "pool", "add", "hot-1", "--keystore", "hot-1.json", "--weight", "3"];
+        match Cli::parse_from(args).command {
+            Commands::Pool { command: PoolCommands::Add { label, source, weight } } => {
+                assert_eq!(label, "hot-1");
+                assert_eq!(source.keystore, Some(PathBuf::from("hot-1.json")));
+                assert_eq!(weight, 3);
+            }
+            _ => panic!("Expected pool add command"),
+        }
+
+        // Exactly one key source
+        assert!(Cli::try_parse_from(vec!["pumpfun-bot", "pool", "add", "hot-1"]).is_err());
+        assert!(Cli::try_parse_from(vec!["pumpfun-bot", "pool", "add", "hot-1", "--env", "KEY", "--keystore", "k.json"]).is_err());
+
+        let args = vec!["pumpfun-bot", "pool", "buy", "token123", "1000000", "--wallet", "hot-1", "--wallet", "hot-2"];
+        match Cli::parse_from(args).command {
+            Commands::Pool { command: PoolCommands::Buy { amount, options, .. } } => {
+                assert_eq!(amount, 1_000_000);
+                assert_eq!(options.wallets, vec!["hot-1", "hot-2"]);
+            }
+            _ => panic!("Expected pool buy command"),
+        }
+
+        let args = vec!["pumpfun-bot", "pool", "sell", "token123", "100%", "--dry-run"];
+        let cli = Cli::parse_from(args);
+        assert!(cli.dry_run);
+        match cli.command {
+            Commands::Pool { command: PoolCommands::Sell { amount, options, .. } } => {
+                assert_eq!(amount, "100%");
+                assert!(options.wallets.is_empty());
+            }
+            _ => panic!("Expected pool sell command"),
+        }
+    }
+
+    #[test]
+    fn test_swarm_parsing() {
+        let args = vec![
+            "pumpfun-bot", "swarm", "token123", "1000000000", "--max-fills", "2",
+            "--nonce-account", "nonce1", "--nonce-account", "nonce2", "--wallet", "hot-1",
+        ];
+        match Cli::parse_from(args).command {
+            Commands::Swarm { token_address, budget, max_fills, nonce_accounts, options } => {
+                assert_eq!(token_address, "token123");
+                assert_eq!(budget, 1_000_000_000);
+                assert_eq!(max_fills, Some(2));
+                assert_eq!(nonce_accounts, vec!["nonce1", "nonce2"]);
+                assert_eq!(options.wallets, vec!["hot-1"]);
+            }
+            _ => panic!("Expected swarm command"),
+        }
+    }
+
+    #[test]
+    fn test_fund_and_sweep_parsing() {
+        let args = vec!["pumpfun-bot", "fund", "500000000", "--wallet", "hot-1", "--wallet", "hot-2"];
+        match Cli::parse_from(args).command {
+            Commands::Fund { target, options } => {
+                assert_eq!(target, 500_000_000);
+                assert_eq!(options.wallets, vec!["hot-1", "hot-2"]);
+            }
+            _ => panic!("Expected fund command"),
+        }
+
+        let args = vec!["pumpfun-bot", "sweep", "--sell", "token123", "--sell", "token456", "-p", "1000"];
+        match Cli::parse_from(args).command {
+            Commands::Sweep { floor, sell_mints, options } => {
+                assert_eq!(floor, 0);
+                assert_eq!(sell_mints, vec!["token123", "token456"]);
+                assert_eq!(options.priority_fee, Some(1_000));
+            }
+            _ => panic!("Expected sweep command"),
+        }
+    }
+
+    #[test]
+    fn test_signer_daemon_parsing() {
+        let args = vec!["pumpfun-bot", "signer-daemon", "--socket", "/run/pumpfun/signer.sock"];
+        match Cli::parse_from(args).command {
+            Commands::SignerDaemon { socket } => assert_eq!(socket, Some(PathBuf::from("/run/pumpfun/signer.sock"))),
+            _ => panic!("Expected signer-daemon command"),
+        }
+    }
+
+    #[test]
+    fn test_dry_run_flag() {
+        let args = vec!["pumpfun-bot", "buy", "token123", "1000000"];
+        assert!(!Cli::parse_from(args).dry_run);
+
+        // Global flag is accepted before or after the subcommand
+        let args = vec!["pumpfun-bot", "--dry-run", "buy", "token123", "1000000"];
+        assert!(Cli::parse_from(args).dry_run);
+        let args = vec!["pumpfun-bot", "sell", "token123", "50%", "--dry-run"];
+        assert!(Cli::parse_from(args).dry_run);
+    }
+
     #[test]
     #[should_panic(expected = "invalid value")]
     fn test_priority_fee_must_be_integer() {
         // This should fail because priority fee should be an integer
-        let args = vec!["pumpfun-bot", "buy", "token123", "creator123", "vault123", "1000000", "--priority-fee", "3.5"];
-        let _cli = Cli::parse_from(args);
+        let args = vec!["pumpfun-bot", "buy", "token123", "1000000", "--priority-fee", "3.5"];
+        let _cli = Cli::try_parse_from(args).unwrap_or_else(|err| panic!("{}", err));
     }
 } 
\ No newline at end of file
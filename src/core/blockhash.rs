@@ -0,0 +1,152 @@
+use {
+    solana_client::{client_error::ClientError, nonblocking::rpc_client::RpcClient},
+    solana_sdk::{commitment_config::CommitmentConfig, hash::Hash},
+    std::{
+        sync::{Arc, RwLock},
+        time::{Duration, Instant},
+    },
+    tokio::{task::JoinHandle, time::sleep},
+};
+
+// Blocks are ~400ms, so a blockhash refreshed every second is at most a few slots old
+pub const DEFAULT_BLOCKHASH_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+// Older cached values are treated as missing and fetched directly
+pub const DEFAULT_BLOCKHASH_MAX_AGE: Duration = Duration::from_secs(5);
+
+/// A blockhash together with the last block height at which transactions using it can land.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CachedBlockhash {
+    pub blockhash: Hash,
+    pub last_valid_block_height: u64,
+    pub fetched_at: Instant,
+}
+
+// Stops the refresh task once the last cache handle is dropped
+#[derive(Debug)]
+struct RefreshTask(JoinHandle<()>);
+
+impl Drop for RefreshTask {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Latest blockhash kept fresh by a background task, so signing never waits on an RPC round trip.
+/// Cheap to clone; all clones share the same value.
+#[derive(Debug, Clone)]
+pub struct BlockhashCache {
+    latest: Arc<RwLock<Option<CachedBlockhash>>>,
+    max_age: Duration,
+    _task: Option<Arc<RefreshTask>>,
+}
+
+impl BlockhashCache {
+    /// A cache without a refresh task, filled only by `get`.
+    pub fn new(max_age: Duration) -> Self {
+        Self { latest: Arc::default(), max_age, _task: None }
+    }
+
+    /// Starts a task that refreshes the blockhash every `refresh_interval`.
+    /// Refresh errors are ignored, readers fall back to a direct fetch once the value is stale.
+    pub fn spawn(rpc_client: Arc<RpcClient>, refresh_interval: Duration, max_age: Duration) -> Self {
+        let latest: Arc<RwLock<Option<CachedBlockhash>>> = Arc::default();
+        let task = tokio::spawn({
+            let latest = latest.clone();
+            async move {
+                loop {
+                    if let Ok(fresh) = fetch(&rpc_client).await {
+                        *latest.write().unwrap() = Some(fresh);
+                    }
+                    sleep(refresh_interval).await;
+                }
+            }
+        });
+        Self { latest, max_age, _task: Some(Arc::new(RefreshTask(task))) }
+    }
+
+    /// The cached blockhash if it is fresh enough, without waiting.
+    pub fn current(&self) -> Option<CachedBlockhash> {
+        let latest = (*self.latest.read().unwrap())?;
+        (latest.fetched_at.elapsed() <= self.max_age).then_some(latest)
+    }
+
+    /// A fresh blockhash other than `exclude`, fetched directly if the cache cannot provide one.
+    /// Pass the blockhash of the previous attempt so a retry never reuses an expired blockhash.
+    pub async fn get(&self, rpc_client: &RpcClient, exclude: Option<Hash>) -> Result<(Hash, u64), ClientError> {
+        if let Some(cached) = self.current().filter(|cached| Some(cached.blockhash) != exclude) {
+            return Ok((cached.blockhash, cached.last_valid_block_height));
+        }
+        let fresh = fetch(rpc_client).await?;
+        self.store(fresh);
+        Ok((fresh.blockhash, fresh.last_valid_block_height))
+    }
+
+    fn store(&self, fresh: CachedBlockhash) {
+        let mut latest = self.latest.write().unwrap();
+        // The background task may have stored a newer value in the meantime
+        if latest.is_none_or(|latest| latest.fetched_at <= fresh.fetched_at) {
+            *latest = Some(fresh);
+        }
+    }
+}
+
+async fn fetch(rpc_client: &RpcClient) -> Result<CachedBlockhash, ClientError> {
+    let (blockhash, last_valid_block_height) = rpc_client
+        .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+        .await?;
+    Ok(CachedBlockhash { blockhash, last_valid_block_height, fetched_at: Instant::now() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_rpc() -> RpcClient {
+        RpcClient::new_mock("succeeds".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_get_fetches_when_empty_or_stale() {
+        let rpc = mock_rpc();
+        let cache = BlockhashCache::new(Duration::from_secs(60));
+        assert_eq!(cache.current(), None);
+
+        let (blockhash, last_valid_block_height) = cache.get(&rpc, None).await.unwrap();
+        assert_eq!(last_valid_block_height, 1234);
+        assert_eq!(cache.current().map(|cached| cached.blockhash), Some(blockhash));
+
+        let stale = BlockhashCache::new(Duration::ZERO);
+        stale.get(&rpc, None).await.unwrap();
+        sleep(Duration::from_millis(5)).await;
+        assert_eq!(stale.current(), None);
+    }
+
+    #[tokio::test]
+    async fn test_get_skips_excluded_blockhash() {
+        let cache = BlockhashCache::new(Duration::from_secs(60));
+        let cached = Hash::new_unique();
+        cache.store(CachedBlockhash { blockhash: cached, last_valid_block_height: 10, fetched_at: Instant::now() });
+
+        // The "fails" mock cannot fetch, so only the cached value can be returned
+        let offline = RpcClient::new_mock("fails".to_string());
+        assert_eq!(cache.get(&offline, None).await.unwrap(), (cached, 10));
+        assert!(cache.get(&offline, Some(cached)).await.is_err());
+
+        // Excluding the cached blockhash forces a direct fetch
+        let (fetched, _) = cache.get(&mock_rpc(), Some(cached)).await.unwrap();
+        assert_ne!(fetched, cached);
+    }
+
+    #[tokio::test]
+    async fn test_spawned_task_refreshes() {
+        let cache = BlockhashCache::spawn(Arc::new(mock_rpc()), Duration::from_millis(10), Duration::from_secs(60));
+        for _ in 0..100 {
+            if cache.current().is_some() {
+                break;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(cache.current().map(|cached| cached.last_valid_block_height), Some(1234));
+    }
+}
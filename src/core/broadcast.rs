@@ -0,0 +1,300 @@
+use {
+    anyhow::Result,
+    solana_client::{
+        client_error::{ClientError, ClientErrorKind},
+        nonblocking::rpc_client::RpcClient,
+        rpc_config::RpcSendTransactionConfig,
+        rpc_request::{RpcError, RpcResponseErrorData},
+    },
+    solana_sdk::{commitment_config::CommitmentConfig, signature::Signature, transaction::VersionedTransaction},
+    std::{
+        env, fmt,
+        sync::{Arc, Mutex},
+        time::Duration,
+    },
+    tokio::sync::mpsc,
+    crate::core::confirmation::{ConfirmationTracker, PendingSignature},
+};
+
+// Consecutive transport errors after which a send endpoint is no longer used
+pub const DEFAULT_MAX_CONSECUTIVE_ERRORS: u32 = 3;
+
+// A provider slower than this is treated as erroring rather than waited on
+const SEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Acceptance and landing counters of one send endpoint.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EndpointStats {
+    pub sent: u64,
+    pub accepted: u64,
+    /// Times this endpoint accepted a transaction before every other endpoint
+    pub first_accepted: u64,
+    /// Transport errors, rejected transactions are not counted
+    pub errors: u64,
+    /// Accepted transactions that were confirmed
+    pub landed: u64,
+    pub consecutive_errors: u32,
+    pub disabled: bool,
+}
+
+struct SendEndpoint {
+    url: String,
+    client: RpcClient,
+    stats: Mutex<EndpointStats>,
+}
+
+impl fmt::Debug for SendEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendEndpoint").field("url", &self.url).field("stats", &self.stats).finish()
+    }
+}
+
+/// Broadcasts every signed transaction to several send endpoints in parallel.
+/// Reads and confirmation polling stay on the read endpoint passed to each call.
+#[derive(Debug)]
+pub struct Broadcaster {
+    endpoints: Vec<Arc<SendEndpoint>>,
+    max_consecutive_errors: u32,
+}
+
+// Endpoints that accepted one broadcast, in order of acceptance. Shared with the send tasks
+// only for as long as the broadcast's caller holds it, so nothing outlives the call.
+type AcceptedBy = Arc<Mutex<Vec<usize>>>;
+
+impl Broadcaster {
+    pub fn new(urls: impl IntoIterator<Item = String>, max_consecutive_errors: u32) -> Self {
+        Self::with_clients(
+            urls.into_iter().map(|url| {
+                let client = RpcClient::new_with_timeout_and_commitment(url.clone(), SEND_TIMEOUT, CommitmentConfig::confirmed());
+                (url, client)
+            }),
+            max_consecutive_errors,
+        )
+    }
+
+    /// Uses already configured clients, e.g. mocks.
+    pub fn with_clients(clients: impl IntoIterator<Item = (String, RpcClient)>, max_consecutive_errors: u32) -> Self {
+        let endpoints = clients
+            .into_iter()
+            .map(|(url, client)| Arc::new(SendEndpoint { url, client, stats: Mutex::default() }))
+            .collect();
+        Self { endpoints, max_consecutive_errors }
+    }
+
+    /// Reads the comma separated `SEND_RPC_URLS` and `RPC_MAX_CONSECUTIVE_ERRORS`.
+    /// Returns `None` when no send endpoints are configured, so `RPC_URL` sends as well.
+    pub fn from_env() -> Result<Option<Self>> {
+        let urls: Vec<String> = env::var("SEND_RPC_URLS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(str::to_string)
+            .collect();
+        if urls.is_empty() {
+            return Ok(None);
+        }
+        let max_consecutive_errors = match env::var("RPC_MAX_CONSECUTIVE_ERRORS") {
+            Ok(value) => value.parse()?,
+            Err(_) => DEFAULT_MAX_CONSECUTIVE_ERRORS,
+        };
+        Ok(Some(Self::new(urls, max_consecutive_errors)))
+    }
+
+    /// Whether any send endpoint is still enabled.
+    pub fn has_enabled_endpoints(&self) -> bool {
+        self.endpoints.iter().any(|endpoint| !endpoint.stats.lock().unwrap().disabled)
+    }
+
+    /// Statistics of every send endpoint, in configuration order.
+    pub fn stats(&self) -> Vec<(String, EndpointStats)> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| (endpoint.url.clone(), *endpoint.stats.lock().unwrap()))
+            .collect()
+    }
+
+    /// Sends `transaction` to every enabled endpoint and returns the URL of the first one that
+    /// accepted it. Slower endpoints keep sending in the background and only update statistics.
+    /// If every endpoint fails, a rejection of the transaction is preferred over a transport error.
+    pub async fn broadcast(&self, transaction: &VersionedTransaction) -> Result<String, ClientError> {
+        self.broadcast_tracked(transaction).await.map(|(url, _)| url)
+    }
+
+    // Like `broadcast`, also returning the endpoints that accepted so far and, until it is
+    // dropped, those accepting later
+    async fn broadcast_tracked(&self, transaction: &VersionedTransaction) -> Result<(String, AcceptedBy), ClientError> {
+        let accepted_by = AcceptedBy::default();
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let mut pending = 0;
+        for (index, endpoint) in self.endpoints.iter().enumerate() {
+            if endpoint.stats.lock().unwrap().disabled {
+                continue;
+            }
+            pending += 1;
+            let endpoint = endpoint.clone();
+            let transaction = transaction.clone();
+            let sender = sender.clone();
+            let accepted_by = accepted_by.clone();
+            let max_consecutive_errors = self.max_consecutive_errors;
+            tokio::spawn(async move {
+                let result = endpoint.client.send_transaction_with_config(&transaction, send_config()).await;
+                let mut stats = endpoint.stats.lock().unwrap();
+                stats.sent += 1;
+                match &result {
+                    Ok(_) => {
+                        stats.accepted += 1;
+                        stats.consecutive_errors = 0;
+                        let mut accepted_by = accepted_by.lock().unwrap();
+                        if accepted_by.is_empty() {
+                            stats.first_accepted += 1;
+                        }
+                        accepted_by.push(index);
+                    }
+                    Err(err) if is_endpoint_error(err) => {
+                        stats.errors += 1;
+                        stats.consecutive_errors += 1;
+                        if stats.consecutive_errors >= max_consecutive_errors && !stats.disabled {
+                            stats.disabled = true;
+                            println!("⚠️ Disabling send endpoint {} after {} consecutive errors", endpoint.url, stats.consecutive_errors);
+                        }
+                    }
+                    // The node is fine, the transaction itself was rejected
+                    Err(_) => stats.consecutive_errors = 0,
+                }
+                drop(stats);
+                // The receiver is gone once another endpoint accepted first
+                let _ = sender.send((endpoint.url.clone(), result));
+            });
+        }
+        drop(sender);
+
+        let mut rejected = None;
+        let mut transport_error = None;
+        for _ in 0..pending {
+            let Some((url, result)) = receiver.recv().await else { break };
+            match result {
+                Ok(_) => return Ok((url, accepted_by)),
+                Err(err) if is_endpoint_error(&err) => transport_error = Some(err),
+                Err(err) => rejected = rejected.or(Some(err)),
+            }
+        }
+        Err(rejected
+            .or(transport_error)
+            .unwrap_or_else(|| RpcError::ForUser("no enabled send endpoints".to_string()).into()))
+    }
+
+    /// Broadcasts `transaction`, waits until `confirmations` reports it confirmed or its
+    /// blockhash expired, and records which endpoints got it landed.
+    pub async fn send_and_confirm(
+        &self,
+        rpc_client: &RpcClient,
+        confirmations: &ConfirmationTracker,
+        transaction: &VersionedTransaction,
+        last_valid_block_height: u64,
+    ) -> Result<Signature, ClientError> {
+        let signature = transaction.signatures[0];
+        let (first_url, accepted_by) = self.broadcast_tracked(transaction).await?;
+        println!("📡 First accepted by {}", first_url);
+        let result = confirmations.confirm(rpc_client, PendingSignature { signature, last_valid_block_height }).await;
+        if result.is_ok() {
+            let accepted = accepted_by.lock().unwrap().clone();
+            for index in accepted {
+                self.endpoints[index].stats.lock().unwrap().landed += 1;
+            }
+        }
+        result.map(|_| signature)
+    }
+}
+
+pub(crate) fn send_config() -> RpcSendTransactionConfig {
+    RpcSendTransactionConfig {
+        skip_preflight: false,
+        preflight_commitment: Some(CommitmentConfig::confirmed().commitment),
+        max_retries: Some(1), // We handle retries ourselves
+        min_context_slot: None,
+        encoding: None,
+    }
+}
+
+// Transport failures, timeouts and rate limits count against the endpoint,
+// preflight rejections do not
+fn is_endpoint_error(err: &ClientError) -> bool {
+    !matches!(
+        &err.kind,
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            data: RpcResponseErrorData::SendTransactionPreflightFailure(_),
+            ..
+        }) | ClientErrorKind::TransactionError(_)
+    )
+}
+
+pub fn print_endpoint_stats(broadcaster: &Broadcaster) {
+    println!("\n📡 Send endpoints");
+    for (url, stats) in broadcaster.stats() {
+        println!(
+            "{} {}: {} sent, {} accepted ({} first), {} landed, {} errors",
+            if stats.disabled { "⛔" } else { "✅" },
+            url,
+            stats.sent,
+            stats.accepted,
+            stats.first_accepted,
+            stats.landed,
+            stats.errors,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{hash::Hash, signature::Keypair, signer::Signer, system_instruction, transaction::Transaction},
+    };
+
+    fn transaction() -> VersionedTransaction {
+        let payer = Keypair::new();
+        let transfer = system_instruction::transfer(&payer.pubkey(), &payer.pubkey(), 1);
+        Transaction::new_signed_with_payer(&[transfer], Some(&payer.pubkey()), &[&payer], Hash::new_unique()).into()
+    }
+
+    fn broadcaster(urls: &[&str], max_consecutive_errors: u32) -> Broadcaster {
+        Broadcaster::with_clients(
+            urls.iter().map(|url| (url.to_string(), RpcClient::new_mock(url.to_string()))),
+            max_consecutive_errors,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_first_acceptance_wins_and_landing_is_recorded() {
+        let broadcaster = broadcaster(&["fails", "succeeds"], 3);
+        let read = RpcClient::new_mock("succeeds".to_string());
+        let transaction = transaction();
+
+        let confirmations = ConfirmationTracker::polling(CommitmentConfig::confirmed());
+        let signature = broadcaster.send_and_confirm(&read, &confirmations, &transaction, 2_000).await.unwrap();
+        assert_eq!(signature, transaction.signatures[0]);
+
+        // Let the failing endpoint's task finish
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let stats = broadcaster.stats();
+        assert_eq!(stats[0].1, EndpointStats { sent: 1, errors: 1, consecutive_errors: 1, ..EndpointStats::default() });
+        assert_eq!(stats[1].1, EndpointStats { sent: 1, accepted: 1, first_accepted: 1, landed: 1, ..EndpointStats::default() });
+    }
+
+    #[tokio::test]
+    async fn test_erroring_endpoint_is_disabled() {
+        let broadcaster = broadcaster(&["fails"], 2);
+        assert!(broadcaster.has_enabled_endpoints());
+
+        for _ in 0..2 {
+            let err = broadcaster.broadcast(&transaction()).await.unwrap_err();
+            assert!(is_endpoint_error(&err));
+        }
+        assert!(!broadcaster.has_enabled_endpoints());
+        assert!(broadcaster.stats()[0].1.disabled);
+        // Nothing is sent to a disabled endpoint
+        assert!(broadcaster.broadcast(&transaction()).await.is_err());
+        assert_eq!(broadcaster.stats()[0].1.sent, 2);
+    }
+}
@@ -0,0 +1,309 @@
+use {
+    futures::{future::join_all, StreamExt},
+    solana_client::{
+        client_error::{ClientError, ClientErrorKind},
+        nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+        rpc_config::RpcSignatureSubscribeConfig,
+        rpc_response::RpcSignatureResult,
+    },
+    solana_sdk::{commitment_config::CommitmentConfig, signature::Signature},
+    std::{io, time::Duration},
+    tokio::time::sleep,
+};
+
+// Roughly one slot, used when there is no websocket
+pub const DEFAULT_CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+// With a websocket, polling only enforces the deadline and covers missed notifications
+const WEBSOCKET_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A signature to confirm before its blockhash expires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingSignature {
+    pub signature: Signature,
+    pub last_valid_block_height: u64,
+}
+
+/// Waits for signatures to reach a commitment level, through `signatureSubscribe`
+/// notifications when a websocket is connected and through status polling otherwise.
+/// All signatures share one websocket connection, so many can be tracked at once.
+#[derive(Debug)]
+pub struct ConfirmationTracker {
+    pubsub: Option<PubsubClient>,
+    commitment: CommitmentConfig,
+}
+
+impl ConfirmationTracker {
+    /// A tracker that only polls `getSignatureStatuses`.
+    pub fn polling(commitment: CommitmentConfig) -> Self {
+        Self { pubsub: None, commitment }
+    }
+
+    /// Opens one websocket connection to `ws_url`, falling back to polling if that fails.
+    pub async fn connect(ws_url: &str, commitment: CommitmentConfig) -> Self {
+        match PubsubClient::new(ws_url).await {
+            Ok(pubsub) => Self { pubsub: Some(pubsub), commitment },
+            Err(err) => {
+                println!("⚠️ Websocket {} unavailable ({}), polling for confirmations", ws_url, err);
+                Self::polling(commitment)
+            }
+        }
+    }
+
+    pub fn commitment(&self) -> CommitmentConfig {
+        self.commitment
+    }
+
+    pub fn is_subscribed(&self) -> bool {
+        self.pubsub.is_some()
+    }
+
+    /// Waits until `pending` reaches the commitment level and returns the slot it landed in.
+    /// Fails with the transaction error if it failed on-chain, and with `ConfirmationExpired`
+    /// once the block height passes its last valid block height.
+    pub async fn confirm(&self, rpc_client: &RpcClient, pending: PendingSignature) -> Result<u64, ClientError> {
+        let config = RpcSignatureSubscribeConfig {
+            commitment: Some(self.commitment),
+            enable_received_notification: Some(false),
+        };
+        let subscription = match &self.pubsub {
+            Some(pubsub) => pubsub.signature_subscribe(&pending.signature, Some(config)).await.ok(),
+            None => None,
+        };
+        let (mut notifications, unsubscribe) = match subscription {
+            Some((notifications, unsubscribe)) => (Some(notifications), Some(unsubscribe)),
+            None => (None, None),
+        };
+
+        let mut block_height = None;
+        let result = loop {
+            // Polling also catches signatures that landed before the subscription started
+            if let Some(result) = self.poll(rpc_client, &pending, notifications.is_some(), &mut block_height).await {
+                break result;
+            }
+            let poll_interval = if notifications.is_some() { WEBSOCKET_POLL_INTERVAL } else { DEFAULT_CONFIRMATION_POLL_INTERVAL };
+            let notification = async {
+                match notifications.as_mut() {
+                    Some(notifications) => Some(notifications.next().await),
+                    None => std::future::pending().await,
+                }
+            };
+            let notification = tokio::select! {
+                notification = notification => notification,
+                _ = sleep(poll_interval) => None,
+            };
+            match notification {
+                Some(Some(response)) => match response.value {
+                    RpcSignatureResult::ProcessedSignature(processed) => {
+                        break match processed.err {
+                            Some(err) => Err(err.into()),
+                            None => Ok(response.context.slot),
+                        };
+                    }
+                    RpcSignatureResult::ReceivedSignature(_) => {}
+                },
+                // The connection dropped, keep going on polling alone
+                Some(None) => notifications = None,
+                None => {}
+            }
+        };
+
+        drop(notifications);
+        if let Some(unsubscribe) = unsubscribe {
+            unsubscribe().await;
+        }
+        result
+    }
+
+    /// Confirms every signature in `pending` concurrently over the shared connection.
+    pub async fn confirm_all(&self, rpc_client: &RpcClient, pending: &[PendingSignature]) -> Vec<Result<u64, ClientError>> {
+        join_all(pending.iter().map(|pending| self.confirm(rpc_client, *pending))).await
+    }
+
+    // `None` while the signature is neither confirmed, failed nor expired. Transient RPC
+    // errors are only fatal without a live subscription, which could still deliver the
+    // result. `block_height` keeps the last height read, so the deadline holds through them.
+    async fn poll(
+        &self,
+        rpc_client: &RpcClient,
+        pending: &PendingSignature,
+        subscribed: bool,
+        block_height: &mut Option<u64>,
+    ) -> Option<Result<u64, ClientError>> {
+        let signatures = [pending.signature];
+        let (statuses, height) = tokio::join!(rpc_client.get_signature_statuses(&signatures), rpc_client.get_block_height());
+        match height {
+            Ok(height) => *block_height = Some(block_height.map_or(height, |known| known.max(height))),
+            Err(err) if !subscribed => return Some(Err(err)),
+            Err(_) => {}
+        }
+        match statuses {
+            Ok(statuses) => {
+                if let Some(status) = statuses.value.into_iter().next().flatten() {
+                    if let Some(err) = status.err {
+                        return Some(Err(err.into()));
+                    }
+                    if status.satisfies_commitment(self.commitment) {
+                        return Some(Ok(status.slot));
+                    }
+                }
+            }
+            Err(err) if !subscribed => return Some(Err(err)),
+            Err(_) => {}
+        }
+        block_height
+            .is_some_and(|height| height > pending.last_valid_block_height)
+            .then(|| Err(ConfirmationExpired(pending.signature).into()))
+    }
+}
+
+/// Pubsub URL served next to an `http(s)://` RPC URL. Like the Solana CLI, an explicit
+/// port is incremented, since validators serve websockets on the RPC port + 1.
+pub fn websocket_url(rpc_url: &str) -> String {
+    let (scheme, rest) = match rpc_url.split_once("://") {
+        Some(("https", rest)) => ("wss", rest),
+        Some(("http", rest)) => ("ws", rest),
+        _ => return rpc_url.to_string(),
+    };
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let authority = match authority.rsplit_once(':').map(|(host, port)| (host, port.parse::<u16>())) {
+        Some((host, Ok(port))) => format!("{}:{}", host, port.saturating_add(1)),
+        _ => authority.to_string(),
+    };
+    format!("{}://{}{}", scheme, authority, path)
+}
+
+/// A signature still unconfirmed once its blockhash expired. It travels inside a
+/// `ClientError`, which `TxError::from_client_error` classifies as `BlockhashExpired`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("transaction {0} was not confirmed before its blockhash expired")]
+pub struct ConfirmationExpired(pub Signature);
+
+impl ConfirmationExpired {
+    pub fn from_client_error(err: &ClientError) -> Option<&Self> {
+        match &err.kind {
+            ClientErrorKind::Io(err) => err.get_ref()?.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl From<ConfirmationExpired> for ClientError {
+    fn from(expired: ConfirmationExpired) -> Self {
+        io::Error::new(io::ErrorKind::TimedOut, expired).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::core::transaction::TxError,
+        futures::SinkExt,
+        serde_json::{json, Value},
+        solana_sdk::transaction::TransactionError,
+        tokio::net::TcpListener,
+        tokio_tungstenite::{accept_async, tungstenite::Message},
+    };
+
+    fn pending(last_valid_block_height: u64) -> PendingSignature {
+        PendingSignature { signature: Signature::new_unique(), last_valid_block_height }
+    }
+
+    // Answers every signatureSubscribe on one connection and notifies each signature right
+    // away, failing the ones listed in `failing`
+    async fn websocket_server(failing: Vec<Signature>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            let mut subscription = 0;
+            while let Some(Ok(Message::Text(text))) = ws.next().await {
+                let request: Value = serde_json::from_str(&text).unwrap();
+                subscription += 1;
+                let response = json!({"jsonrpc": "2.0", "result": subscription, "id": request["id"]});
+                ws.send(Message::Text(response.to_string())).await.unwrap();
+                if request["method"] != "signatureSubscribe" {
+                    continue;
+                }
+                let signature: Signature = request["params"][0].as_str().unwrap().parse().unwrap();
+                let err = failing.contains(&signature).then_some(TransactionError::AccountInUse);
+                let notification = json!({
+                    "jsonrpc": "2.0",
+                    "method": "signatureNotification",
+                    "params": {"result": {"context": {"slot": 42}, "value": {"err": err}}, "subscription": subscription},
+                });
+                ws.send(Message::Text(notification.to_string())).await.unwrap();
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_websocket_confirms_many_signatures() {
+        let failed = pending(2_000);
+        let url = websocket_server(vec![failed.signature]).await;
+        let tracker = ConfirmationTracker::connect(&url, CommitmentConfig::confirmed()).await;
+        assert!(tracker.is_subscribed());
+
+        // The RPC never sees the signatures, so only notifications can confirm them
+        let rpc = RpcClient::new_mock("sig_not_found".to_string());
+        let results = tracker.confirm_all(&rpc, &[pending(2_000), failed, pending(2_000)]).await;
+        assert_eq!(results[0].as_ref().ok(), Some(&42));
+        assert!(matches!(
+            results[1].as_ref().map_err(|err| err.get_transaction_error()),
+            Err(Some(TransactionError::AccountInUse))
+        ));
+        assert_eq!(results[2].as_ref().ok(), Some(&42));
+    }
+
+    #[tokio::test]
+    async fn test_polling_fallback() {
+        // Nothing listens on this port
+        let tracker = ConfirmationTracker::connect("ws://127.0.0.1:1", CommitmentConfig::finalized()).await;
+        assert!(!tracker.is_subscribed());
+
+        // The mock reports every signature as finalized in slot 1
+        let rpc = RpcClient::new_mock("succeeds".to_string());
+        assert_eq!(tracker.confirm(&rpc, pending(2_000)).await.unwrap(), 1);
+
+        let failed = RpcClient::new_mock("instruction_error".to_string());
+        assert!(tracker.confirm(&failed, pending(2_000)).await.unwrap_err().get_transaction_error().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_rpc_errors_fail_once_the_subscription_is_gone() {
+        // Completes the websocket handshake, then drops the connection
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            drop(accept_async(stream).await.unwrap());
+        });
+        let tracker = ConfirmationTracker::connect(&url, CommitmentConfig::confirmed()).await;
+        assert!(tracker.is_subscribed());
+
+        // Every RPC response is unusable, without a subscription that must end the wait
+        let rpc = RpcClient::new_mock("fails".to_string());
+        let result = tokio::time::timeout(Duration::from_secs(10), tracker.confirm(&rpc, pending(2_000))).await;
+        assert!(result.expect("confirm kept waiting on a dead subscription").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_deadline_at_last_valid_block_height() {
+        // The mock is at block height 1234 and never finds the signature
+        let rpc = RpcClient::new_mock("sig_not_found".to_string());
+        let pending = pending(1_000);
+        let err = ConfirmationTracker::polling(CommitmentConfig::processed()).confirm(&rpc, pending).await.unwrap_err();
+        assert_eq!(ConfirmationExpired::from_client_error(&err), Some(&ConfirmationExpired(pending.signature)));
+        assert!(matches!(TxError::from_client_error(err, &[]), TxError::BlockhashExpired));
+    }
+
+    #[test]
+    fn test_websocket_url() {
+        assert_eq!(websocket_url("https://api.mainnet-beta.solana.com"), "wss://api.mainnet-beta.solana.com");
+        assert_eq!(websocket_url("http://127.0.0.1:8899"), "ws://127.0.0.1:8900");
+        assert_eq!(websocket_url("https://rpc.example.com/?api-key=abc"), "wss://rpc.example.com/?api-key=abc");
+    }
+}
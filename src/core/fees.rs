@@ -0,0 +1,266 @@
+use {
+    anyhow::{anyhow, Result},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{
+        compute_budget::{self, ComputeBudgetInstruction},
+        instruction::Instruction,
+        pubkey::Pubkey,
+    },
+    std::env,
+};
+
+// Compute unit price used when no estimate is available, in micro-lamports per CU
+pub const DEFAULT_COMPUTE_UNIT_PRICE: u64 = 2;
+
+pub const BASE_FEE_LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+// Upper bound a transaction can request, used for the fee cap when no limit is set
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+// ComputeBudgetInstruction tags (borsh enum index)
+pub(crate) const SET_COMPUTE_UNIT_LIMIT_TAG: u8 = 2;
+pub(crate) const SET_COMPUTE_UNIT_PRICE_TAG: u8 = 3;
+
+/// How priority fees are estimated and escalated. Prices are in micro-lamports per CU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeConfig {
+    /// Percentile (0-100) of recent prioritization fees to pay
+    pub percentile: u8,
+    pub floor: u64,
+    pub ceiling: u64,
+    /// Increase applied for every retry, in basis points of the previous price
+    pub escalation_bps: u64,
+    /// Cap on base fees, priority fee and Jito tip for one transaction, in lamports
+    pub max_total_fee_lamports: Option<u64>,
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        Self {
+            percentile: 75,
+            floor: DEFAULT_COMPUTE_UNIT_PRICE,
+            ceiling: 1_000_000,
+            escalation_bps: 5_000,
+            max_total_fee_lamports: None,
+        }
+    }
+}
+
+impl FeeConfig {
+    /// Reads overrides from `PRIORITY_FEE_PERCENTILE`, `PRIORITY_FEE_FLOOR`, `PRIORITY_FEE_CEILING`,
+    /// `PRIORITY_FEE_ESCALATION_BPS` and `MAX_TOTAL_FEE_LAMPORTS`.
+    pub fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let config = Self {
+            percentile: env_or("PRIORITY_FEE_PERCENTILE", defaults.percentile)?,
+            floor: env_or("PRIORITY_FEE_FLOOR", defaults.floor)?,
+            ceiling: env_or("PRIORITY_FEE_CEILING", defaults.ceiling)?,
+            escalation_bps: env_or("PRIORITY_FEE_ESCALATION_BPS", defaults.escalation_bps)?,
+            max_total_fee_lamports: env::var("MAX_TOTAL_FEE_LAMPORTS").ok().map(|v| v.parse()).transpose()?,
+        };
+        if config.percentile > 100 {
+            return Err(anyhow!("PRIORITY_FEE_PERCENTILE must be between 0 and 100"));
+        }
+        if config.floor > config.ceiling {
+            return Err(anyhow!("PRIORITY_FEE_FLOOR must not exceed PRIORITY_FEE_CEILING"));
+        }
+        Ok(config)
+    }
+
+    /// Picks the configured percentile of `fees`, clamped to the floor and ceiling.
+    pub fn price_from_samples(&self, fees: &[u64]) -> u64 {
+        percentile(fees, self.percentile)
+            .unwrap_or(self.floor)
+            .clamp(self.floor, self.ceiling)
+    }
+}
+
+pub(crate) fn env_or<T: std::str::FromStr>(name: &str, default: T) -> Result<T>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match env::var(name) {
+        Ok(value) => Ok(value.parse()?),
+        Err(_) => Ok(default),
+    }
+}
+
+/// Nearest-rank percentile, `None` when there are no samples.
+pub fn percentile(values: &[u64], percentile: u8) -> Option<u64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let rank = (percentile.min(100) as usize * sorted.len()).div_ceil(100);
+    Some(sorted[rank.saturating_sub(1)])
+}
+
+/// Estimates a compute unit price from `getRecentPrioritizationFees` for the writable
+/// accounts the transaction locks (bonding curve, fee recipient).
+pub async fn estimate_compute_unit_price(rpc_client: &RpcClient, writable_accounts: &[Pubkey], config: &FeeConfig) -> Result<u64> {
+    let fees: Vec<u64> = rpc_client
+        .get_recent_prioritization_fees(writable_accounts)
+        .await?
+        .into_iter()
+        .map(|fee| fee.prioritization_fee)
+        .collect();
+    Ok(config.price_from_samples(&fees))
+}
+
+/// Priority fee in lamports for `compute_units` at `price` micro-lamports per CU, rounded up.
+pub fn priority_fee_lamports(compute_units: u32, price: u64) -> u64 {
+    (compute_units as u128 * price as u128).div_ceil(MICRO_LAMPORTS_PER_LAMPORT) as u64
+}
+
+/// Compute unit prices for successive attempts of one trade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeSchedule {
+    /// Price of the first attempt (estimated or set with `--priority-fee`)
+    pub base_price: u64,
+    pub config: FeeConfig,
+}
+
+impl FeeSchedule {
+    /// Price for the 1-based `attempt`, escalated per retry up to the ceiling and lowered
+    /// if needed so the whole transaction stays under the total fee cap. The base fee of
+    /// each of its `signatures` and the attempt's `tip_lamports` count against the cap first.
+    pub fn price_for_attempt(&self, attempt: usize, compute_units: u32, signatures: usize, tip_lamports: u64) -> u64 {
+        let mut price = self.base_price;
+        if attempt > 1 {
+            // An explicit price above the ceiling is kept, just not escalated further
+            let ceiling = self.config.ceiling.max(self.base_price);
+            for _ in 1..attempt {
+                let escalated = price as u128 * (10_000 + self.config.escalation_bps) as u128 / 10_000;
+                price = escalated.min(ceiling as u128) as u64;
+            }
+        }
+
+        match self.config.max_total_fee_lamports {
+            Some(max_total) if compute_units > 0 => {
+                let fixed = (signatures as u64).saturating_mul(BASE_FEE_LAMPORTS_PER_SIGNATURE).saturating_add(tip_lamports);
+                let budget = max_total.saturating_sub(fixed) as u128;
+                let max_price = budget * MICRO_LAMPORTS_PER_LAMPORT / compute_units as u128;
+                price.min(max_price.min(u64::MAX as u128) as u64)
+            }
+            _ => price,
+        }
+    }
+}
+
+/// Compute unit limit requested by the instructions, if any.
+pub fn compute_unit_limit(instructions: &[Instruction]) -> Option<u32> {
+    instructions
+        .iter()
+        .filter(|ix| ix.program_id == compute_budget::id())
+        .find_map(|ix| match ix.data.split_first() {
+            Some((&SET_COMPUTE_UNIT_LIMIT_TAG, rest)) => rest.try_into().ok().map(u32::from_le_bytes),
+            _ => None,
+        })
+}
+
+/// Compute unit price requested by the instructions, if any.
+pub fn compute_unit_price(instructions: &[Instruction]) -> Option<u64> {
+    instructions
+        .iter()
+        .filter(|ix| ix.program_id == compute_budget::id())
+        .find_map(|ix| match ix.data.split_first() {
+            Some((&SET_COMPUTE_UNIT_PRICE_TAG, rest)) => rest.try_into().ok().map(u64::from_le_bytes),
+            _ => None,
+        })
+}
+
+/// Replaces the compute unit price instruction, returning false if there is none.
+pub fn set_compute_unit_price(instructions: &mut [Instruction], price: u64) -> bool {
+    let existing = instructions
+        .iter_mut()
+        .find(|ix| ix.program_id == compute_budget::id() && ix.data.first() == Some(&SET_COMPUTE_UNIT_PRICE_TAG));
+    match existing {
+        Some(ix) => {
+            *ix = ComputeBudgetInstruction::set_compute_unit_price(price);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::core::transaction::create_compute_budget_instructions,
+        serde_json::json,
+        solana_client::rpc_request::RpcRequest,
+        std::collections::HashMap,
+    };
+
+    #[test]
+    fn test_percentile_and_clamping() {
+        let fees = [0, 0, 10, 50, 100, 500, 1_000, 2_000];
+        assert_eq!(percentile(&fees, 0), Some(0));
+        assert_eq!(percentile(&fees, 50), Some(50));
+        assert_eq!(percentile(&fees, 75), Some(500));
+        assert_eq!(percentile(&fees, 100), Some(2_000));
+        assert_eq!(percentile(&[], 50), None);
+
+        let config = FeeConfig { floor: 20, ceiling: 1_000, ..FeeConfig::default() };
+        assert_eq!(config.price_from_samples(&fees), 500);
+        assert_eq!(FeeConfig { percentile: 10, ..config }.price_from_samples(&fees), 20);
+        assert_eq!(FeeConfig { percentile: 100, ..config }.price_from_samples(&fees), 1_000);
+        // No recent fees means the floor
+        assert_eq!(config.price_from_samples(&[]), 20);
+    }
+
+    #[tokio::test]
+    async fn test_estimate_from_rpc() {
+        let mut mocks = HashMap::new();
+        mocks.insert(
+            RpcRequest::GetRecentPrioritizationFees,
+            json!([
+                {"slot": 1, "prioritizationFee": 0},
+                {"slot": 2, "prioritizationFee": 1_000},
+                {"slot": 3, "prioritizationFee": 5_000},
+                {"slot": 4, "prioritizationFee": 20_000},
+            ]),
+        );
+        let rpc = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+        let price = estimate_compute_unit_price(&rpc, &[Pubkey::new_unique()], &FeeConfig::default()).await.unwrap();
+        assert_eq!(price, 5_000);
+    }
+
+    #[test]
+    fn test_escalation_and_total_fee_cap() {
+        let schedule = FeeSchedule {
+            base_price: 1_000,
+            config: FeeConfig { ceiling: 3_000, escalation_bps: 5_000, ..FeeConfig::default() },
+        };
+        assert_eq!(schedule.price_for_attempt(1, 100_000, 1, 0), 1_000);
+        assert_eq!(schedule.price_for_attempt(2, 100_000, 1, 0), 1_500);
+        assert_eq!(schedule.price_for_attempt(3, 100_000, 1, 0), 2_250);
+        assert_eq!(schedule.price_for_attempt(4, 100_000, 1, 0), 3_000);
+
+        // 5,000 base + at most 100 lamports of priority fee
+        let capped = FeeSchedule { config: FeeConfig { max_total_fee_lamports: Some(5_100), ..schedule.config }, ..schedule };
+        let price = capped.price_for_attempt(4, 100_000, 1, 0);
+        assert_eq!(price, 1_000);
+        assert!(BASE_FEE_LAMPORTS_PER_SIGNATURE + priority_fee_lamports(100_000, price) <= 5_100);
+
+        // A second signature and a tip leave less room for the priority fee
+        let capped = FeeSchedule { config: FeeConfig { max_total_fee_lamports: Some(10_200), ..schedule.config }, ..schedule };
+        assert_eq!(capped.price_for_attempt(4, 100_000, 2, 0), 2_000);
+        assert_eq!(capped.price_for_attempt(4, 100_000, 2, 100), 1_000);
+        assert_eq!(capped.price_for_attempt(4, 100_000, 2, 1_000_000), 0);
+    }
+
+    #[test]
+    fn test_rewrite_compute_budget_instructions() {
+        let mut instructions = create_compute_budget_instructions(63_665, 2);
+        assert_eq!(compute_unit_limit(&instructions), Some(63_665));
+        assert!(set_compute_unit_price(&mut instructions, 7_500));
+        assert_eq!(instructions[1], ComputeBudgetInstruction::set_compute_unit_price(7_500));
+        assert!(!set_compute_unit_price(&mut instructions[..1], 1));
+        assert_eq!(compute_unit_limit(&[]), None);
+    }
+}
@@ -0,0 +1,63 @@
+use {
+    base64::{engine::general_purpose::STANDARD as BASE64, Engine},
+    serde_json::{json, Value},
+    solana_sdk::{
+        hash::Hash,
+        nonce::{state::{Data, DurableNonce as StoredNonce, Versions}, State as NonceState},
+        pubkey::Pubkey,
+        system_program,
+    },
+    crate::core::{global::{GlobalAccount, FEE_RECIPIENTS_LEN}, token_price::BondingCurveAccount},
+};
+
+// Account fixtures shared by the unit tests of the core modules
+
+/// Bonding curve of a freshly launched token.
+pub fn bonding_curve() -> BondingCurveAccount {
+    BondingCurveAccount {
+        virtual_token_reserves: 1_073_000_000_000_000,
+        virtual_sol_reserves: 30_000_000_000,
+        real_token_reserves: 793_100_000_000_000,
+        real_sol_reserves: 0,
+        token_total_supply: 1_000_000_000_000_000,
+        complete: false,
+        creator: Pubkey::new_unique(),
+    }
+}
+
+/// Global account with the mainnet fees. `fee_recipient` is also the first of two extra
+/// fee recipients, the remaining slots are empty.
+pub fn global_account(fee_recipient: Pubkey) -> GlobalAccount {
+    let mut fee_recipients = [Pubkey::default(); FEE_RECIPIENTS_LEN];
+    fee_recipients[0] = fee_recipient;
+    fee_recipients[1] = Pubkey::new_unique();
+    GlobalAccount {
+        initialized: true,
+        authority: Pubkey::new_unique(),
+        fee_recipient,
+        initial_virtual_token_reserves: 1_073_000_000_000_000,
+        initial_virtual_sol_reserves: 30_000_000_000,
+        initial_real_token_reserves: 793_100_000_000_000,
+        token_total_supply: 1_000_000_000_000_000,
+        fee_basis_points: 95,
+        withdraw_authority: Pubkey::new_unique(),
+        enable_migrate: true,
+        pool_migration_fee: 15_000_001,
+        creator_fee_basis_points: 5,
+        fee_recipients,
+    }
+}
+
+/// `getAccountInfo` response for a nonce account of `authority` that stores `blockhash`.
+pub fn nonce_account_json(authority: &Pubkey, blockhash: &Hash) -> Value {
+    let data = Data::new(*authority, StoredNonce::from_blockhash(blockhash), 5_000);
+    let data = bincode::serialize(&Versions::new(NonceState::Initialized(data))).unwrap();
+    json!({"context": {"slot": 1}, "value": {
+        "lamports": 1_447_680,
+        "data": [BASE64.encode(&data), "base64"],
+        "owner": system_program::id().to_string(),
+        "executable": false,
+        "rentEpoch": 0,
+        "space": data.len(),
+    }})
+}
@@ -0,0 +1,148 @@
+use {
+    anyhow::Result,
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::pubkey::Pubkey,
+    crate::{
+        core::token_price::{check_discriminator, read_pubkey, read_u64, AccountDecodeError},
+        utils::config::global_pda,
+    },
+};
+
+// Anchor account discriminator: sha256("account:Global")[..8]
+pub const GLOBAL_DISCRIMINATOR: [u8; 8] = [0xa7, 0xe8, 0xe8, 0xb1, 0xc8, 0x6c, 0x72, 0x7f];
+
+pub const FEE_RECIPIENTS_LEN: usize = 7;
+
+// Everything up to and including the `fee_recipients` array
+pub const GLOBAL_ACCOUNT_LEN: usize = 8 + 1 + 32 + 32 + 5 * 8 + 32 + 1 + 8 + 8 + FEE_RECIPIENTS_LEN * 32;
+
+pub const BASIS_POINTS_DENOMINATOR: u64 = 10_000;
+
+/// Decoded pump.fun `Global` account (the `GLOBAL_PDA` config account).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlobalAccount {
+    pub initialized: bool,
+    pub authority: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub initial_virtual_token_reserves: u64,
+    pub initial_virtual_sol_reserves: u64,
+    pub initial_real_token_reserves: u64,
+    pub token_total_supply: u64,
+    pub fee_basis_points: u64,
+    pub withdraw_authority: Pubkey,
+    pub enable_migrate: bool,
+    pub pool_migration_fee: u64,
+    pub creator_fee_basis_points: u64,
+    pub fee_recipients: [Pubkey; FEE_RECIPIENTS_LEN],
+}
+
+impl GlobalAccount {
+    /// Decodes the raw account data, checking the length and the Anchor discriminator.
+    /// Fields appended by later program versions are ignored.
+    pub fn from_account_data(data: &[u8]) -> Result<Self, AccountDecodeError> {
+        check_discriminator(data, GLOBAL_DISCRIMINATOR, GLOBAL_ACCOUNT_LEN)?;
+
+        let mut fee_recipients = [Pubkey::default(); FEE_RECIPIENTS_LEN];
+        for (i, recipient) in fee_recipients.iter_mut().enumerate() {
+            *recipient = read_pubkey(data, 162 + i * 32);
+        }
+
+        Ok(Self {
+            initialized: data[8] != 0,
+            authority: read_pubkey(data, 9),
+            fee_recipient: read_pubkey(data, 41),
+            initial_virtual_token_reserves: read_u64(data, 73),
+            initial_virtual_sol_reserves: read_u64(data, 81),
+            initial_real_token_reserves: read_u64(data, 89),
+            token_total_supply: read_u64(data, 97),
+            fee_basis_points: read_u64(data, 105),
+            withdraw_authority: read_pubkey(data, 113),
+            enable_migrate: data[145] != 0,
+            pool_migration_fee: read_u64(data, 146),
+            creator_fee_basis_points: read_u64(data, 154),
+            fee_recipients,
+        })
+    }
+
+    /// Fetches and decodes the Global account at `GLOBAL_PDA`.
+    pub async fn fetch(rpc_client: &RpcClient) -> Result<Self> {
+        let account = rpc_client.get_account(&global_pda()).await?;
+        Ok(Self::from_account_data(&account.data)?)
+    }
+
+    /// Protocol fee plus creator fee, in basis points of the SOL side of a trade.
+    pub fn total_fee_basis_points(&self) -> u64 {
+        self.fee_basis_points + self.creator_fee_basis_points
+    }
+
+    /// Every fee recipient the program accepts, primary recipient first.
+    pub fn all_fee_recipients(&self) -> Vec<Pubkey> {
+        std::iter::once(self.fee_recipient)
+            .chain(self.fee_recipients.iter().copied())
+            .filter(|recipient| *recipient != Pubkey::default())
+            .collect()
+    }
+}
+
+/// Fee charged by the program on `amount` lamports, rounded up like the on-chain `ceil_div`.
+pub fn compute_fee(amount: u64, fee_basis_points: u64) -> u64 {
+    let fee = (amount as u128 * fee_basis_points as u128).div_ceil(BASIS_POINTS_DENOMINATOR as u128);
+    fee as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::core::fixtures::global_account};
+
+    fn encode_global(global: &GlobalAccount) -> Vec<u8> {
+        let mut data = vec![];
+        data.extend_from_slice(&GLOBAL_DISCRIMINATOR);
+        data.push(global.initialized as u8);
+        data.extend_from_slice(global.authority.as_ref());
+        data.extend_from_slice(global.fee_recipient.as_ref());
+        data.extend_from_slice(&global.initial_virtual_token_reserves.to_le_bytes());
+        data.extend_from_slice(&global.initial_virtual_sol_reserves.to_le_bytes());
+        data.extend_from_slice(&global.initial_real_token_reserves.to_le_bytes());
+        data.extend_from_slice(&global.token_total_supply.to_le_bytes());
+        data.extend_from_slice(&global.fee_basis_points.to_le_bytes());
+        data.extend_from_slice(global.withdraw_authority.as_ref());
+        data.push(global.enable_migrate as u8);
+        data.extend_from_slice(&global.pool_migration_fee.to_le_bytes());
+        data.extend_from_slice(&global.creator_fee_basis_points.to_le_bytes());
+        for recipient in &global.fee_recipients {
+            data.extend_from_slice(recipient.as_ref());
+        }
+        data
+    }
+
+    #[test]
+    fn test_decode_global_account() {
+        let global = global_account(Pubkey::new_unique());
+        let mut data = encode_global(&global);
+        assert_eq!(data.len(), GLOBAL_ACCOUNT_LEN);
+        assert_eq!(GlobalAccount::from_account_data(&data), Ok(global));
+
+        data.extend_from_slice(&[0u8; 64]);
+        assert_eq!(GlobalAccount::from_account_data(&data), Ok(global));
+
+        assert!(matches!(
+            GlobalAccount::from_account_data(&data[..100]),
+            Err(AccountDecodeError::TooShort { .. })
+        ));
+    }
+
+    #[test]
+    fn test_fee_helpers() {
+        let global = global_account(Pubkey::new_unique());
+        assert_eq!(global.total_fee_basis_points(), 100);
+        assert_eq!(
+            global.all_fee_recipients(),
+            vec![global.fee_recipient, global.fee_recipients[0], global.fee_recipients[1]]
+        );
+
+        assert_eq!(compute_fee(1_000_000_000, 100), 10_000_000);
+        // Rounds up on fractional lamports
+        assert_eq!(compute_fee(101, 100), 2);
+        assert_eq!(compute_fee(0, 100), 0);
+    }
+}
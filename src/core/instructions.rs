@@ -8,7 +8,6 @@ use {
     spl_token::ID as TOKEN_PROGRAM_ID,
     crate::utils::config::{
         global_pda,
-        fee_account,
         event_authority,
         pump_program_id,
     },
@@ -18,6 +17,37 @@ use {
 pub const BUY_DISCRIMINATOR: [u8; 8] = [0x66, 0x06, 0x3d, 0x12, 0x01, 0xda, 0xeb, 0xea];
 pub const SELL_DISCRIMINATOR: [u8; 8] = [0x33, 0xe6, 0x85, 0xa4, 0x01, 0x7f, 0x83, 0xad];
 
+// Account names in instruction order, for printing and decoding
+pub const BUY_ACCOUNT_NAMES: [&str; 12] = [
+    "Global PDA",
+    "Fee account",
+    "Token mint",
+    "Bonding curve",
+    "Associated bonding curve",
+    "User token account",
+    "User (signer)",
+    "System program",
+    "Token program",
+    "Creator vault",
+    "Event authority",
+    "Program ID",
+];
+pub const SELL_ACCOUNT_NAMES: [&str; 12] = [
+    "Global PDA",
+    "Fee account",
+    "Token mint",
+    "Bonding curve",
+    "Associated bonding curve",
+    "User token account",
+    "User (signer)",
+    "System program",
+    "Creator vault",
+    "Token program",
+    "Event authority",
+    "Program ID",
+];
+
+#[allow(clippy::too_many_arguments)]
 pub fn create_buy_instruction(
     buyer: &Pubkey,
     token_mint: &Pubkey,
@@ -25,6 +55,7 @@ pub fn create_buy_instruction(
     bonding_curve: &Pubkey,
     associated_bonding_curve: &Pubkey,
     creator_vault_ata: &Pubkey,
+    fee_recipient: &Pubkey,
     token_amount: u64,
     max_sol_cost: u64,
 ) -> Result<Instruction> {
@@ -36,7 +67,7 @@ pub fn create_buy_instruction(
     // Accounts must be in exactly this order to match the Pump.fun program expectations
     let accounts = vec![
         AccountMeta::new_readonly(global_pda(), false),           // #1 - Global PDA
-        AccountMeta::new(fee_account(), false),                   // #2 - Fee account
+        AccountMeta::new(*fee_recipient, false),                  // #2 - Fee account
         AccountMeta::new_readonly(*token_mint, false),            // #3 - Token mint
         AccountMeta::new(*bonding_curve, false),                  // #4 - Bonding curve
         AccountMeta::new(*associated_bonding_curve, false),       // #5 - Associated bonding curve
@@ -56,6 +87,7 @@ pub fn create_buy_instruction(
     })
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn create_sell_instruction(
     seller: &Pubkey,
     token_mint: &Pubkey,
@@ -63,6 +95,7 @@ pub fn create_sell_instruction(
     bonding_curve: &Pubkey,
     associated_bonding_curve: &Pubkey,
     creator_vault_ata: &Pubkey,
+    fee_recipient: &Pubkey,
     token_amount: u64,
     min_sol_output: u64,
 ) -> Result<Instruction> {
@@ -75,7 +108,7 @@ pub fn create_sell_instruction(
     // NOTE: Sell has a different account order than Buy!
     let accounts = vec![
         AccountMeta::new_readonly(global_pda(), false),           // #1 - Global PDA
-        AccountMeta::new(fee_account(), false),                   // #2 - Fee account
+        AccountMeta::new(*fee_recipient, false),                  // #2 - Fee account
         AccountMeta::new_readonly(*token_mint, false),            // #3 - Token mint
         AccountMeta::new(*bonding_curve, false),                  // #4 - Bonding curve
         AccountMeta::new(*associated_bonding_curve, false),       // #5 - Associated bonding curve
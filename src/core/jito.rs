@@ -0,0 +1,380 @@
+use {
+    anyhow::{anyhow, Result},
+    base64::{engine::general_purpose::STANDARD as BASE64, Engine},
+    serde_json::{json, Value},
+    reqwest::StatusCode,
+    solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::RpcError},
+    solana_sdk::{
+        instruction::Instruction, pubkey::Pubkey, signature::Signature, system_instruction, transaction::VersionedTransaction,
+    },
+    std::{env, str::FromStr, time::Duration},
+    tokio::time::sleep,
+    crate::core::{fees::env_or, transaction::TxError},
+};
+
+// Mainnet tip accounts of the Jito tip payment program, any of them can receive the tip
+pub const JITO_TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+
+// Block engines drop bundles that tip less than this
+pub const MIN_TIP_LAMPORTS: u64 = 1_000;
+
+// Bundle statuses are only reported once a bundle landed, so poll about once per slot
+const BUNDLE_STATUS_POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Errors talking to a block engine.
+#[derive(Debug, thiserror::Error)]
+pub enum JitoError {
+    #[error("block engine request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("block engine error {code}: {message}")]
+    Rpc { status: StatusCode, code: i64, message: String },
+    #[error("unexpected block engine response: {0}")]
+    InvalidResponse(String),
+    #[error("failed to encode transaction: {0}")]
+    Encode(#[from] bincode::Error),
+}
+
+impl JitoError {
+    /// Whether the block engine was unreachable, overloaded or rate limiting, rather than
+    /// rejecting the request itself. Only these are worth resending.
+    pub fn is_transport(&self) -> bool {
+        match self {
+            Self::Http(_) => true,
+            Self::Rpc { status, .. } => status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS,
+            Self::InvalidResponse(_) | Self::Encode(_) => false,
+        }
+    }
+}
+
+/// Tip paid per attempt, escalated like the priority fee. Amounts are in lamports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TipPolicy {
+    pub base_lamports: u64,
+    /// Increase applied for every retry, in basis points of the previous tip
+    pub escalation_bps: u64,
+    pub max_lamports: u64,
+}
+
+impl Default for TipPolicy {
+    fn default() -> Self {
+        Self { base_lamports: 10_000, escalation_bps: 5_000, max_lamports: 1_000_000 }
+    }
+}
+
+impl TipPolicy {
+    /// Tip for the 1-based `attempt`, never below the block engine minimum.
+    pub fn tip_for_attempt(&self, attempt: usize) -> u64 {
+        let ceiling = self.max_lamports.max(self.base_lamports);
+        let mut tip = self.base_lamports;
+        for _ in 1..attempt {
+            let escalated = tip as u128 * (10_000 + self.escalation_bps) as u128 / 10_000;
+            tip = escalated.min(ceiling as u128) as u64;
+        }
+        tip.max(MIN_TIP_LAMPORTS)
+    }
+}
+
+/// Status of a bundle that landed, as reported by `getBundleStatuses`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleStatus {
+    pub bundle_id: String,
+    pub slot: u64,
+    pub confirmation_status: String,
+    pub transactions: Vec<Signature>,
+}
+
+impl BundleStatus {
+    fn from_value(value: &Value) -> Result<Self, JitoError> {
+        let invalid = || JitoError::InvalidResponse(value.to_string());
+        let transactions = value["transactions"]
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|signature| signature.as_str().and_then(|s| Signature::from_str(s).ok()).ok_or_else(invalid))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            bundle_id: value["bundle_id"].as_str().ok_or_else(invalid)?.to_string(),
+            slot: value["slot"].as_u64().ok_or_else(invalid)?,
+            confirmation_status: value["confirmation_status"].as_str().ok_or_else(invalid)?.to_string(),
+            transactions,
+        })
+    }
+
+    pub fn is_confirmed(&self) -> bool {
+        matches!(self.confirmation_status.as_str(), "confirmed" | "finalized")
+    }
+}
+
+/// Submits transactions as bundles to a Jito block engine, so they skip the public mempool.
+#[derive(Debug)]
+pub struct JitoClient {
+    url: String,
+    http: reqwest::Client,
+    tip_account: Pubkey,
+    pub tip_policy: TipPolicy,
+}
+
+impl JitoClient {
+    /// `url` is the bundle JSON-RPC endpoint, e.g. `https://mainnet.block-engine.jito.wtf/api/v1/bundles`.
+    pub fn new(url: String, tip_account: Pubkey, tip_policy: TipPolicy) -> Result<Self> {
+        let http = reqwest::Client::builder().timeout(HTTP_TIMEOUT).build()?;
+        Ok(Self { url, http, tip_account, tip_policy })
+    }
+
+    /// Reads `JITO_BLOCK_ENGINE_URL`, `JITO_TIP_ACCOUNT`, `JITO_TIP_LAMPORTS`,
+    /// `JITO_TIP_ESCALATION_BPS` and `JITO_MAX_TIP_LAMPORTS`.
+    /// Returns `None` when no block engine is configured.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(url) = env::var("JITO_BLOCK_ENGINE_URL") else {
+            return Ok(None);
+        };
+        let tip_account = match env::var("JITO_TIP_ACCOUNT") {
+            Ok(address) => Pubkey::from_str(&address)?,
+            Err(_) => Pubkey::from_str(JITO_TIP_ACCOUNTS[0])?,
+        };
+        let defaults = TipPolicy::default();
+        let tip_policy = TipPolicy {
+            base_lamports: env_or("JITO_TIP_LAMPORTS", defaults.base_lamports)?,
+            escalation_bps: env_or("JITO_TIP_ESCALATION_BPS", defaults.escalation_bps)?,
+            max_lamports: env_or("JITO_MAX_TIP_LAMPORTS", defaults.max_lamports)?,
+        };
+        if tip_policy.base_lamports < MIN_TIP_LAMPORTS {
+            return Err(anyhow!("JITO_TIP_LAMPORTS must be at least {}", MIN_TIP_LAMPORTS));
+        }
+        Ok(Some(Self::new(url, tip_account, tip_policy)?))
+    }
+
+    pub fn tip_account(&self) -> Pubkey {
+        self.tip_account
+    }
+
+    /// Transfer of `lamports` from `payer` to the tip account, appended to the bundled transaction.
+    pub fn tip_instruction(&self, payer: &Pubkey, lamports: u64) -> Instruction {
+        system_instruction::transfer(payer, &self.tip_account, lamports)
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value, JitoError> {
+        let request = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+        let response = self.http.post(&self.url).json(&request).send().await?;
+        let status = response.status();
+        let mut response: Value = response.json().await?;
+        if let Some(error) = response.get("error") {
+            return Err(JitoError::Rpc {
+                status,
+                code: error["code"].as_i64().unwrap_or_default(),
+                message: error["message"].as_str().unwrap_or_default().to_string(),
+            });
+        }
+        match response.get_mut("result") {
+            Some(result) => Ok(result.take()),
+            None => Err(JitoError::InvalidResponse(response.to_string())),
+        }
+    }
+
+    /// Submits `transactions` as one atomic bundle and returns the bundle id.
+    pub async fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<String, JitoError> {
+        let encoded = transactions
+            .iter()
+            .map(|transaction| Ok(BASE64.encode(bincode::serialize(transaction)?)))
+            .collect::<Result<Vec<_>, JitoError>>()?;
+        let result = self.call("sendBundle", json!([encoded, {"encoding": "base64"}])).await?;
+        result.as_str().map(str::to_string).ok_or_else(|| JitoError::InvalidResponse(result.to_string()))
+    }
+
+    /// Statuses of `bundle_ids`, `None` for bundles that have not landed.
+    pub async fn get_bundle_statuses(&self, bundle_ids: &[String]) -> Result<Vec<Option<BundleStatus>>, JitoError> {
+        let result = self.call("getBundleStatuses", json!([bundle_ids])).await?;
+        let values = result["value"].as_array().ok_or_else(|| JitoError::InvalidResponse(result.to_string()))?;
+        let mut statuses: Vec<Option<BundleStatus>> = vec![None; bundle_ids.len()];
+        for value in values.iter().filter(|value| !value.is_null()) {
+            let status = BundleStatus::from_value(value)?;
+            if let Some(index) = bundle_ids.iter().position(|id| *id == status.bundle_id) {
+                statuses[index] = Some(status);
+            }
+        }
+        Ok(statuses)
+    }
+
+    /// Sends `transaction` as a single-transaction bundle and polls its status until it is
+    /// confirmed, failing with `BlockhashExpired` once the blockhash expires.
+    pub async fn send_and_confirm(
+        &self,
+        rpc_client: &RpcClient,
+        transaction: &VersionedTransaction,
+        last_valid_block_height: u64,
+    ) -> Result<Signature, TxError> {
+        let signature = transaction.signatures[0];
+        let bundle_id = self.send_bundle(std::slice::from_ref(transaction)).await.map_err(tx_error)?;
+        println!("📦 Bundle submitted: {}", bundle_id);
+        let bundle_ids = [bundle_id];
+        loop {
+            let (statuses, block_height) = tokio::join!(self.get_bundle_statuses(&bundle_ids), rpc_client.get_block_height());
+            // A status error only delays confirmation, expiry is decided by the block height
+            if let Some(status) = statuses.ok().and_then(|statuses| statuses.into_iter().next().flatten()) {
+                if status.is_confirmed() {
+                    println!("📦 Bundle landed in slot {}", status.slot);
+                    return Ok(signature);
+                }
+            }
+            if block_height.map_err(TxError::Rpc)? > last_valid_block_height {
+                return Err(TxError::BlockhashExpired);
+            }
+            sleep(BUNDLE_STATUS_POLL_INTERVAL).await;
+        }
+    }
+}
+
+// Only transport failures are retried: a rejected bundle fails the same way on every
+// attempt, and each retry pays a higher tip
+fn tx_error(err: JitoError) -> TxError {
+    match err {
+        JitoError::Http(err) => TxError::Rpc(err.into()),
+        err if err.is_transport() => TxError::Rpc(RpcError::ForUser(err.to_string()).into()),
+        err => TxError::Bundle(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::core::transaction::TxError,
+        mockito::{Matcher, Server},
+        solana_sdk::{hash::Hash, signature::Keypair, signer::Signer, transaction::Transaction},
+    };
+
+    fn client(url: String) -> JitoClient {
+        JitoClient::new(url, Pubkey::new_unique(), TipPolicy::default()).unwrap()
+    }
+
+    fn tipped_transaction(jito: &JitoClient) -> VersionedTransaction {
+        let payer = Keypair::new();
+        let tip = jito.tip_instruction(&payer.pubkey(), 10_000);
+        Transaction::new_signed_with_payer(&[tip], Some(&payer.pubkey()), &[&payer], Hash::new_unique()).into()
+    }
+
+    #[test]
+    fn test_tip_policy() {
+        let policy = TipPolicy { base_lamports: 10_000, escalation_bps: 5_000, max_lamports: 30_000 };
+        assert_eq!(policy.tip_for_attempt(1), 10_000);
+        assert_eq!(policy.tip_for_attempt(2), 15_000);
+        assert_eq!(policy.tip_for_attempt(3), 22_500);
+        assert_eq!(policy.tip_for_attempt(4), 30_000);
+        assert_eq!(TipPolicy { base_lamports: 0, ..policy }.tip_for_attempt(1), MIN_TIP_LAMPORTS);
+        for account in JITO_TIP_ACCOUNTS {
+            assert!(Pubkey::from_str(account).is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_bundle_and_poll_status() {
+        let mut server = Server::new_async().await;
+        let jito = client(server.url());
+        let transaction = tipped_transaction(&jito);
+        let encoded = BASE64.encode(bincode::serialize(&transaction).unwrap());
+
+        let send = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({"method": "sendBundle", "params": [[encoded], {"encoding": "base64"}]})))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": "bundle-1"}).to_string())
+            .create_async()
+            .await;
+        let status = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({"method": "getBundleStatuses", "params": [["bundle-1"]]})))
+            .with_body(
+                json!({"jsonrpc": "2.0", "id": 1, "result": {"context": {"slot": 100}, "value": [{
+                    "bundle_id": "bundle-1",
+                    "transactions": [transaction.signatures[0].to_string()],
+                    "slot": 99,
+                    "confirmation_status": "confirmed",
+                    "err": {"Ok": null},
+                }]}})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let rpc = RpcClient::new_mock("succeeds".to_string());
+        let signature = jito.send_and_confirm(&rpc, &transaction, 2_000).await.unwrap();
+        assert_eq!(signature, transaction.signatures[0]);
+        send.assert_async().await;
+        status.assert_async().await;
+
+        let statuses = jito.get_bundle_statuses(&["bundle-1".to_string()]).await.unwrap();
+        assert_eq!(statuses[0].as_ref().map(|status| status.slot), Some(99));
+    }
+
+    #[tokio::test]
+    async fn test_unlanded_bundle_expires() {
+        let mut server = Server::new_async().await;
+        let jito = client(server.url());
+        server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({"method": "sendBundle"})))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": "bundle-2"}).to_string())
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({"method": "getBundleStatuses"})))
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": {"context": {"slot": 100}, "value": [null]}}).to_string())
+            .create_async()
+            .await;
+
+        // The mock RPC is at block height 1234, past the blockhash's last valid height
+        let rpc = RpcClient::new_mock("succeeds".to_string());
+        let err = jito.send_and_confirm(&rpc, &tipped_transaction(&jito), 1_000).await.unwrap_err();
+        assert!(matches!(err, TxError::BlockhashExpired));
+    }
+
+    #[tokio::test]
+    async fn test_block_engine_error_is_retryable() {
+        let rpc = RpcClient::new_mock("succeeds".to_string());
+        let cases = [
+            (429, json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32097, "message": "rate limit exceeded"}}), true),
+            (503, json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32603, "message": "service unavailable"}}), true),
+            (200, json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32602, "message": "bundle tip too low"}}), false),
+            (200, json!({"jsonrpc": "2.0", "id": 1, "result": 7}), false),
+        ];
+        for (status, body, retryable) in cases {
+            let mut server = Server::new_async().await;
+            let jito = client(server.url());
+            server.mock("POST", "/").with_status(status).with_body(body.to_string()).create_async().await;
+
+            let transaction = tipped_transaction(&jito);
+            let err = jito.send_and_confirm(&rpc, &transaction, 2_000).await.unwrap_err();
+            assert_eq!(err.is_retryable(), retryable, "{}", err);
+            assert_eq!(matches!(err, TxError::Bundle(_)), !retryable);
+        }
+
+        let mut server = Server::new_async().await;
+        let jito = client(server.url());
+        server
+            .mock("POST", "/")
+            .with_body(json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32602, "message": "bundle tip too low"}}).to_string())
+            .create_async()
+            .await;
+        assert!(matches!(
+            jito.send_bundle(std::slice::from_ref(&tipped_transaction(&jito))).await,
+            Err(JitoError::Rpc { status: StatusCode::OK, code: -32602, .. })
+        ));
+
+        // Nothing listens on port 1, so the request itself fails
+        let jito = client("http://127.0.0.1:1".to_string());
+        let err = jito.send_and_confirm(&rpc, &tipped_transaction(&jito), 2_000).await.unwrap_err();
+        assert!(matches!(err, TxError::Rpc(_)));
+        assert!(err.is_retryable());
+    }
+}
@@ -0,0 +1,208 @@
+use {
+    aes_gcm::{
+        aead::{Aead, KeyInit, Payload},
+        Aes256Gcm, Nonce,
+    },
+    anyhow::Result,
+    argon2::{Algorithm, Argon2, Params, Version},
+    base64::{engine::general_purpose::STANDARD as BASE64, Engine},
+    rand::{rngs::OsRng, RngCore},
+    serde::{Deserialize, Serialize},
+    solana_sdk::{
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+    },
+    std::{fs, io::Write, path::Path, str::FromStr},
+    tempfile::NamedTempFile,
+    zeroize::Zeroizing,
+    crate::core::wallet::WalletError,
+};
+
+pub const KEYSTORE_VERSION: u32 = 1;
+
+const KDF_ALGORITHM: &str = "argon2id";
+const CIPHER: &str = "aes-256-gcm";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+/// Argon2id cost parameters, stored in the keystore so they can be raised later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    // 64 MiB and 3 passes, about a second per unlock on a trading box
+    fn default() -> Self {
+        Self { memory_kib: 64 * 1024, iterations: 3, parallelism: 1 }
+    }
+}
+
+/// A keypair encrypted with a key derived from a password (Argon2id, then AES-256-GCM).
+/// The public key is stored in the clear and authenticated with the ciphertext.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u32,
+    pub pubkey: String,
+    pub kdf: String,
+    pub kdf_params: KdfParams,
+    /// Base64 encoded
+    pub salt: String,
+    pub cipher: String,
+    /// Base64 encoded
+    pub nonce: String,
+    /// Base64 encoded secret key and authentication tag
+    pub ciphertext: String,
+}
+
+impl Keystore {
+    pub fn encrypt(keypair: &Keypair, password: &[u8]) -> Result<Self> {
+        Self::encrypt_with_params(keypair, password, KdfParams::default())
+    }
+
+    pub fn encrypt_with_params(keypair: &Keypair, password: &[u8], kdf_params: KdfParams) -> Result<Self> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let pubkey = keypair.pubkey().to_string();
+        let key = derive_key(password, &salt, &kdf_params)?;
+        let secret = Zeroizing::new(keypair.to_bytes());
+        let cipher = Aes256Gcm::new_from_slice(key.as_ref()).map_err(|_| WalletError::Decrypt)?;
+        let ciphertext = cipher
+            .encrypt(&Nonce::from(nonce), Payload { msg: secret.as_ref(), aad: pubkey.as_bytes() })
+            .map_err(|_| WalletError::Decrypt)?;
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            pubkey,
+            kdf: KDF_ALGORITHM.to_string(),
+            kdf_params,
+            salt: BASE64.encode(salt),
+            cipher: CIPHER.to_string(),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        })
+    }
+
+    /// Public key of the stored keypair, readable without the password.
+    pub fn pubkey(&self) -> Result<Pubkey> {
+        Ok(Pubkey::from_str(&self.pubkey)?)
+    }
+
+    pub fn decrypt(&self, password: &[u8]) -> Result<Keypair> {
+        if self.version != KEYSTORE_VERSION || self.kdf != KDF_ALGORITHM || self.cipher != CIPHER {
+            return Err(WalletError::UnsupportedKeystore(format!("version {} with {} and {}", self.version, self.kdf, self.cipher)).into());
+        }
+        let salt = BASE64.decode(&self.salt)?;
+        let nonce: [u8; NONCE_LEN] = BASE64
+            .decode(&self.nonce)?
+            .try_into()
+            .map_err(|nonce: Vec<u8>| WalletError::UnsupportedKeystore(format!("{} byte nonce", nonce.len())))?;
+        let ciphertext = BASE64.decode(&self.ciphertext)?;
+
+        let key = derive_key(password, &salt, &self.kdf_params)?;
+        let cipher = Aes256Gcm::new_from_slice(key.as_ref()).map_err(|_| WalletError::Decrypt)?;
+        // A wrong password and a tampered file fail the same authentication check
+        let secret = Zeroizing::new(
+            cipher
+                .decrypt(&Nonce::from(nonce), Payload { msg: &ciphertext, aad: self.pubkey.as_bytes() })
+                .map_err(|_| WalletError::Decrypt)?,
+        );
+        let keypair = Keypair::from_bytes(&secret).map_err(|err| WalletError::InvalidKey(err.to_string()))?;
+        if keypair.pubkey().to_string() != self.pubkey {
+            return Err(WalletError::Decrypt.into());
+        }
+        Ok(keypair)
+    }
+
+    /// Writes the keystore through a temporary file in the same directory, so an existing
+    /// keystore is replaced atomically. The file is only readable by its owner.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let mut file = NamedTempFile::new_in(directory)?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        file.as_file().sync_all()?;
+        file.persist(path)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+}
+
+fn derive_key(password: &[u8], salt: &[u8], kdf_params: &KdfParams) -> Result<Zeroizing<[u8; KEY_LEN]>> {
+    let params = Params::new(kdf_params.memory_kib, kdf_params.iterations, kdf_params.parallelism, Some(KEY_LEN))
+        .map_err(|err| WalletError::UnsupportedKeystore(err.to_string()))?;
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password, salt, key.as_mut())
+        .map_err(|err| WalletError::UnsupportedKeystore(err.to_string()))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cheap parameters, the defaults take a second per derivation
+    const TEST_PARAMS: KdfParams = KdfParams { memory_kib: 64, iterations: 1, parallelism: 1 };
+
+    #[test]
+    fn test_round_trip_and_wrong_password() {
+        let keypair = Keypair::new();
+        let keystore = Keystore::encrypt_with_params(&keypair, b"correct horse", TEST_PARAMS).unwrap();
+        assert_eq!(keystore.pubkey().unwrap(), keypair.pubkey());
+        assert!(!keystore.ciphertext.contains(&keypair.to_base58_string()));
+
+        let decrypted = keystore.decrypt(b"correct horse").unwrap();
+        assert_eq!(decrypted.to_bytes(), keypair.to_bytes());
+
+        let err = keystore.decrypt(b"battery staple").unwrap_err();
+        assert!(matches!(err.downcast_ref::<WalletError>(), Some(WalletError::Decrypt)));
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let keypair = Keypair::new();
+        let keystore = Keystore::encrypt_with_params(&keypair, b"pw", TEST_PARAMS).unwrap();
+
+        // The clear public key is authenticated, so it cannot be swapped
+        let swapped = Keystore { pubkey: Keypair::new().pubkey().to_string(), ..keystore.clone() };
+        assert!(swapped.decrypt(b"pw").is_err());
+
+        let mut ciphertext = BASE64.decode(&keystore.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        let flipped = Keystore { ciphertext: BASE64.encode(ciphertext), ..keystore.clone() };
+        assert!(flipped.decrypt(b"pw").is_err());
+
+        let unknown = Keystore { cipher: "rot13".to_string(), ..keystore };
+        let err = unknown.decrypt(b"pw").unwrap_err();
+        assert!(matches!(err.downcast_ref::<WalletError>(), Some(WalletError::UnsupportedKeystore(_))));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("wallet.json");
+        let keystore = Keystore::encrypt_with_params(&Keypair::new(), b"pw", TEST_PARAMS).unwrap();
+        keystore.save(&path).unwrap();
+        // Saving again replaces the file in place
+        keystore.save(&path).unwrap();
+        assert_eq!(Keystore::load(&path).unwrap(), keystore);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+}
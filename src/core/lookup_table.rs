@@ -0,0 +1,199 @@
+use {
+    anyhow::{anyhow, Result},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{
+        address_lookup_table::{
+            instruction::{create_lookup_table, extend_lookup_table},
+            state::{AddressLookupTable, LOOKUP_TABLE_MAX_ADDRESSES},
+            AddressLookupTableAccount,
+        },
+        commitment_config::CommitmentConfig,
+        compute_budget,
+        instruction::Instruction,
+        pubkey::Pubkey,
+        signature::{Keypair, Signature, Signer},
+        system_program,
+        transaction::Transaction,
+    },
+    std::{env, str::FromStr},
+    crate::{
+        core::global::GlobalAccount,
+        utils::config::{associated_token_program_id, event_authority, global_pda, pump_program_id, token_program_id},
+    },
+};
+
+// Addresses per extend transaction, well within the transaction size limit
+const EXTEND_CHUNK_SIZE: usize = 20;
+
+/// Accounts every pump trade references whatever the mint or wallet: the programs, the
+/// Global PDA, the event authority and every fee recipient the Global account accepts.
+pub fn static_pump_accounts(global: &GlobalAccount) -> Vec<Pubkey> {
+    let mut accounts = vec![
+        global_pda(),
+        event_authority(),
+        pump_program_id(),
+        system_program::id(),
+        token_program_id(),
+        associated_token_program_id(),
+        compute_budget::id(),
+    ];
+    accounts.extend(global.all_fee_recipients());
+    dedup(accounts)
+}
+
+/// Reads the comma separated `LOOKUP_TABLE_ADDRESSES`. Empty when none are configured,
+/// so transactions stay legacy.
+pub fn lookup_table_addresses_from_env() -> Result<Vec<Pubkey>> {
+    env::var("LOOKUP_TABLE_ADDRESSES")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|address| !address.is_empty())
+        .map(|address| Ok(Pubkey::from_str(address)?))
+        .collect()
+}
+
+/// Fetches and decodes the lookup tables at `addresses` in one request.
+pub async fn fetch_lookup_tables(rpc_client: &RpcClient, addresses: &[Pubkey]) -> Result<Vec<AddressLookupTableAccount>> {
+    if addresses.is_empty() {
+        return Ok(vec![]);
+    }
+    let accounts = rpc_client.get_multiple_accounts(addresses).await?;
+    addresses
+        .iter()
+        .zip(accounts)
+        .map(|(address, account)| {
+            let account = account.ok_or_else(|| anyhow!("lookup table {} does not exist", address))?;
+            let table = AddressLookupTable::deserialize(&account.data)?;
+            Ok(AddressLookupTableAccount { key: *address, addresses: table.addresses.to_vec() })
+        })
+        .collect()
+}
+
+/// Creates an empty lookup table with `authority` as authority and payer, returning its address.
+pub async fn create_address_lookup_table(rpc_client: &RpcClient, authority: &Keypair) -> Result<Pubkey> {
+    // The table address is derived from a slot that must still be in the SlotHashes sysvar
+    let recent_slot = rpc_client.get_slot_with_commitment(CommitmentConfig::finalized()).await?;
+    let (instruction, address) = create_lookup_table(authority.pubkey(), authority.pubkey(), recent_slot);
+    send_instruction(rpc_client, authority, instruction).await?;
+    Ok(address)
+}
+
+/// Addresses in `addresses` that `table` does not hold yet, without duplicates.
+pub fn missing_addresses(table: &AddressLookupTableAccount, addresses: &[Pubkey]) -> Result<Vec<Pubkey>> {
+    let missing: Vec<Pubkey> = dedup(addresses.to_vec())
+        .into_iter()
+        .filter(|address| !table.addresses.contains(address))
+        .collect();
+    if table.addresses.len() + missing.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+        return Err(anyhow!(
+            "lookup table {} holds {} addresses, {} more exceed the limit of {}",
+            table.key,
+            table.addresses.len(),
+            missing.len(),
+            LOOKUP_TABLE_MAX_ADDRESSES
+        ));
+    }
+    Ok(missing)
+}
+
+/// Appends the `addresses` that `table` does not hold yet and returns them. Added addresses
+/// can be looked up from the slot after the extension.
+pub async fn extend_address_lookup_table(
+    rpc_client: &RpcClient,
+    authority: &Keypair,
+    table: &AddressLookupTableAccount,
+    addresses: &[Pubkey],
+) -> Result<Vec<Pubkey>> {
+    let missing = missing_addresses(table, addresses)?;
+    for chunk in missing.chunks(EXTEND_CHUNK_SIZE) {
+        let instruction = extend_lookup_table(table.key, authority.pubkey(), Some(authority.pubkey()), chunk.to_vec());
+        let signature = send_instruction(rpc_client, authority, instruction).await?;
+        println!("➕ Added {} address(es): {}", chunk.len(), signature);
+    }
+    Ok(missing)
+}
+
+async fn send_instruction(rpc_client: &RpcClient, payer: &Keypair, instruction: Instruction) -> Result<Signature> {
+    let blockhash = rpc_client.get_latest_blockhash().await?;
+    let transaction = Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[payer], blockhash);
+    Ok(rpc_client.send_and_confirm_transaction(&transaction).await?)
+}
+
+// Keeps the first occurrence of every address
+fn dedup(addresses: Vec<Pubkey>) -> Vec<Pubkey> {
+    let mut unique = Vec::with_capacity(addresses.len());
+    for address in addresses {
+        if !unique.contains(&address) {
+            unique.push(address);
+        }
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::core::{fixtures::global_account, instructions::create_buy_instruction, transaction::create_compute_budget_instructions},
+        solana_sdk::{
+            hash::Hash,
+            message::{v0, Message, VersionedMessage},
+            transaction::VersionedTransaction,
+        },
+    };
+
+    #[test]
+    fn test_static_accounts_and_missing_addresses() {
+        let fee_recipient = Pubkey::new_unique();
+        let accounts = static_pump_accounts(&global_account(fee_recipient));
+        // Seven fixed accounts plus two distinct fee recipients, empty slots are skipped
+        assert_eq!(accounts.len(), 9);
+        assert!(accounts.contains(&fee_recipient));
+
+        let wallet = Pubkey::new_unique();
+        let table = AddressLookupTableAccount { key: Pubkey::new_unique(), addresses: accounts[..4].to_vec() };
+        let mut wanted = accounts.clone();
+        wanted.extend([wallet, wallet]);
+        let missing = missing_addresses(&table, &wanted).unwrap();
+        assert_eq!(missing.len(), 6);
+        assert_eq!(missing.last(), Some(&wallet));
+
+        let full = AddressLookupTableAccount { key: Pubkey::new_unique(), addresses: vec![Pubkey::new_unique(); LOOKUP_TABLE_MAX_ADDRESSES] };
+        assert!(missing_addresses(&full, &[wallet]).is_err());
+    }
+
+    #[test]
+    fn test_lookup_table_shrinks_a_buy() {
+        let global = global_account(Pubkey::new_unique());
+        let buyer = Keypair::new();
+        let mut instructions = create_compute_budget_instructions(100_000, 10_000);
+        instructions.push(
+            create_buy_instruction(
+                &buyer.pubkey(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &global.fee_recipient,
+                1_000,
+                1_000,
+            )
+            .unwrap(),
+        );
+        let table = AddressLookupTableAccount { key: Pubkey::new_unique(), addresses: static_pump_accounts(&global) };
+        let blockhash = Hash::new_unique();
+
+        let legacy = Message::new_with_blockhash(&instructions, Some(&buyer.pubkey()), &blockhash);
+        let legacy = VersionedTransaction::try_new(VersionedMessage::Legacy(legacy), &[&buyer]).unwrap();
+        let compiled = v0::Message::try_compile(&buyer.pubkey(), &instructions, &[table], blockhash).unwrap();
+        assert_eq!(compiled.address_table_lookups.len(), 1);
+        let compiled = VersionedTransaction::try_new(VersionedMessage::V0(compiled), &[&buyer]).unwrap();
+
+        // Each looked up account costs one byte instead of 32
+        let legacy_size = bincode::serialize(&legacy).unwrap().len();
+        let compiled_size = bincode::serialize(&compiled).unwrap().len();
+        assert!(compiled_size + 100 < legacy_size, "{} vs {}", compiled_size, legacy_size);
+    }
+}
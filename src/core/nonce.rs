@@ -0,0 +1,246 @@
+use {
+    anyhow::{anyhow, Result},
+    base64::{engine::general_purpose::STANDARD as BASE64, Engine},
+    serde::{Deserialize, Serialize},
+    solana_client::{
+        nonblocking::{nonce_utils, rpc_client::RpcClient},
+        rpc_config::RpcSendTransactionConfig,
+    },
+    solana_sdk::{
+        clock::MAX_PROCESSING_AGE,
+        hash::Hash,
+        instruction::Instruction,
+        nonce::State as NonceState,
+        pubkey::Pubkey,
+        signature::{Keypair, Signature, Signer},
+        system_instruction,
+        transaction::Transaction,
+    },
+    std::{fs, path::Path, str::FromStr},
+    crate::core::{
+        broadcast::Broadcaster,
+        confirmation::{ConfirmationTracker, PendingSignature},
+        transaction::TxError,
+    },
+};
+
+// Broadcasts of a pre-signed transaction before giving up
+const PRESIGNED_MAX_BROADCASTS: usize = 3;
+
+/// A durable nonce, used in place of a recent blockhash so a transaction stays valid
+/// until the nonce is advanced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DurableNonce {
+    pub account: Pubkey,
+    pub authority: Pubkey,
+    /// Stored nonce, signed as the transaction's recent blockhash
+    pub nonce: Hash,
+    pub lamports_per_signature: u64,
+}
+
+impl DurableNonce {
+    pub async fn fetch(rpc_client: &RpcClient, account: &Pubkey) -> Result<Self> {
+        let nonce_account = nonce_utils::get_account_with_commitment(rpc_client, account, rpc_client.commitment()).await?;
+        let data = nonce_utils::data_from_account(&nonce_account)?;
+        Ok(Self {
+            account: *account,
+            authority: data.authority,
+            nonce: data.blockhash(),
+            lamports_per_signature: data.get_lamports_per_signature(),
+        })
+    }
+
+    /// The nonce authority has to sign every transaction, so it must be the fee payer here.
+    pub fn ensure_authority(&self, payer: &Pubkey) -> Result<()> {
+        if self.authority != *payer {
+            return Err(anyhow!("nonce account {} is controlled by {}, not by the wallet {}", self.account, self.authority, payer));
+        }
+        Ok(())
+    }
+
+    pub fn advance_instruction(&self) -> Instruction {
+        system_instruction::advance_nonce_account(&self.account, &self.authority)
+    }
+
+    /// Signs `instructions` against the nonce, with `AdvanceNonceAccount` as the first instruction.
+    pub fn sign(&self, payer: &Keypair, instructions: &[Instruction]) -> Result<Transaction> {
+        self.ensure_authority(&payer.pubkey())?;
+        let mut nonce_instructions = vec![self.advance_instruction()];
+        nonce_instructions.extend_from_slice(instructions);
+        Ok(Transaction::new_signed_with_payer(&nonce_instructions, Some(&payer.pubkey()), &[payer], self.nonce))
+    }
+}
+
+/// Creates and initializes `nonce_account` with `authority`, funded rent exempt by `payer`.
+pub async fn create_nonce_account(
+    rpc_client: &RpcClient,
+    payer: &Keypair,
+    nonce_account: &Keypair,
+    authority: &Pubkey,
+) -> Result<Signature> {
+    let (lamports, blockhash) = tokio::try_join!(
+        rpc_client.get_minimum_balance_for_rent_exemption(NonceState::size()),
+        rpc_client.get_latest_blockhash(),
+    )?;
+    let instructions = system_instruction::create_nonce_account(&payer.pubkey(), &nonce_account.pubkey(), authority, lamports);
+    let transaction = Transaction::new_signed_with_payer(&instructions, Some(&payer.pubkey()), &[payer, nonce_account], blockhash);
+    Ok(rpc_client.send_and_confirm_transaction(&transaction).await?)
+}
+
+/// A transaction signed against a durable nonce, kept on disk until it is fired.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresignedTransaction {
+    pub description: String,
+    pub signature: String,
+    pub nonce_account: String,
+    pub nonce: String,
+    /// Base64 encoded signed transaction
+    pub transaction: String,
+}
+
+impl PresignedTransaction {
+    pub fn new(description: String, nonce: &DurableNonce, transaction: &Transaction) -> Result<Self> {
+        Ok(Self {
+            description,
+            signature: transaction.signatures[0].to_string(),
+            nonce_account: nonce.account.to_string(),
+            nonce: nonce.nonce.to_string(),
+            transaction: BASE64.encode(bincode::serialize(transaction)?),
+        })
+    }
+
+    pub fn transaction(&self) -> Result<Transaction> {
+        Ok(bincode::deserialize(&BASE64.decode(&self.transaction)?)?)
+    }
+
+    pub fn nonce_account(&self) -> Result<Pubkey> {
+        Ok(Pubkey::from_str(&self.nonce_account)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+}
+
+/// Sends a pre-signed durable nonce transaction as is, with no blockhash fetch or signing
+/// first, and returns the slot it landed in. It is rebroadcast while the nonce is unchanged
+/// and fails once another transaction advanced the nonce.
+pub async fn send_presigned(
+    rpc_client: &RpcClient,
+    confirmations: &ConfirmationTracker,
+    broadcaster: Option<&Broadcaster>,
+    transaction: &Transaction,
+    nonce_account: &Pubkey,
+) -> Result<u64> {
+    let signature = transaction.signatures[0];
+    for broadcast in 1..=PRESIGNED_MAX_BROADCASTS {
+        let sent = match broadcaster.filter(|broadcaster| broadcaster.has_enabled_endpoints()) {
+            Some(broadcaster) => broadcaster.broadcast(&transaction.clone().into()).await.map(|_| signature),
+            None => rpc_client.send_transaction_with_config(transaction, presigned_send_config()).await,
+        };
+        sent.map_err(|err| TxError::from_client_error(err, &[]))?;
+        println!("🔥 Broadcast {} of {}: {}", broadcast, PRESIGNED_MAX_BROADCASTS, signature);
+
+        // A nonce never expires, so confirmation waits as long as a blockhash would
+        let deadline = rpc_client.get_block_height().await? + MAX_PROCESSING_AGE as u64;
+        let err = match confirmations.confirm(rpc_client, PendingSignature { signature, last_valid_block_height: deadline }).await {
+            Ok(slot) => return Ok(slot),
+            Err(err) => TxError::from_client_error(err, &[]),
+        };
+        if !matches!(err, TxError::BlockhashExpired) {
+            return Err(err.into());
+        }
+
+        // It may have landed right at the deadline, which advanced the nonce too
+        let signatures = [signature];
+        let (statuses, current) = tokio::try_join!(
+            async { Ok(rpc_client.get_signature_statuses(&signatures).await?) },
+            DurableNonce::fetch(rpc_client, nonce_account),
+        )?;
+        if let Some(status) = statuses.value.into_iter().next().flatten() {
+            return match status.err {
+                Some(err) => Err(anyhow!("pre-signed transaction failed: {}", err)),
+                None => Ok(status.slot),
+            };
+        }
+        if current.nonce != transaction.message.recent_blockhash {
+            return Err(anyhow!("nonce {} was advanced by another transaction, {} can no longer land", nonce_account, signature));
+        }
+    }
+    Err(anyhow!("pre-signed transaction {} did not land after {} broadcasts", signature, PRESIGNED_MAX_BROADCASTS))
+}
+
+// Preflight would only add a simulation round trip, the transaction was checked when signed
+fn presigned_send_config() -> RpcSendTransactionConfig {
+    RpcSendTransactionConfig { skip_preflight: true, max_retries: Some(0), ..RpcSendTransactionConfig::default() }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_client::rpc_request::RpcRequest,
+        solana_sdk::{commitment_config::CommitmentConfig, hash::hash, system_program},
+        std::collections::HashMap,
+        crate::core::fixtures::nonce_account_json,
+    };
+
+    #[tokio::test]
+    async fn test_fetch_and_sign_against_nonce() {
+        let payer = Keypair::new();
+        let account = Pubkey::new_unique();
+        let blockhash = hash(b"nonce");
+        let mut mocks = HashMap::new();
+        mocks.insert(RpcRequest::GetAccountInfo, nonce_account_json(&payer.pubkey(), &blockhash));
+        let rpc = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+
+        let nonce = DurableNonce::fetch(&rpc, &account).await.unwrap();
+        assert_eq!(nonce.authority, payer.pubkey());
+        assert_eq!(nonce.lamports_per_signature, 5_000);
+
+        let transfer = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        let transaction = nonce.sign(&payer, std::slice::from_ref(&transfer)).unwrap();
+        assert_eq!(transaction.message.recent_blockhash, nonce.nonce);
+        assert_eq!(transaction.message.instructions.len(), 2);
+        let first = &transaction.message.instructions[0];
+        assert_eq!(transaction.message.account_keys[first.program_id_index as usize], system_program::id());
+        assert_eq!(first.data, nonce.advance_instruction().data);
+        assert!(transaction.verify().is_ok());
+
+        // Only the authority can advance the nonce
+        assert!(nonce.sign(&Keypair::new(), &[transfer]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_presigned_round_trip_and_send() {
+        let payer = Keypair::new();
+        let nonce = DurableNonce {
+            account: Pubkey::new_unique(),
+            authority: payer.pubkey(),
+            nonce: hash(b"nonce"),
+            lamports_per_signature: 5_000,
+        };
+        let transfer = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        let transaction = nonce.sign(&payer, &[transfer]).unwrap();
+
+        let presigned = PresignedTransaction::new("exit".to_string(), &nonce, &transaction).unwrap();
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("presigned.json");
+        presigned.save(&file).unwrap();
+        let loaded = PresignedTransaction::load(&file).unwrap();
+        assert_eq!(loaded, presigned);
+        assert_eq!(loaded.transaction().unwrap(), transaction);
+        assert_eq!(loaded.nonce_account().unwrap(), nonce.account);
+
+        // The mock RPC echoes the signature and reports it finalized in slot 1
+        let rpc = RpcClient::new_mock("succeeds".to_string());
+        let confirmations = ConfirmationTracker::polling(CommitmentConfig::confirmed());
+        let slot = send_presigned(&rpc, &confirmations, None, &transaction, &nonce.account).await.unwrap();
+        assert_eq!(slot, 1);
+    }
+}
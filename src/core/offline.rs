@@ -0,0 +1,331 @@
+use {
+    anyhow::{anyhow, Result},
+    base64::{engine::general_purpose::STANDARD as BASE64, Engine},
+    serde::{Deserialize, Serialize},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{
+        instruction::{AccountMeta, Instruction},
+        message::Message,
+        pubkey::Pubkey,
+        sanitize::Sanitize,
+        signature::Keypair,
+        transaction::Transaction,
+    },
+    std::{fs, path::Path, str::FromStr},
+    crate::{
+        core::{
+            broadcast::{send_config, Broadcaster},
+            confirmation::{ConfirmationTracker, PendingSignature},
+            fees::{compute_unit_limit, compute_unit_price},
+            instructions::{BUY_ACCOUNT_NAMES, BUY_DISCRIMINATOR, SELL_ACCOUNT_NAMES, SELL_DISCRIMINATOR},
+            nonce::send_presigned,
+            transaction::TxError,
+        },
+        utils::config::pump_program_id,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountSummary {
+    pub name: String,
+    pub address: String,
+    pub writable: bool,
+    pub signer: bool,
+}
+
+/// A decoded pump buy or sell instruction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PumpInstructionSummary {
+    pub side: TradeSide,
+    /// Raw token amount bought or sold
+    pub token_amount: u64,
+    /// `max_sol_cost` of a buy or `min_sol_output` of a sell, in lamports
+    pub sol_limit: u64,
+    pub accounts: Vec<AccountSummary>,
+}
+
+impl PumpInstructionSummary {
+    /// Decodes a pump buy or sell, `None` for any other instruction.
+    pub fn from_instruction(instruction: &Instruction) -> Option<Self> {
+        if instruction.program_id != pump_program_id() || instruction.data.len() < 24 {
+            return None;
+        }
+        let (discriminator, amounts) = instruction.data.split_at(8);
+        let (side, names) = if discriminator == BUY_DISCRIMINATOR {
+            (TradeSide::Buy, &BUY_ACCOUNT_NAMES)
+        } else if discriminator == SELL_DISCRIMINATOR {
+            (TradeSide::Sell, &SELL_ACCOUNT_NAMES)
+        } else {
+            return None;
+        };
+        let accounts = instruction
+            .accounts
+            .iter()
+            .enumerate()
+            .map(|(index, meta)| AccountSummary {
+                name: names.get(index).copied().unwrap_or("Extra account").to_string(),
+                address: meta.pubkey.to_string(),
+                writable: meta.is_writable,
+                signer: meta.is_signer,
+            })
+            .collect();
+        Some(Self {
+            side,
+            token_amount: u64::from_le_bytes(amounts[..8].try_into().ok()?),
+            sol_limit: u64::from_le_bytes(amounts[8..16].try_into().ok()?),
+            accounts,
+        })
+    }
+}
+
+/// What a transaction does, decoded from the transaction itself for review before signing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionSummary {
+    pub fee_payer: String,
+    /// Recent blockhash, or the nonce of a durable nonce transaction
+    pub recent_blockhash: String,
+    pub compute_unit_limit: Option<u32>,
+    /// Micro-lamports per compute unit
+    pub compute_unit_price: Option<u64>,
+    /// Program invoked by each instruction, in order
+    pub programs: Vec<String>,
+    pub pump_instructions: Vec<PumpInstructionSummary>,
+}
+
+impl TransactionSummary {
+    pub fn from_message(message: &Message) -> Self {
+        let instructions = decompile_instructions(message);
+        Self {
+            fee_payer: message.account_keys.first().map(Pubkey::to_string).unwrap_or_default(),
+            recent_blockhash: message.recent_blockhash.to_string(),
+            compute_unit_limit: compute_unit_limit(&instructions),
+            compute_unit_price: compute_unit_price(&instructions),
+            programs: instructions.iter().map(|ix| ix.program_id.to_string()).collect(),
+            pump_instructions: instructions.iter().filter_map(PumpInstructionSummary::from_instruction).collect(),
+        }
+    }
+}
+
+/// Instructions of a legacy message, with signer and writable flags restored from its header.
+/// The message must be sanitized, decoded files go through `OfflineTransaction::transaction`.
+pub fn decompile_instructions(message: &Message) -> Vec<Instruction> {
+    message
+        .instructions
+        .iter()
+        .map(|compiled| Instruction {
+            program_id: message.account_keys[compiled.program_id_index as usize],
+            accounts: compiled
+                .accounts
+                .iter()
+                .map(|&index| {
+                    let index = index as usize;
+                    AccountMeta {
+                        pubkey: message.account_keys[index],
+                        is_signer: message.is_signer(index),
+                        is_writable: message.is_writable(index),
+                    }
+                })
+                .collect(),
+            data: compiled.data.clone(),
+        })
+        .collect()
+}
+
+/// A transaction built on an online machine without keys, signed on an isolated one and
+/// broadcast from an online one again. Stored as JSON so the summary can be reviewed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OfflineTransaction {
+    pub description: String,
+    /// Durable nonce account the transaction is signed against, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce_account: Option<String>,
+    /// Last block height a transaction signed against a recent blockhash can land at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_valid_block_height: Option<u64>,
+    pub summary: TransactionSummary,
+    /// Base64 encoded transaction, with default signatures until it is signed
+    pub transaction: String,
+}
+
+impl OfflineTransaction {
+    pub fn new(
+        description: String,
+        transaction: &Transaction,
+        nonce_account: Option<&Pubkey>,
+        last_valid_block_height: Option<u64>,
+    ) -> Result<Self> {
+        Ok(Self {
+            description,
+            nonce_account: nonce_account.map(Pubkey::to_string),
+            last_valid_block_height,
+            summary: TransactionSummary::from_message(&transaction.message),
+            transaction: BASE64.encode(bincode::serialize(transaction)?),
+        })
+    }
+
+    /// Decodes the stored transaction, refusing one whose account indices are out of range.
+    pub fn transaction(&self) -> Result<Transaction> {
+        let transaction: Transaction = bincode::deserialize(&BASE64.decode(&self.transaction)?)?;
+        transaction.sanitize()?;
+        Ok(transaction)
+    }
+
+    pub fn nonce_account(&self) -> Result<Option<Pubkey>> {
+        Ok(self.nonce_account.as_deref().map(Pubkey::from_str).transpose()?)
+    }
+
+    /// Decodes the transaction and fails if the stored summary does not describe it,
+    /// so an edited summary cannot hide what is being signed.
+    pub fn verified_transaction(&self) -> Result<Transaction> {
+        let transaction = self.transaction()?;
+        if TransactionSummary::from_message(&transaction.message) != self.summary {
+            return Err(anyhow!("summary does not match the serialized transaction"));
+        }
+        Ok(transaction)
+    }
+
+    pub fn is_signed(&self) -> Result<bool> {
+        Ok(self.transaction()?.is_signed())
+    }
+
+    /// Adds `keypair`'s signature, which must be one of the transaction's required signers.
+    pub fn sign(&mut self, keypair: &Keypair) -> Result<()> {
+        let mut transaction = self.verified_transaction()?;
+        let blockhash = transaction.message.recent_blockhash;
+        transaction.try_partial_sign(&[keypair], blockhash)?;
+        self.transaction = BASE64.encode(bincode::serialize(&transaction)?);
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+}
+
+/// Sends a fully signed offline transaction and returns the slot it landed in. Durable nonce
+/// transactions are rebroadcast like pre-signed ones; the others are sent once and confirmed
+/// until their last valid block height.
+pub async fn send_offline(
+    rpc_client: &RpcClient,
+    confirmations: &ConfirmationTracker,
+    broadcaster: Option<&Broadcaster>,
+    offline: &OfflineTransaction,
+) -> Result<u64> {
+    let transaction = offline.verified_transaction()?;
+    if !transaction.is_signed() {
+        return Err(anyhow!("transaction is missing signatures, run `sign` first"));
+    }
+    if let Some(nonce_account) = offline.nonce_account()? {
+        return send_presigned(rpc_client, confirmations, broadcaster, &transaction, &nonce_account).await;
+    }
+
+    let last_valid_block_height = offline
+        .last_valid_block_height
+        .ok_or_else(|| anyhow!("transaction has neither a nonce account nor a last valid block height"))?;
+    let signature = transaction.signatures[0];
+    let instructions = decompile_instructions(&transaction.message);
+    let sent = match broadcaster.filter(|broadcaster| broadcaster.has_enabled_endpoints()) {
+        Some(broadcaster) => broadcaster.broadcast(&transaction.clone().into()).await.map(|_| signature),
+        None => rpc_client.send_transaction_with_config(&transaction, send_config()).await,
+    };
+    sent.map_err(|err| TxError::from_client_error(err, &instructions))?;
+    let pending = PendingSignature { signature, last_valid_block_height };
+    Ok(confirmations
+        .confirm(rpc_client, pending)
+        .await
+        .map_err(|err| TxError::from_client_error(err, &instructions))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::core::{instructions::create_sell_instruction, transaction::create_compute_budget_instructions},
+        solana_sdk::{commitment_config::CommitmentConfig, hash::Hash, signer::Signer},
+    };
+
+    fn unsigned_sell(seller: &Pubkey) -> Transaction {
+        let mut instructions = create_compute_budget_instructions(80_000, 25_000);
+        let sell = create_sell_instruction(
+            seller,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            5_000_000,
+            1_234,
+        )
+        .unwrap();
+        instructions.push(sell);
+        Transaction::new_unsigned(Message::new_with_blockhash(&instructions, Some(seller), &Hash::new_unique()))
+    }
+
+    #[test]
+    fn test_summary_decodes_the_transaction() {
+        let seller = Pubkey::new_unique();
+        let summary = TransactionSummary::from_message(&unsigned_sell(&seller).message);
+        assert_eq!(summary.fee_payer, seller.to_string());
+        assert_eq!(summary.compute_unit_limit, Some(80_000));
+        assert_eq!(summary.compute_unit_price, Some(25_000));
+        assert_eq!(summary.programs.len(), 3);
+
+        let [sell] = summary.pump_instructions.as_slice() else { panic!("expected one pump instruction") };
+        assert_eq!(sell.side, TradeSide::Sell);
+        assert_eq!((sell.token_amount, sell.sol_limit), (5_000_000, 1_234));
+        assert_eq!(sell.accounts.len(), 12);
+        assert_eq!(sell.accounts[6], AccountSummary { name: "User (signer)".to_string(), address: seller.to_string(), writable: true, signer: true });
+        assert_eq!(sell.accounts[8].name, "Creator vault");
+    }
+
+    #[tokio::test]
+    async fn test_build_sign_and_broadcast() {
+        let keypair = Keypair::new();
+        let mut offline = OfflineTransaction::new("sell".to_string(), &unsigned_sell(&keypair.pubkey()), None, Some(2_000)).unwrap();
+        assert!(!offline.is_signed().unwrap());
+
+        let rpc = RpcClient::new_mock("succeeds".to_string());
+        let confirmations = ConfirmationTracker::polling(CommitmentConfig::confirmed());
+        assert!(send_offline(&rpc, &confirmations, None, &offline).await.is_err());
+
+        // Only a required signer can sign
+        assert!(offline.clone().sign(&Keypair::new()).is_err());
+        offline.sign(&keypair).unwrap();
+        assert!(offline.is_signed().unwrap());
+        assert!(offline.transaction().unwrap().verify().is_ok());
+
+        // An edited summary is refused
+        let mut tampered = offline.clone();
+        tampered.summary.pump_instructions[0].sol_limit = 0;
+        assert!(send_offline(&rpc, &confirmations, None, &tampered).await.is_err());
+
+        assert_eq!(send_offline(&rpc, &confirmations, None, &offline).await.unwrap(), 1);
+    }
+
+    #[test]
+    fn test_malformed_transaction_is_an_error() {
+        let keypair = Keypair::new();
+        let mut transaction = unsigned_sell(&keypair.pubkey());
+        let mut offline = OfflineTransaction::new("sell".to_string(), &transaction, None, None).unwrap();
+
+        // An edited file pointing an instruction past the account keys
+        transaction.message.instructions[2].accounts[0] = u8::MAX;
+        offline.transaction = BASE64.encode(bincode::serialize(&transaction).unwrap());
+        assert!(offline.transaction().is_err());
+        assert!(offline.verified_transaction().is_err());
+        assert!(offline.sign(&keypair).is_err());
+    }
+}
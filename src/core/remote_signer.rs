@@ -0,0 +1,576 @@
+use {
+    anyhow::{anyhow, Context, Result},
+    base64::{engine::general_purpose::STANDARD as BASE64, Engine},
+    serde::{Deserialize, Serialize},
+    solana_sdk::{
+        compute_budget,
+        message::VersionedMessage,
+        pubkey::Pubkey,
+        signature::{Keypair, Signature},
+        signer::{Signer, SignerError},
+        system_instruction::SystemInstruction,
+        system_program,
+    },
+    std::{
+        env, fs,
+        io::{BufRead, BufReader, Write},
+        os::unix::{fs::{FileTypeExt, PermissionsExt}, net::UnixStream},
+        path::{Path, PathBuf},
+        str::FromStr,
+        sync::{Arc, Mutex},
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+    tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader as AsyncBufReader},
+        net::{UnixListener, UnixStream as AsyncUnixStream},
+        runtime::{Handle, RuntimeFlavor},
+        task,
+    },
+    crate::{
+        core::{
+            fees::{
+                env_or, priority_fee_lamports, BASE_FEE_LAMPORTS_PER_SIGNATURE, MAX_COMPUTE_UNIT_LIMIT,
+                SET_COMPUTE_UNIT_LIMIT_TAG, SET_COMPUTE_UNIT_PRICE_TAG,
+            },
+            instructions::{BUY_DISCRIMINATOR, SELL_DISCRIMINATOR},
+        },
+        utils::config::{associated_token_program_id, pump_program_id},
+    },
+};
+
+pub const DEFAULT_SIGNER_SOCKET: &str = "pumpfun-signer.sock";
+pub const DEFAULT_SIGNER_SPEND_FILE: &str = "signer-spend.json";
+
+// Rent-exempt minimum of a 165 byte token account, paid by the signer when an ATA is created
+const TOKEN_ACCOUNT_RENT_LAMPORTS: u64 = 2_039_280;
+const SECONDS_PER_DAY: u64 = 86_400;
+// Signing sits on the send path, a daemon that answers slower than this is treated as down
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Why the signer daemon refused to sign a message.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PolicyError {
+    #[error("malformed message: {0}")]
+    MalformedMessage(String),
+    #[error("{0} is not a required signer of the message")]
+    NotASigner(Pubkey),
+    #[error("program {0} is not allowed")]
+    ProgramNotAllowed(Pubkey),
+    #[error("instruction {index} for program {program} is not allowed")]
+    InstructionNotAllowed { index: usize, program: Pubkey },
+    #[error("transfer of {lamports} lamports exceeds the {limit} lamport transfer limit")]
+    TransferTooLarge { lamports: u64, limit: u64 },
+    #[error("transaction puts {lamports} lamports at risk, above the {limit} lamport limit")]
+    TransactionTooLarge { lamports: u64, limit: u64 },
+    #[error("daily limit of {limit} lamports reached, {spent} signed today and {requested} requested")]
+    DailyLimit { spent: u64, requested: u64, limit: u64 },
+}
+
+/// What the signer daemon is willing to sign. Only pump buys and sells, compute budget,
+/// associated token account creation, durable nonce advances and small system transfers
+/// (Jito tips) pass; anything else is refused whatever its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignerPolicy {
+    pub max_transfer_lamports: u64,
+    /// Cap on the lamports one transaction can move out of the wallet, fees included
+    pub max_transaction_lamports: u64,
+    /// Cap on the lamports signed for per UTC day
+    pub daily_limit_lamports: u64,
+}
+
+impl Default for SignerPolicy {
+    fn default() -> Self {
+        Self {
+            max_transfer_lamports: 10_000_000,
+            max_transaction_lamports: 1_000_000_000,
+            daily_limit_lamports: 10_000_000_000,
+        }
+    }
+}
+
+impl SignerPolicy {
+    /// Reads overrides from `SIGNER_MAX_TRANSFER_LAMPORTS`, `SIGNER_MAX_TX_LAMPORTS` and
+    /// `SIGNER_DAILY_LIMIT_LAMPORTS`.
+    pub fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        Ok(Self {
+            max_transfer_lamports: env_or("SIGNER_MAX_TRANSFER_LAMPORTS", defaults.max_transfer_lamports)?,
+            max_transaction_lamports: env_or("SIGNER_MAX_TX_LAMPORTS", defaults.max_transaction_lamports)?,
+            daily_limit_lamports: env_or("SIGNER_DAILY_LIMIT_LAMPORTS", defaults.daily_limit_lamports)?,
+        })
+    }
+
+    /// Upper bound on the lamports `signer` can lose if `message` executes: the buy
+    /// `max_sol_cost`, transfers, token account rent and fees. Errors if any instruction
+    /// falls outside the policy.
+    pub fn lamports_at_risk(&self, message: &VersionedMessage, signer: &Pubkey) -> Result<u64, PolicyError> {
+        let keys = message.static_account_keys();
+        let required_signatures = message.header().num_required_signatures as usize;
+        if !keys.iter().take(required_signatures).any(|key| key == signer) {
+            return Err(PolicyError::NotASigner(*signer));
+        }
+
+        let mut at_risk: u64 = 0;
+        let mut compute_unit_limit = None;
+        let mut compute_unit_price = 0;
+        for (index, instruction) in message.instructions().iter().enumerate() {
+            // Program ids can never come from a lookup table, so they are always static keys
+            let program = *keys
+                .get(instruction.program_id_index as usize)
+                .ok_or_else(|| PolicyError::MalformedMessage(format!("instruction {} has no program id", index)))?;
+            let data = instruction.data.as_slice();
+            let not_allowed = || PolicyError::InstructionNotAllowed { index, program };
+
+            let lamports = if program == system_program::id() {
+                match bincode::deserialize::<SystemInstruction>(data).map_err(|_| not_allowed())? {
+                    SystemInstruction::Transfer { lamports } if lamports > self.max_transfer_lamports => {
+                        return Err(PolicyError::TransferTooLarge { lamports, limit: self.max_transfer_lamports });
+                    }
+                    SystemInstruction::Transfer { lamports } => lamports,
+                    SystemInstruction::AdvanceNonceAccount => 0,
+                    _ => return Err(not_allowed()),
+                }
+            } else if program == compute_budget::id() {
+                match data.split_first() {
+                    Some((&SET_COMPUTE_UNIT_LIMIT_TAG, rest)) => {
+                        compute_unit_limit = Some(u32::from_le_bytes(rest.try_into().map_err(|_| not_allowed())?));
+                    }
+                    Some((&SET_COMPUTE_UNIT_PRICE_TAG, rest)) => {
+                        compute_unit_price = u64::from_le_bytes(rest.try_into().map_err(|_| not_allowed())?);
+                    }
+                    _ => {}
+                }
+                0
+            } else if program == associated_token_program_id() {
+                // Create and CreateIdempotent, the payer funds the new account
+                match data {
+                    [] | [0] | [1] => TOKEN_ACCOUNT_RENT_LAMPORTS,
+                    _ => return Err(not_allowed()),
+                }
+            } else if program == pump_program_id() {
+                if data.starts_with(&BUY_DISCRIMINATOR) {
+                    // Buy data is discriminator, token amount, then max_sol_cost
+                    let max_sol_cost = data.get(16..24).ok_or_else(not_allowed)?;
+                    u64::from_le_bytes(max_sol_cost.try_into().map_err(|_| not_allowed())?)
+                } else if data.starts_with(&SELL_DISCRIMINATOR) {
+                    0
+                } else {
+                    return Err(not_allowed());
+                }
+            } else {
+                return Err(PolicyError::ProgramNotAllowed(program));
+            };
+            at_risk = at_risk.saturating_add(lamports);
+        }
+
+        // Without a limit the runtime default is lower, the maximum keeps this an upper bound
+        let compute_units = compute_unit_limit.unwrap_or(MAX_COMPUTE_UNIT_LIMIT);
+        let fees = (required_signatures as u64 * BASE_FEE_LAMPORTS_PER_SIGNATURE)
+            .saturating_add(priority_fee_lamports(compute_units, compute_unit_price));
+        at_risk = at_risk.saturating_add(fees);
+        if at_risk > self.max_transaction_lamports {
+            return Err(PolicyError::TransactionTooLarge { lamports: at_risk, limit: self.max_transaction_lamports });
+        }
+        Ok(at_risk)
+    }
+}
+
+/// Lamports signed for on one UTC day, persisted so a restart does not reset the limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpendLedger {
+    /// Days since the Unix epoch
+    pub day: u64,
+    pub spent_lamports: u64,
+}
+
+impl SpendLedger {
+    /// An empty ledger when the file does not exist yet.
+    pub fn load_or_default(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path).with_context(|| format!("reading spend ledger {}", path.display()))?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn spent_on(&self, day: u64) -> u64 {
+        if self.day == day {
+            self.spent_lamports
+        } else {
+            0
+        }
+    }
+
+    /// Records `lamports` against `day`, refusing if that would go over `limit`.
+    pub fn reserve(&mut self, day: u64, lamports: u64, limit: u64) -> Result<(), PolicyError> {
+        let spent = self.spent_on(day);
+        if spent.saturating_add(lamports) > limit {
+            return Err(PolicyError::DailyLimit { spent, requested: lamports, limit });
+        }
+        *self = Self { day, spent_lamports: spent + lamports };
+        Ok(())
+    }
+}
+
+/// One request per line on the signer socket.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum SignerRequest {
+    Pubkey,
+    /// Base64 encoded serialized message, exactly the bytes that get signed
+    Sign { message: String },
+}
+
+/// One response line per request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum SignerResponse {
+    Pubkey { pubkey: String },
+    Signature { signature: String },
+    /// The message was refused by the policy
+    Denied { reason: String },
+    Error { message: String },
+}
+
+/// Holds the trading key and signs only messages its policy allows, over a Unix socket.
+#[derive(Debug)]
+pub struct SignerDaemon {
+    keypair: Keypair,
+    policy: SignerPolicy,
+    ledger: Mutex<SpendLedger>,
+    ledger_path: Option<PathBuf>,
+}
+
+impl SignerDaemon {
+    /// Picks up today's spend from `ledger_path` when given.
+    pub fn new(keypair: Keypair, policy: SignerPolicy, ledger_path: Option<PathBuf>) -> Result<Self> {
+        let ledger = match &ledger_path {
+            Some(path) => SpendLedger::load_or_default(path)?,
+            None => SpendLedger::default(),
+        };
+        Ok(Self { keypair, policy, ledger: Mutex::new(ledger), ledger_path })
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    pub fn policy(&self) -> SignerPolicy {
+        self.policy
+    }
+
+    /// Lamports signed for today.
+    pub fn spent_today(&self) -> u64 {
+        self.ledger.lock().unwrap().spent_on(current_day())
+    }
+
+    pub fn handle(&self, request: SignerRequest) -> SignerResponse {
+        self.handle_on(request, current_day())
+    }
+
+    fn handle_on(&self, request: SignerRequest, day: u64) -> SignerResponse {
+        match request {
+            SignerRequest::Pubkey => SignerResponse::Pubkey { pubkey: self.pubkey().to_string() },
+            SignerRequest::Sign { message } => match self.sign(&message, day) {
+                Ok(signature) => SignerResponse::Signature { signature: signature.to_string() },
+                Err(err) => match err.downcast_ref::<PolicyError>() {
+                    Some(denied) => {
+                        println!("⛔ Denied: {}", denied);
+                        SignerResponse::Denied { reason: denied.to_string() }
+                    }
+                    None => SignerResponse::Error { message: err.to_string() },
+                },
+            },
+        }
+    }
+
+    // The spend is recorded before signing: the daemon cannot tell whether a signed
+    // transaction lands, so every signature counts
+    fn sign(&self, encoded: &str, day: u64) -> Result<Signature> {
+        let bytes = BASE64.decode(encoded)?;
+        let message: VersionedMessage =
+            bincode::deserialize(&bytes).map_err(|err| PolicyError::MalformedMessage(err.to_string()))?;
+        // Trailing or non canonical bytes would be signed without being checked
+        if message.serialize() != bytes {
+            return Err(PolicyError::MalformedMessage("message is not canonically encoded".to_string()).into());
+        }
+        let lamports = self.policy.lamports_at_risk(&message, &self.pubkey())?;
+
+        let mut ledger = self.ledger.lock().unwrap();
+        let previous = *ledger;
+        ledger.reserve(day, lamports, self.policy.daily_limit_lamports)?;
+        if let Some(path) = &self.ledger_path {
+            if let Err(err) = ledger.save(path) {
+                *ledger = previous;
+                return Err(err.context("recording the spend"));
+            }
+        }
+        println!(
+            "✍️ Signed a transaction risking {} lamports, {} of {} signed today",
+            lamports, ledger.spent_lamports, self.policy.daily_limit_lamports
+        );
+        Ok(self.keypair.sign_message(&bytes))
+    }
+
+    /// Listens on `socket_path` until the process exits. The socket is only accessible to
+    /// the daemon's user, a stale socket from an earlier run is replaced.
+    pub async fn serve(self: Arc<Self>, socket_path: &Path) -> Result<()> {
+        if let Ok(metadata) = fs::symlink_metadata(socket_path) {
+            if !metadata.file_type().is_socket() {
+                return Err(anyhow!("{} exists and is not a socket", socket_path.display()));
+            }
+            fs::remove_file(socket_path)?;
+        }
+        let listener = UnixListener::bind(socket_path)?;
+        fs::set_permissions(socket_path, fs::Permissions::from_mode(0o600))?;
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let daemon = self.clone();
+            tokio::spawn(async move {
+                if let Err(err) = daemon.serve_connection(stream).await {
+                    eprintln!("⚠️ Signer connection closed: {}", err);
+                }
+            });
+        }
+    }
+
+    async fn serve_connection(&self, stream: AsyncUnixStream) -> Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = AsyncBufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            let response = match serde_json::from_str(&line) {
+                Ok(request) => self.handle(request),
+                Err(err) => SignerResponse::Error { message: format!("invalid request: {}", err) },
+            };
+            let mut encoded = serde_json::to_vec(&response)?;
+            encoded.push(b'\n');
+            writer.write_all(&encoded).await?;
+        }
+        Ok(())
+    }
+}
+
+fn current_day() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs() / SECONDS_PER_DAY).unwrap_or_default()
+}
+
+/// A `Signer` whose key lives in a `SignerDaemon`. Each signature is a blocking round trip
+/// over the daemon's socket, which is local and answers in well under a millisecond.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    socket_path: PathBuf,
+    pubkey: Pubkey,
+}
+
+impl RemoteSigner {
+    /// Asks the daemon at `socket_path` for its public key.
+    pub fn connect(socket_path: impl Into<PathBuf>) -> Result<Self> {
+        let socket_path = socket_path.into();
+        match call(&socket_path, &SignerRequest::Pubkey)? {
+            SignerResponse::Pubkey { pubkey } => Ok(Self { socket_path, pubkey: Pubkey::from_str(&pubkey)? }),
+            other => Err(anyhow!("unexpected signer response: {:?}", other)),
+        }
+    }
+
+    /// `SIGNER_SOCKET`, or `None` when no signer daemon is configured.
+    pub fn from_env() -> Result<Option<Self>> {
+        env::var("SIGNER_SOCKET").ok().map(Self::connect).transpose()
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        let request = SignerRequest::Sign { message: BASE64.encode(message) };
+        match call(&self.socket_path, &request).map_err(|err| SignerError::Connection(err.to_string()))? {
+            SignerResponse::Signature { signature } => {
+                let signature = Signature::from_str(&signature).map_err(|err| SignerError::Custom(err.to_string()))?;
+                if !signature.verify(self.pubkey.as_ref(), message) {
+                    return Err(SignerError::Custom("signer daemon returned an invalid signature".to_string()));
+                }
+                Ok(signature)
+            }
+            SignerResponse::Denied { reason } => Err(SignerError::Custom(format!("signer daemon denied the transaction: {}", reason))),
+            SignerResponse::Error { message } => Err(SignerError::Custom(message)),
+            other => Err(SignerError::Custom(format!("unexpected signer response: {:?}", other))),
+        }
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+/// Runs `sign` from async code. `Signer` is synchronous and a `RemoteSigner` blocks on the
+/// daemon socket, so on a multi-thread runtime the worker hands its other tasks off first.
+/// Elsewhere, like a current-thread runtime where that is not possible, `sign` runs inline.
+pub fn sign_blocking<T>(sign: impl FnOnce() -> T) -> T {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => task::block_in_place(sign),
+        _ => sign(),
+    }
+}
+
+fn call(socket_path: &Path, request: &SignerRequest) -> Result<SignerResponse> {
+    let mut stream = UnixStream::connect(socket_path)
+        .with_context(|| format!("connecting to the signer daemon at {}", socket_path.display()))?;
+    stream.set_read_timeout(Some(SOCKET_TIMEOUT))?;
+    stream.set_write_timeout(Some(SOCKET_TIMEOUT))?;
+    let mut encoded = serde_json::to_vec(request)?;
+    encoded.push(b'\n');
+    stream.write_all(&encoded)?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::core::{instructions::create_buy_instruction, transaction::create_compute_budget_instructions},
+        solana_sdk::{hash::Hash, message::Message, system_instruction, transaction::Transaction},
+        spl_associated_token_account::instruction::create_associated_token_account_idempotent,
+    };
+
+    fn buy_message(payer: &Pubkey, max_sol_cost: u64, tip: u64) -> VersionedMessage {
+        let mint = Pubkey::new_unique();
+        let token_account = Pubkey::new_unique();
+        let mut instructions = create_compute_budget_instructions(100_000, 1_000_000);
+        instructions.push(create_associated_token_account_idempotent(payer, payer, &mint, &spl_token::id()));
+        let accounts: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        instructions.push(
+            create_buy_instruction(payer, &mint, &token_account, &accounts[0], &accounts[1], &accounts[2], &accounts[3], 1_000, max_sol_cost)
+                .unwrap(),
+        );
+        instructions.push(system_instruction::transfer(payer, &Pubkey::new_unique(), tip));
+        VersionedMessage::Legacy(Message::new(&instructions, Some(payer)))
+    }
+
+    fn legacy(instructions: &[solana_sdk::instruction::Instruction], payer: &Pubkey) -> VersionedMessage {
+        VersionedMessage::Legacy(Message::new(instructions, Some(payer)))
+    }
+
+    #[test]
+    fn test_lamports_at_risk() {
+        let policy = SignerPolicy::default();
+        let payer = Pubkey::new_unique();
+        // Buy cost, token account rent, tip, one signature and 100k CU at 1 lamport per CU
+        let at_risk = policy.lamports_at_risk(&buy_message(&payer, 50_000_000, 10_000), &payer).unwrap();
+        assert_eq!(at_risk, 50_000_000 + TOKEN_ACCOUNT_RENT_LAMPORTS + 10_000 + 5_000 + 100_000);
+
+        // Over the per transfer and per transaction limits
+        assert_eq!(
+            policy.lamports_at_risk(&buy_message(&payer, 50_000_000, 20_000_000), &payer),
+            Err(PolicyError::TransferTooLarge { lamports: 20_000_000, limit: 10_000_000 })
+        );
+        assert!(matches!(
+            policy.lamports_at_risk(&buy_message(&payer, 2_000_000_000, 10_000), &payer),
+            Err(PolicyError::TransactionTooLarge { .. })
+        ));
+        // Not signed by the daemon's key
+        let other = Pubkey::new_unique();
+        assert_eq!(policy.lamports_at_risk(&buy_message(&payer, 1, 1), &other), Err(PolicyError::NotASigner(other)));
+    }
+
+    #[test]
+    fn test_disallowed_instructions() {
+        let policy = SignerPolicy::default();
+        let payer = Pubkey::new_unique();
+
+        // Token transfers could drain the wallet's holdings
+        let token_account = Pubkey::new_unique();
+        let drain = spl_token::instruction::transfer(&spl_token::id(), &token_account, &Pubkey::new_unique(), &payer, &[], 1).unwrap();
+        assert_eq!(policy.lamports_at_risk(&legacy(&[drain], &payer), &payer), Err(PolicyError::ProgramNotAllowed(spl_token::id())));
+
+        // Only transfers and nonce advances from the system program
+        let assign = system_instruction::assign(&payer, &Pubkey::new_unique());
+        assert_eq!(
+            policy.lamports_at_risk(&legacy(&[assign], &payer), &payer),
+            Err(PolicyError::InstructionNotAllowed { index: 0, program: system_program::id() })
+        );
+        let advance = system_instruction::advance_nonce_account(&Pubkey::new_unique(), &payer);
+        assert_eq!(policy.lamports_at_risk(&legacy(&[advance], &payer), &payer), Ok(5_000));
+
+        // Pump instructions other than buy and sell
+        let mut withdraw = create_buy_instruction(&payer, &Pubkey::new_unique(), &token_account, &token_account, &token_account, &token_account, &token_account, 1, 1).unwrap();
+        withdraw.data[..8].copy_from_slice(&[0xb7, 0x12, 0x46, 0x9c, 0x94, 0x6d, 0xa1, 0x22]);
+        assert_eq!(
+            policy.lamports_at_risk(&legacy(&[withdraw], &payer), &payer),
+            Err(PolicyError::InstructionNotAllowed { index: 0, program: pump_program_id() })
+        );
+    }
+
+    #[test]
+    fn test_daily_limit() {
+        let keypair = Keypair::new();
+        let payer = keypair.pubkey();
+        let directory = tempfile::tempdir().unwrap();
+        let ledger_path = directory.path().join("spend.json");
+        let policy = SignerPolicy { daily_limit_lamports: 120_000_000, ..SignerPolicy::default() };
+        let daemon = SignerDaemon::new(keypair.insecure_clone(), policy, Some(ledger_path.clone())).unwrap();
+
+        let message = buy_message(&payer, 50_000_000, 10_000);
+        let request = SignerRequest::Sign { message: BASE64.encode(message.serialize()) };
+        let SignerResponse::Signature { signature } = daemon.handle_on(request.clone(), 100) else {
+            panic!("expected a signature");
+        };
+        assert!(Signature::from_str(&signature).unwrap().verify(payer.as_ref(), &message.serialize()));
+        assert!(matches!(daemon.handle_on(request.clone(), 100), SignerResponse::Signature { .. }));
+        // A third buy would go over the limit
+        assert!(matches!(daemon.handle_on(request.clone(), 100), SignerResponse::Denied { .. }));
+
+        // The spend survives a restart and resets the next day
+        let restarted = SignerDaemon::new(keypair, policy, Some(ledger_path)).unwrap();
+        assert!(matches!(restarted.handle_on(request.clone(), 100), SignerResponse::Denied { .. }));
+        assert!(matches!(restarted.handle_on(request, 101), SignerResponse::Signature { .. }));
+
+        // Bytes that are not a message are refused rather than signed blindly
+        let garbage = SignerRequest::Sign { message: BASE64.encode([0xff; 8]) };
+        assert!(matches!(daemon.handle_on(garbage, 100), SignerResponse::Denied { .. }));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_remote_signer_round_trip() {
+        let keypair = Keypair::new();
+        let payer = keypair.pubkey();
+        let directory = tempfile::tempdir().unwrap();
+        let socket_path = directory.path().join("signer.sock");
+        let daemon = Arc::new(SignerDaemon::new(keypair, SignerPolicy::default(), None).unwrap());
+        let server_path = socket_path.clone();
+        tokio::spawn(async move { daemon.serve(&server_path).await });
+        while !socket_path.exists() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // Called from the test's worker like the send path does, the daemon task keeps running
+        let signer = sign_blocking(|| RemoteSigner::connect(&socket_path)).unwrap();
+        assert_eq!(signer.pubkey(), payer);
+
+        let transfer = system_instruction::transfer(&payer, &Pubkey::new_unique(), 10_000);
+        let mut transaction = Transaction::new_with_payer(&[transfer], Some(&payer));
+        sign_blocking(|| transaction.try_sign(&[&signer as &dyn Signer], Hash::new_unique())).unwrap();
+        transaction.verify().unwrap();
+
+        let drain = system_instruction::transfer(&payer, &Pubkey::new_unique(), 1_000_000_000);
+        let mut transaction = Transaction::new_with_payer(&[drain], Some(&payer));
+        let err = sign_blocking(|| transaction.try_sign(&[&signer as &dyn Signer], Hash::new_unique())).unwrap_err();
+        assert!(err.to_string().contains("denied"));
+    }
+
+    #[test]
+    fn test_sign_blocking_without_a_multi_thread_runtime() {
+        // `block_in_place` would panic on a current-thread runtime, signing runs inline there
+        assert_eq!(sign_blocking(|| 1), 1);
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        assert_eq!(runtime.block_on(async { sign_blocking(|| 2) }), 2);
+    }
+}
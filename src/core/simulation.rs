@@ -0,0 +1,410 @@
+use {
+    anyhow::Result,
+    base64::{engine::general_purpose::STANDARD as BASE64, Engine},
+    solana_account_decoder::{UiAccount, UiAccountEncoding},
+    solana_client::{
+        nonblocking::rpc_client::RpcClient,
+        rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig},
+    },
+    solana_sdk::{
+        account::Account,
+        commitment_config::CommitmentConfig,
+        instruction::Instruction,
+        message::Message,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        transaction::{Transaction, TransactionError},
+    },
+    solana_transaction_status::{UiInnerInstructions, UiInstruction, UiParsedInstruction},
+    crate::{
+        core::token_price::{check_discriminator, read_pubkey, read_u64, AccountDecodeError, BondingCurveAccount},
+        utils::utils::format_sol_amount,
+    },
+};
+
+// Anchor event discriminator: sha256("event:TradeEvent")[..8]
+pub const TRADE_EVENT_DISCRIMINATOR: [u8; 8] = [0xbd, 0xdb, 0x7f, 0xd3, 0x4e, 0xe6, 0x61, 0xee];
+
+// Anchor `emit_cpi!` prefixes self-CPI data with sha256("anchor:event")[..8], little endian
+const EVENT_IX_TAG_LE: [u8; 8] = [0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d];
+
+// discriminator + mint + sol/token amounts + is_buy + user + timestamp + 4 x reserves
+pub const TRADE_EVENT_LEN: usize = 8 + 32 + 8 + 8 + 1 + 32 + 8 + 4 * 8;
+
+const PROGRAM_DATA_LOG_PREFIX: &str = "Program data: ";
+
+/// Decoded pump.fun `TradeEvent`, emitted by every buy and sell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradeEvent {
+    pub mint: Pubkey,
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub is_buy: bool,
+    pub user: Pubkey,
+    pub timestamp: i64,
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub real_token_reserves: u64,
+}
+
+impl TradeEvent {
+    /// Decodes the event data (discriminator first). Fields appended by later program versions are ignored.
+    pub fn from_event_data(data: &[u8]) -> Result<Self, AccountDecodeError> {
+        check_discriminator(data, TRADE_EVENT_DISCRIMINATOR, TRADE_EVENT_LEN)?;
+
+        Ok(Self {
+            mint: read_pubkey(data, 8),
+            sol_amount: read_u64(data, 40),
+            token_amount: read_u64(data, 48),
+            is_buy: data[56] != 0,
+            user: read_pubkey(data, 57),
+            timestamp: read_u64(data, 89) as i64,
+            virtual_sol_reserves: read_u64(data, 97),
+            virtual_token_reserves: read_u64(data, 105),
+            real_sol_reserves: read_u64(data, 113),
+            real_token_reserves: read_u64(data, 121),
+        })
+    }
+
+    /// Finds the first trade event in `Program data:` log lines (`emit!`).
+    pub fn find_in_logs(logs: &[String]) -> Option<Self> {
+        logs.iter()
+            .filter_map(|log| log.strip_prefix(PROGRAM_DATA_LOG_PREFIX))
+            .filter_map(|data| BASE64.decode(data).ok())
+            .find_map(|data| Self::from_event_data(&data).ok())
+    }
+
+    /// Finds the first trade event in self-CPI inner instructions (`emit_cpi!`).
+    pub fn find_in_inner_instructions(inner_instructions: &[UiInnerInstructions]) -> Option<Self> {
+        inner_instructions
+            .iter()
+            .flat_map(|inner| &inner.instructions)
+            .filter_map(|instruction| match instruction {
+                UiInstruction::Compiled(compiled) => Some(&compiled.data),
+                UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(decoded)) => Some(&decoded.data),
+                UiInstruction::Parsed(UiParsedInstruction::Parsed(_)) => None,
+            })
+            .filter_map(|data| bs58::decode(data).into_vec().ok())
+            .filter_map(|data| data.strip_prefix(&EVENT_IX_TAG_LE).map(<[u8]>::to_vec))
+            .find_map(|data| Self::from_event_data(&data).ok())
+    }
+}
+
+/// Accounts whose state is captured before and after a simulated trade.
+#[derive(Debug, Clone, Copy)]
+pub struct SimulationAccounts {
+    pub wallet: Pubkey,
+    pub token_account: Pubkey,
+    pub bonding_curve: Pubkey,
+}
+
+impl SimulationAccounts {
+    fn addresses(&self) -> [Pubkey; 3] {
+        [self.wallet, self.token_account, self.bonding_curve]
+    }
+}
+
+/// State of the watched accounts at one point in time. Missing accounts read as empty.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccountSnapshot {
+    pub wallet_lamports: u64,
+    pub token_balance: u64,
+    pub curve: Option<BondingCurveAccount>,
+}
+
+impl AccountSnapshot {
+    fn from_accounts(wallet: Option<&Account>, token_account: Option<&Account>, curve: Option<&Account>) -> Self {
+        Self {
+            wallet_lamports: wallet.map(|account| account.lamports).unwrap_or_default(),
+            token_balance: token_account
+                .and_then(|account| spl_token::state::Account::unpack(&account.data).ok())
+                .map(|account| account.amount)
+                .unwrap_or_default(),
+            curve: curve.and_then(|account| BondingCurveAccount::from_account_data(&account.data).ok()),
+        }
+    }
+}
+
+/// Result of simulating a trade without sending it.
+#[derive(Debug, Clone)]
+pub struct SimulationReport {
+    pub err: Option<TransactionError>,
+    pub units_consumed: Option<u64>,
+    pub logs: Vec<String>,
+    pub before: AccountSnapshot,
+    pub after: AccountSnapshot,
+    pub trade_event: Option<TradeEvent>,
+}
+
+impl SimulationReport {
+    /// Change in wallet lamports (negative when SOL left the wallet).
+    pub fn sol_delta(&self) -> i128 {
+        self.after.wallet_lamports as i128 - self.before.wallet_lamports as i128
+    }
+
+    /// Change in the user's token balance, in raw token units.
+    pub fn token_delta(&self) -> i128 {
+        self.after.token_balance as i128 - self.before.token_balance as i128
+    }
+}
+
+/// Simulates `instructions` paid by `payer` unsigned, against the latest blockhash,
+/// capturing the watched accounts before and after. Nothing is signed or sent, so a remote
+/// signer is never asked and no valid transaction exists afterwards.
+pub async fn simulate_transaction(
+    rpc_client: &RpcClient,
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    accounts: &SimulationAccounts,
+) -> Result<SimulationReport> {
+    let addresses = accounts.addresses();
+    let before = rpc_client.get_multiple_accounts(&addresses).await?;
+    let before = AccountSnapshot::from_accounts(before[0].as_ref(), before[1].as_ref(), before[2].as_ref());
+
+    let transaction = Transaction::new_unsigned(Message::new(instructions, Some(payer)));
+    let result = rpc_client
+        .simulate_transaction_with_config(
+            &transaction,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(CommitmentConfig::confirmed()),
+                encoding: None,
+                accounts: Some(RpcSimulateTransactionAccountsConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    addresses: addresses.iter().map(Pubkey::to_string).collect(),
+                }),
+                min_context_slot: None,
+                inner_instructions: true,
+            },
+        )
+        .await?
+        .value;
+
+    let after: Vec<Option<Account>> = result
+        .accounts
+        .unwrap_or_default()
+        .iter()
+        .map(|account| account.as_ref().and_then(UiAccount::decode))
+        .collect();
+    let after = match after.as_slice() {
+        [wallet, token_account, curve] => {
+            AccountSnapshot::from_accounts(wallet.as_ref(), token_account.as_ref(), curve.as_ref())
+        }
+        // Failed simulations return no account state
+        _ => before,
+    };
+
+    let logs = result.logs.unwrap_or_default();
+    let trade_event = result
+        .inner_instructions
+        .as_deref()
+        .and_then(TradeEvent::find_in_inner_instructions)
+        .or_else(|| TradeEvent::find_in_logs(&logs));
+
+    Ok(SimulationReport {
+        err: result.err,
+        units_consumed: result.units_consumed,
+        logs,
+        before,
+        after,
+        trade_event,
+    })
+}
+
+pub fn print_simulation_report(report: &SimulationReport) {
+    println!("\n🧪 Simulation result (nothing was sent)");
+    match &report.err {
+        Some(err) => println!("❌ Simulation failed: {}", err),
+        None => println!("✅ Simulation succeeded"),
+    }
+    if let Some(units) = report.units_consumed {
+        println!("⚙️ Compute units consumed: {}", units);
+    }
+
+    let sol_delta = report.sol_delta();
+    let sign = if sol_delta < 0 { "-" } else { "+" };
+    println!("💰 SOL delta: {}{}", sign, format_sol_amount(sol_delta.unsigned_abs() as u64));
+    println!("🪙 Token delta: {:+} tokens", report.token_delta() as f64 / 1_000_000.0);
+    if let (Some(before), Some(after)) = (report.before.curve, report.after.curve) {
+        println!(
+            "📈 Curve reserves: {} -> {} lamports, {} -> {} tokens",
+            before.virtual_sol_reserves, after.virtual_sol_reserves,
+            before.virtual_token_reserves, after.virtual_token_reserves,
+        );
+    }
+
+    match &report.trade_event {
+        Some(event) => {
+            println!("\n📣 TradeEvent:");
+            println!("  Side: {}", if event.is_buy { "buy" } else { "sell" });
+            println!("  Mint: {}", event.mint);
+            println!("  User: {}", event.user);
+            println!("  SOL amount: {}", format_sol_amount(event.sol_amount));
+            println!("  Token amount: {} tokens", event.token_amount as f64 / 1_000_000.0);
+            println!("  Virtual reserves: {} lamports, {} tokens", event.virtual_sol_reserves, event.virtual_token_reserves);
+            println!("  Real reserves: {} lamports, {} tokens", event.real_sol_reserves, event.real_token_reserves);
+        }
+        None => println!("\n📣 No TradeEvent emitted"),
+    }
+
+    println!("\n📜 Program logs:");
+    for log in &report.logs {
+        println!("  {}", log);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        serde_json::json,
+        solana_client::rpc_request::RpcRequest,
+        solana_sdk::program_option::COption,
+        spl_token::state::AccountState,
+        std::collections::HashMap,
+    };
+
+    fn sample_event() -> TradeEvent {
+        TradeEvent {
+            mint: Pubkey::new_unique(),
+            sol_amount: 28_264_927,
+            token_amount: 1_000_000_000,
+            is_buy: true,
+            user: Pubkey::new_unique(),
+            timestamp: 1_750_000_000,
+            virtual_sol_reserves: 30_027_985_075,
+            virtual_token_reserves: 1_072_000_000_000_000,
+            real_sol_reserves: 27_985_075,
+            real_token_reserves: 792_100_000_000_000,
+        }
+    }
+
+    fn encode_event(event: &TradeEvent) -> Vec<u8> {
+        let mut data = vec![];
+        data.extend_from_slice(&TRADE_EVENT_DISCRIMINATOR);
+        data.extend_from_slice(event.mint.as_ref());
+        data.extend_from_slice(&event.sol_amount.to_le_bytes());
+        data.extend_from_slice(&event.token_amount.to_le_bytes());
+        data.push(event.is_buy as u8);
+        data.extend_from_slice(event.user.as_ref());
+        data.extend_from_slice(&event.timestamp.to_le_bytes());
+        data.extend_from_slice(&event.virtual_sol_reserves.to_le_bytes());
+        data.extend_from_slice(&event.virtual_token_reserves.to_le_bytes());
+        data.extend_from_slice(&event.real_sol_reserves.to_le_bytes());
+        data.extend_from_slice(&event.real_token_reserves.to_le_bytes());
+        data
+    }
+
+    fn token_account_data(mint: Pubkey, owner: Pubkey, amount: u64) -> Vec<u8> {
+        let account = spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        };
+        let mut data = vec![0u8; spl_token::state::Account::LEN];
+        account.pack_into_slice(&mut data);
+        data
+    }
+
+    fn ui_account(lamports: u64, data: &[u8]) -> serde_json::Value {
+        json!({
+            "lamports": lamports,
+            "data": [BASE64.encode(data), "base64"],
+            "owner": Pubkey::default().to_string(),
+            "executable": false,
+            "rentEpoch": 0,
+            "space": data.len(),
+        })
+    }
+
+    #[test]
+    fn test_decode_trade_event() {
+        let event = sample_event();
+        let mut data = encode_event(&event);
+        assert_eq!(data.len(), TRADE_EVENT_LEN);
+        assert_eq!(TradeEvent::from_event_data(&data), Ok(event));
+
+        // Newer program versions append fee fields
+        data.extend_from_slice(&[0u8; 32]);
+        assert_eq!(TradeEvent::from_event_data(&data), Ok(event));
+
+        data[0] ^= 0xff;
+        assert!(matches!(
+            TradeEvent::from_event_data(&data),
+            Err(AccountDecodeError::DiscriminatorMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_find_trade_event_in_logs_and_inner_instructions() {
+        let event = sample_event();
+        let logs = vec![
+            "Program log: Instruction: Buy".to_string(),
+            format!("{}{}", PROGRAM_DATA_LOG_PREFIX, BASE64.encode(b"not an event")),
+            format!("{}{}", PROGRAM_DATA_LOG_PREFIX, BASE64.encode(encode_event(&event))),
+        ];
+        assert_eq!(TradeEvent::find_in_logs(&logs), Some(event));
+        assert_eq!(TradeEvent::find_in_logs(&logs[..2]), None);
+
+        let mut cpi_data = EVENT_IX_TAG_LE.to_vec();
+        cpi_data.extend_from_slice(&encode_event(&event));
+        let inner: Vec<UiInnerInstructions> = serde_json::from_value(json!([{
+            "index": 3,
+            "instructions": [
+                {"programIdIndex": 5, "accounts": [], "data": bs58::encode([2u8, 0, 0, 0]).into_string(), "stackHeight": 2},
+                {"programIdIndex": 9, "accounts": [8], "data": bs58::encode(cpi_data).into_string(), "stackHeight": 2},
+            ],
+        }]))
+        .unwrap();
+        assert_eq!(TradeEvent::find_in_inner_instructions(&inner), Some(event));
+    }
+
+    #[tokio::test]
+    async fn test_simulate_reports_deltas() {
+        let wallet = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let accounts = SimulationAccounts {
+            wallet,
+            token_account: Pubkey::new_unique(),
+            bonding_curve: Pubkey::new_unique(),
+        };
+        let event = sample_event();
+
+        let mut mocks = HashMap::new();
+        // The user ATA does not exist before the first buy
+        mocks.insert(
+            RpcRequest::GetMultipleAccounts,
+            json!({"context": {"slot": 1}, "value": [ui_account(1_000_000_000, &[]), null, null]}),
+        );
+        mocks.insert(
+            RpcRequest::SimulateTransaction,
+            json!({"context": {"slot": 1}, "value": {
+                "err": null,
+                "logs": [format!("{}{}", PROGRAM_DATA_LOG_PREFIX, BASE64.encode(encode_event(&event)))],
+                "accounts": [
+                    ui_account(969_700_000, &[]),
+                    ui_account(2_039_280, &token_account_data(mint, wallet, 1_000_000_000)),
+                    null,
+                ],
+                "unitsConsumed": 61_234,
+                "returnData": null,
+                "innerInstructions": [],
+            }}),
+        );
+        let rpc = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+
+        let report = simulate_transaction(&rpc, &wallet, &[], &accounts).await.unwrap();
+        assert_eq!(report.err, None);
+        assert_eq!(report.units_consumed, Some(61_234));
+        assert_eq!(report.sol_delta(), -30_300_000);
+        assert_eq!(report.token_delta(), 1_000_000_000);
+        assert_eq!(report.trade_event, Some(event));
+    }
+}
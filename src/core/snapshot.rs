@@ -0,0 +1,284 @@
+use {
+    anyhow::Result,
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{account::Account, instruction::Instruction, program_pack::Pack, pubkey::Pubkey},
+    crate::{
+        core::{
+            global::GlobalAccount,
+            instructions::{create_buy_instruction, create_sell_instruction},
+            token_price::{AccountDecodeError, BondingCurveAccount},
+        },
+        utils::{
+            config::{find_associated_bonding_curve_pda, find_bonding_curve_pda, global_pda},
+            utils::get_token_account,
+        },
+    },
+};
+
+/// Errors returned when a fetched snapshot cannot be used for a trade.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SnapshotError {
+    #[error("{name} account {address} does not exist")]
+    MissingAccount { name: &'static str, address: Pubkey },
+    #[error("failed to decode {name} account: {source}")]
+    Decode { name: &'static str, source: AccountDecodeError },
+    #[error("user token account {0} is not a valid SPL token account")]
+    InvalidTokenAccount(Pubkey),
+}
+
+/// Addresses a trade on one token touches, derived from the wallet and the mint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradeAddresses {
+    pub wallet: Pubkey,
+    pub token_mint: Pubkey,
+    pub token_account: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub associated_bonding_curve: Pubkey,
+}
+
+impl TradeAddresses {
+    pub fn derive(wallet: &Pubkey, token_mint: &Pubkey) -> Self {
+        let (bonding_curve, _) = find_bonding_curve_pda(token_mint);
+        let (associated_bonding_curve, _) = find_associated_bonding_curve_pda(token_mint, &bonding_curve);
+        Self {
+            wallet: *wallet,
+            token_mint: *token_mint,
+            token_account: get_token_account(wallet, token_mint),
+            bonding_curve,
+            associated_bonding_curve,
+        }
+    }
+
+    // Order of the accounts in the getMultipleAccounts request
+    fn snapshot_keys(&self) -> [Pubkey; 4] {
+        [self.bonding_curve, global_pda(), self.token_account, self.wallet]
+    }
+}
+
+/// Everything a buy or sell needs, read in a single `getMultipleAccounts` round trip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradeSnapshot {
+    pub addresses: TradeAddresses,
+    /// Slot the accounts were read at
+    pub slot: u64,
+    pub curve: BondingCurveAccount,
+    pub global: GlobalAccount,
+    /// Raw token balance, `None` if the user token account does not exist yet
+    pub token_balance: Option<u64>,
+    pub wallet_lamports: u64,
+}
+
+impl TradeSnapshot {
+    /// Fetches the bonding curve, Global account, user token account and wallet in one request.
+    pub async fn fetch(rpc_client: &RpcClient, wallet: &Pubkey, token_mint: &Pubkey) -> Result<Self> {
+        let addresses = TradeAddresses::derive(wallet, token_mint);
+        let response = rpc_client
+            .get_multiple_accounts_with_commitment(&addresses.snapshot_keys(), rpc_client.commitment())
+            .await?;
+        Ok(Self::from_accounts(addresses, response.context.slot, &response.value)?)
+    }
+
+    /// Decodes accounts returned in `TradeAddresses` snapshot order.
+    pub fn from_accounts(
+        addresses: TradeAddresses,
+        slot: u64,
+        accounts: &[Option<Account>],
+    ) -> Result<Self, SnapshotError> {
+        let account = |index: usize| accounts.get(index).and_then(Option::as_ref);
+        let [curve_key, global_key, token_account_key, _] = addresses.snapshot_keys();
+
+        let curve = account(0)
+            .ok_or(SnapshotError::MissingAccount { name: "bonding curve", address: curve_key })?;
+        let curve = BondingCurveAccount::from_account_data(&curve.data)
+            .map_err(|source| SnapshotError::Decode { name: "bonding curve", source })?;
+
+        let global = account(1)
+            .ok_or(SnapshotError::MissingAccount { name: "global", address: global_key })?;
+        let global = GlobalAccount::from_account_data(&global.data)
+            .map_err(|source| SnapshotError::Decode { name: "global", source })?;
+
+        let token_balance = account(2)
+            .map(|token_account| {
+                spl_token::state::Account::unpack(&token_account.data)
+                    .map(|token_account| token_account.amount)
+                    .map_err(|_| SnapshotError::InvalidTokenAccount(token_account_key))
+            })
+            .transpose()?;
+
+        // An unfunded wallet simply has no account
+        let wallet_lamports = account(3).map(|wallet| wallet.lamports).unwrap_or_default();
+
+        Ok(Self { addresses, slot, curve, global, token_balance, wallet_lamports })
+    }
+
+    /// Buy instruction for this token, paying fees to the Global account's fee recipient.
+    pub fn buy_instruction(&self, creator_vault: &Pubkey, token_amount: u64, max_sol_cost: u64) -> Result<Instruction> {
+        let addresses = &self.addresses;
+        create_buy_instruction(
+            &addresses.wallet,
+            &addresses.token_mint,
+            &addresses.token_account,
+            &addresses.bonding_curve,
+            &addresses.associated_bonding_curve,
+            creator_vault,
+            &self.global.fee_recipient,
+            token_amount,
+            max_sol_cost,
+        )
+    }
+
+    /// Sell instruction for this token, paying fees to the Global account's fee recipient.
+    pub fn sell_instruction(&self, creator_vault: &Pubkey, token_amount: u64, min_sol_output: u64) -> Result<Instruction> {
+        let addresses = &self.addresses;
+        create_sell_instruction(
+            &addresses.wallet,
+            &addresses.token_mint,
+            &addresses.token_account,
+            &addresses.bonding_curve,
+            &addresses.associated_bonding_curve,
+            creator_vault,
+            &self.global.fee_recipient,
+            token_amount,
+            min_sol_output,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::core::{
+            global::{GLOBAL_ACCOUNT_LEN, GLOBAL_DISCRIMINATOR},
+            token_price::{BONDING_CURVE_ACCOUNT_LEN, BONDING_CURVE_DISCRIMINATOR},
+        },
+        base64::{engine::general_purpose::STANDARD as BASE64, Engine},
+        serde_json::json,
+        solana_client::rpc_request::RpcRequest,
+        solana_sdk::program_option::COption,
+        spl_token::state::AccountState,
+        std::collections::HashMap,
+    };
+
+    fn curve_data(virtual_sol_reserves: u64, creator: &Pubkey) -> Vec<u8> {
+        let mut data = vec![0u8; BONDING_CURVE_ACCOUNT_LEN];
+        data[..8].copy_from_slice(&BONDING_CURVE_DISCRIMINATOR);
+        data[16..24].copy_from_slice(&virtual_sol_reserves.to_le_bytes());
+        data[49..81].copy_from_slice(creator.as_ref());
+        data
+    }
+
+    fn global_data(fee_recipient: &Pubkey) -> Vec<u8> {
+        let mut data = vec![0u8; GLOBAL_ACCOUNT_LEN];
+        data[..8].copy_from_slice(&GLOBAL_DISCRIMINATOR);
+        data[41..73].copy_from_slice(fee_recipient.as_ref());
+        data
+    }
+
+    fn token_account_data(addresses: &TradeAddresses, amount: u64) -> Vec<u8> {
+        let account = spl_token::state::Account {
+            mint: addresses.token_mint,
+            owner: addresses.wallet,
+            amount,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        };
+        let mut data = vec![0u8; spl_token::state::Account::LEN];
+        account.pack_into_slice(&mut data);
+        data
+    }
+
+    fn account(lamports: u64, data: Vec<u8>) -> Option<Account> {
+        Some(Account { lamports, data, ..Account::default() })
+    }
+
+    #[test]
+    fn test_decode_snapshot() {
+        let addresses = TradeAddresses::derive(&Pubkey::new_unique(), &Pubkey::new_unique());
+        let creator = Pubkey::new_unique();
+        let fee_recipient = Pubkey::new_unique();
+        let accounts = vec![
+            account(1, curve_data(30_000_000_000, &creator)),
+            account(1, global_data(&fee_recipient)),
+            account(2_039_280, token_account_data(&addresses, 5_000_000)),
+            account(1_000_000_000, vec![]),
+        ];
+
+        let snapshot = TradeSnapshot::from_accounts(addresses, 42, &accounts).unwrap();
+        assert_eq!(snapshot.slot, 42);
+        assert_eq!(snapshot.curve.virtual_sol_reserves, 30_000_000_000);
+        assert_eq!(snapshot.curve.creator, creator);
+        assert_eq!(snapshot.global.fee_recipient, fee_recipient);
+        assert_eq!(snapshot.token_balance, Some(5_000_000));
+        assert_eq!(snapshot.wallet_lamports, 1_000_000_000);
+
+        // Fee recipient and derived addresses flow into the instruction
+        let buy = snapshot.buy_instruction(&snapshot.curve.creator_vault(), 1_000, 2_000).unwrap();
+        assert_eq!(buy.accounts[1].pubkey, fee_recipient);
+        assert_eq!(buy.accounts[3].pubkey, addresses.bonding_curve);
+        assert_eq!(buy.accounts[5].pubkey, addresses.token_account);
+    }
+
+    #[test]
+    fn test_missing_accounts() {
+        let addresses = TradeAddresses::derive(&Pubkey::new_unique(), &Pubkey::new_unique());
+        let curve = account(1, curve_data(1, &Pubkey::new_unique()));
+        let global = account(1, global_data(&Pubkey::new_unique()));
+
+        // Missing token account and wallet are fine before the first buy
+        let snapshot = TradeSnapshot::from_accounts(addresses, 1, &[curve.clone(), global.clone(), None, None]).unwrap();
+        assert_eq!(snapshot.token_balance, None);
+        assert_eq!(snapshot.wallet_lamports, 0);
+
+        assert_eq!(
+            TradeSnapshot::from_accounts(addresses, 1, &[None, global.clone(), None, None]),
+            Err(SnapshotError::MissingAccount { name: "bonding curve", address: addresses.bonding_curve })
+        );
+        assert!(matches!(
+            TradeSnapshot::from_accounts(addresses, 1, &[curve.clone(), curve.clone(), None, None]),
+            Err(SnapshotError::Decode { name: "global", .. })
+        ));
+        assert_eq!(
+            TradeSnapshot::from_accounts(addresses, 1, &[curve, global, account(1, vec![1, 2, 3]), None]),
+            Err(SnapshotError::InvalidTokenAccount(addresses.token_account))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_uses_one_request() {
+        let wallet = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let ui_account = |lamports: u64, data: Vec<u8>| {
+            json!({
+                "lamports": lamports,
+                "data": [BASE64.encode(&data), "base64"],
+                "owner": Pubkey::default().to_string(),
+                "executable": false,
+                "rentEpoch": 0,
+                "space": data.len(),
+            })
+        };
+        let mut mocks = HashMap::new();
+        mocks.insert(
+            RpcRequest::GetMultipleAccounts,
+            json!({"context": {"slot": 77}, "value": [
+                ui_account(1, curve_data(123, &Pubkey::new_unique())),
+                ui_account(1, global_data(&Pubkey::new_unique())),
+                null,
+                ui_account(5_000, vec![]),
+            ]}),
+        );
+        let rpc = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+
+        let snapshot = TradeSnapshot::fetch(&rpc, &wallet, &mint).await.unwrap();
+        assert_eq!(snapshot.slot, 77);
+        assert_eq!(snapshot.curve.virtual_sol_reserves, 123);
+        assert_eq!(snapshot.token_balance, None);
+        assert_eq!(snapshot.wallet_lamports, 5_000);
+        assert_eq!(snapshot.addresses, TradeAddresses::derive(&wallet, &mint));
+    }
+
+}
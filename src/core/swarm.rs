@@ -0,0 +1,711 @@
+use {
+    anyhow::Result,
+    futures::{future::join_all, stream::FuturesUnordered, StreamExt},
+    solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig},
+    solana_sdk::{
+        commitment_config::CommitmentConfig,
+        hash::Hash,
+        instruction::Instruction,
+        pubkey::Pubkey,
+        signature::{Keypair, Signature, Signer},
+        transaction::Transaction,
+    },
+    solana_transaction_status::{UiInnerInstructions, UiTransactionEncoding},
+    spl_associated_token_account::instruction::create_associated_token_account_idempotent,
+    crate::{
+        core::{
+            broadcast::{send_config, Broadcaster},
+            confirmation::{ConfirmationTracker, PendingSignature},
+            global::BASIS_POINTS_DENOMINATOR,
+            nonce::DurableNonce,
+            offline::decompile_instructions,
+            simulation::TradeEvent,
+            snapshot::{TradeAddresses, TradeSnapshot},
+            token_price::Quote,
+            transaction::TxError,
+            wallet_pool::split_by_weight,
+        },
+        utils::config::token_program_id,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SwarmError {
+    #[error("a swarm needs at least one wallet")]
+    NoWallets,
+    #[error("{max_fills} fills need at least as many wallets, only {wallets} were selected")]
+    TooManyFills { max_fills: usize, wallets: usize },
+    #[error("capping {wallets} wallets at {max_fills} fills needs {max_fills} durable nonce accounts")]
+    NeedsNonces { max_fills: usize, wallets: usize },
+    #[error("{nonces} nonce accounts allow exactly {nonces} fills, not {max_fills}")]
+    NonceCountMismatch { nonces: usize, max_fills: usize },
+    #[error("{wallets} keypairs were given for {legs} legs")]
+    WalletCountMismatch { legs: usize, wallets: usize },
+    #[error("the nonce authority must sign every leg sharing a nonce")]
+    MissingNonceAuthority,
+    #[error("nonce account {account} is controlled by {authority}, every swarm nonce must share the authority {expected}")]
+    MixedNonceAuthorities { account: Pubkey, authority: Pubkey, expected: Pubkey },
+}
+
+/// The authority every nonce of a swarm shares, `None` without nonces. One keypair co-signs
+/// every leg, so nonces controlled by different authorities are rejected.
+pub fn shared_nonce_authority(nonces: &[DurableNonce]) -> Result<Option<Pubkey>> {
+    let Some(expected) = nonces.first().map(|nonce| nonce.authority) else {
+        return Ok(None);
+    };
+    match nonces.iter().find(|nonce| nonce.authority != expected) {
+        Some(nonce) => Err(SwarmError::MixedNonceAuthorities { account: nonce.account, authority: nonce.authority, expected }.into()),
+        None => Ok(Some(expected)),
+    }
+}
+
+/// One wallet's buy in a swarm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwarmLeg {
+    pub wallet: Pubkey,
+    /// Legs in the same group share a durable nonce, so at most one of them can land
+    pub group: usize,
+    /// Quoted spend, sized so that the signed `max_sol_cost` stays within the leg's share
+    pub amount_in_lamports: u64,
+}
+
+/// How a budget is spread over wallets that all buy the same mint at the same time, so that
+/// the budget holds however many of their transactions land.
+///
+/// Without nonces every leg can land, so the budget is split across the wallets by weight.
+/// With `N` durable nonces the wallets are dealt round robin into `N` groups whose legs all
+/// advance the group's nonce: the first to land invalidates the rest, at most `N` legs fill
+/// and each leg buys with an `N`th of the budget.
+///
+/// A leg may spend up to its `max_sol_cost`, the quote plus the slippage tolerance, so each
+/// leg quotes its share less the tolerance and the budget holds even at full slippage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwarmPlan {
+    pub budget_lamports: u64,
+    pub slippage_bps: u64,
+    pub max_fills: usize,
+    pub legs: Vec<SwarmLeg>,
+    /// Nonce of each group, empty when every leg is its own group
+    pub nonces: Vec<DurableNonce>,
+}
+
+impl SwarmPlan {
+    /// Plans a swarm over `wallets` (address and weight). `max_fills` defaults to one fill per
+    /// nonce, or to one per wallet without nonces.
+    pub fn new(
+        budget_lamports: u64,
+        slippage_bps: u64,
+        wallets: &[(Pubkey, u64)],
+        nonces: Vec<DurableNonce>,
+        max_fills: Option<usize>,
+    ) -> Result<Self> {
+        if wallets.is_empty() {
+            return Err(SwarmError::NoWallets.into());
+        }
+        let max_fills = max_fills.unwrap_or(if nonces.is_empty() { wallets.len() } else { nonces.len() });
+        if max_fills > wallets.len() {
+            return Err(SwarmError::TooManyFills { max_fills, wallets: wallets.len() }.into());
+        }
+
+        if nonces.is_empty() {
+            if max_fills < wallets.len() {
+                return Err(SwarmError::NeedsNonces { max_fills, wallets: wallets.len() }.into());
+            }
+            let weights: Vec<u64> = wallets.iter().map(|(_, weight)| *weight).collect();
+            let legs = wallets
+                .iter()
+                .zip(split_by_weight(budget_lamports, &weights)?)
+                .enumerate()
+                .map(|(group, ((wallet, _), share))| SwarmLeg { wallet: *wallet, group, amount_in_lamports: spend_within(share, slippage_bps) })
+                .collect();
+            return Ok(Self { budget_lamports, slippage_bps, max_fills, legs, nonces });
+        }
+
+        if nonces.len() != max_fills {
+            return Err(SwarmError::NonceCountMismatch { nonces: nonces.len(), max_fills }.into());
+        }
+        shared_nonce_authority(&nonces)?;
+        // Only one leg per group can land, so weights do not matter within a group
+        let group_amounts = split_by_weight(budget_lamports, &vec![1; max_fills])?;
+        let legs = wallets
+            .iter()
+            .enumerate()
+            .map(|(index, (wallet, _))| {
+                let group = index % max_fills;
+                SwarmLeg { wallet: *wallet, group, amount_in_lamports: spend_within(group_amounts[group], slippage_bps) }
+            })
+            .collect();
+        Ok(Self { budget_lamports, slippage_bps, max_fills, legs, nonces })
+    }
+
+    /// Most SOL the signed `legs` (in leg order) can spend before network fees if every group
+    /// fills: the sum over groups of their largest `max_sol_cost`.
+    pub fn worst_case_spend(&self, legs: &[SignedLeg]) -> u64 {
+        (0..self.group_count())
+            .map(|group| {
+                self.legs
+                    .iter()
+                    .zip(legs)
+                    .filter(|(leg, _)| leg.group == group)
+                    .map(|(_, signed)| signed.max_sol_cost)
+                    .max()
+                    .unwrap_or(0)
+            })
+            .sum()
+    }
+
+    pub fn group_count(&self) -> usize {
+        self.legs.iter().map(|leg| leg.group + 1).max().unwrap_or(0)
+    }
+
+    fn nonce_for(&self, leg: &SwarmLeg) -> Option<&DurableNonce> {
+        self.nonces.get(leg.group)
+    }
+
+    /// Signs every leg's buy against `snapshot`. `wallets` are in leg order. Legs sharing a
+    /// nonce advance it first and are co-signed by `nonce_authority`, all others are signed
+    /// against `recent_blockhash`.
+    pub fn sign_legs(
+        &self,
+        wallets: &[&Keypair],
+        nonce_authority: Option<&Keypair>,
+        buy: &SwarmBuy,
+        recent_blockhash: Hash,
+    ) -> Result<Vec<SignedLeg>> {
+        if wallets.len() != self.legs.len() {
+            return Err(SwarmError::WalletCountMismatch { legs: self.legs.len(), wallets: wallets.len() }.into());
+        }
+        if !self.nonces.is_empty() {
+            let authority = nonce_authority.ok_or(SwarmError::MissingNonceAuthority)?;
+            for nonce in &self.nonces {
+                nonce.ensure_authority(&authority.pubkey())?;
+            }
+        }
+
+        self.legs
+            .iter()
+            .zip(wallets)
+            .map(|(leg, wallet)| {
+                let snapshot = TradeSnapshot { addresses: TradeAddresses::derive(&leg.wallet, &buy.snapshot.addresses.token_mint), ..buy.snapshot };
+                let quote = Quote::buy_exact_sol_in(&snapshot.curve, &snapshot.global, leg.amount_in_lamports)?;
+                let max_sol_cost = quote.max_sol_cost(self.slippage_bps);
+                let mut instructions = buy.compute_budget.clone();
+                instructions.push(create_associated_token_account_idempotent(
+                    &leg.wallet,
+                    &leg.wallet,
+                    &snapshot.addresses.token_mint,
+                    &token_program_id(),
+                ));
+                instructions.push(snapshot.buy_instruction(&buy.creator_vault, quote.token_amount, max_sol_cost)?);
+
+                let transaction = match (self.nonce_for(leg), nonce_authority) {
+                    (Some(nonce), Some(authority)) => {
+                        instructions.insert(0, nonce.advance_instruction());
+                        // The authority may be one of the swarm wallets itself
+                        let signers: Vec<&Keypair> = if authority.pubkey() == leg.wallet { vec![wallet] } else { vec![wallet, authority] };
+                        Transaction::new_signed_with_payer(&instructions, Some(&leg.wallet), &signers, nonce.nonce)
+                    }
+                    _ => Transaction::new_signed_with_payer(&instructions, Some(&leg.wallet), &[wallet], recent_blockhash),
+                };
+                Ok(SignedLeg { quote, max_sol_cost, transaction })
+            })
+            .collect()
+    }
+}
+
+// Largest spend whose `max_sol_cost` at `slippage_bps` still fits in `share`
+fn spend_within(share: u64, slippage_bps: u64) -> u64 {
+    (share as u128 * BASIS_POINTS_DENOMINATOR as u128 / (BASIS_POINTS_DENOMINATOR + slippage_bps) as u128) as u64
+}
+
+/// The buy every leg makes, sized per leg by the plan.
+#[derive(Debug, Clone)]
+pub struct SwarmBuy {
+    pub snapshot: TradeSnapshot,
+    pub creator_vault: Pubkey,
+    /// Compute unit limit and price, the same for every leg
+    pub compute_budget: Vec<Instruction>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SignedLeg {
+    /// Quote against the shared snapshot, legs landing later see a moved curve
+    pub quote: Quote,
+    /// Most the leg can spend, as signed into its buy instruction
+    pub max_sol_cost: u64,
+    pub transaction: Transaction,
+}
+
+#[derive(Debug)]
+pub enum LegStatus {
+    /// Landed; the fill is decoded from the transaction's trade event when available
+    Filled { slot: u64, fill: Option<TradeEvent> },
+    /// Another leg sharing its nonce landed or failed on chain first, so this one never can
+    Superseded,
+    Failed(TxError),
+    /// Past the deadline the nonce could not be advanced, so the leg may still land
+    Unsettled { nonce_account: Pubkey },
+}
+
+#[derive(Debug)]
+pub struct LegReport {
+    pub leg: SwarmLeg,
+    pub signature: Signature,
+    pub status: LegStatus,
+}
+
+impl LegReport {
+    pub fn is_filled(&self) -> bool {
+        matches!(self.status, LegStatus::Filled { .. })
+    }
+}
+
+/// SOL paid per whole token in a fill, fees excluded.
+pub fn fill_price(fill: &TradeEvent) -> f64 {
+    if fill.token_amount == 0 {
+        return 0.0;
+    }
+    (fill.sol_amount as f64 / 1_000_000_000.0) / (fill.token_amount as f64 / 1_000_000.0)
+}
+
+/// Broadcasts every signed leg (in leg order) at once, then confirms them group by group: the first leg of
+/// a group to land makes the group's other legs `Superseded`. Nothing is retried, a leg that
+/// fails or expires is reported as failed. `deadline` is the block height after which legs
+/// are given up on.
+///
+/// Nonce legs never expire, so a group still open at the deadline is settled through its
+/// nonce: if a leg advanced it, its status decides the group, otherwise `nonce_authority`
+/// advances it so that none of the legs can land any more.
+pub async fn execute_swarm(
+    rpc_client: &RpcClient,
+    confirmations: &ConfirmationTracker,
+    broadcaster: Option<&Broadcaster>,
+    plan: &SwarmPlan,
+    nonce_authority: Option<&Keypair>,
+    transactions: &[Transaction],
+    deadline: u64,
+) -> Vec<LegReport> {
+    let broadcaster = broadcaster.filter(|broadcaster| broadcaster.has_enabled_endpoints());
+    let sent = join_all(transactions.iter().map(|transaction| async move {
+        let result = match broadcaster {
+            Some(broadcaster) => broadcaster.broadcast(&transaction.clone().into()).await.map(|_| transaction.signatures[0]),
+            None => rpc_client.send_transaction_with_config(transaction, send_config()).await,
+        };
+        result.map_err(|err| TxError::from_client_error(err, &decompile_instructions(&transaction.message)))
+    }))
+    .await;
+
+    let mut statuses: Vec<Option<LegStatus>> = sent
+        .into_iter()
+        .map(|result| result.err().map(LegStatus::Failed))
+        .collect();
+    let groups = (0..plan.group_count()).map(|group| {
+        let pending: Vec<(usize, PendingSignature)> = plan
+            .legs
+            .iter()
+            .enumerate()
+            .filter(|(index, leg)| leg.group == group && statuses[*index].is_none())
+            .map(|(index, _)| (index, PendingSignature { signature: transactions[index].signatures[0], last_valid_block_height: deadline }))
+            .collect();
+        let nonce = plan.nonces.get(group);
+        async move {
+            let signatures: Vec<(usize, Signature)> = pending.iter().map(|(index, pending)| (*index, pending.signature)).collect();
+            let (statuses, settled) = confirm_group(rpc_client, confirmations, pending, nonce.is_some()).await;
+            match nonce {
+                Some(nonce) if !settled && !signatures.is_empty() => {
+                    match settle_nonce_group(rpc_client, confirmations, nonce, nonce_authority, &signatures).await {
+                        NonceSettlement::Resolved(status) => vec![*status],
+                        NonceSettlement::Advanced => statuses,
+                        NonceSettlement::Unsettled => {
+                            signatures.iter().map(|(index, _)| (*index, LegStatus::Unsettled { nonce_account: nonce.account })).collect()
+                        }
+                    }
+                }
+                _ => statuses,
+            }
+        }
+    });
+    for (index, status) in join_all(groups).await.into_iter().flatten() {
+        statuses[index] = Some(status);
+    }
+
+    // Prices come from the trade events of the landed transactions
+    let fills = join_all(statuses.iter().zip(transactions).map(|(status, transaction)| async move {
+        match status {
+            Some(LegStatus::Filled { .. }) => fetch_trade_event(rpc_client, &transaction.signatures[0]).await,
+            _ => None,
+        }
+    }))
+    .await;
+
+    plan.legs
+        .iter()
+        .zip(transactions)
+        .zip(statuses.into_iter().zip(fills))
+        .map(|((leg, transaction), (status, fill))| {
+            let status = match status {
+                Some(LegStatus::Filled { slot, .. }) => LegStatus::Filled { slot, fill },
+                Some(status) => status,
+                None => LegStatus::Superseded,
+            };
+            LegReport { leg: *leg, signature: transaction.signatures[0], status }
+        })
+        .collect()
+}
+
+// Confirms a group's legs concurrently and stops at the first one that lands, or with a
+// shared nonce at the first one that failed on chain, which advanced the nonce all the same.
+// Also returns whether the group was settled that way.
+async fn confirm_group(
+    rpc_client: &RpcClient,
+    confirmations: &ConfirmationTracker,
+    pending: Vec<(usize, PendingSignature)>,
+    shares_nonce: bool,
+) -> (Vec<(usize, LegStatus)>, bool) {
+    let mut confirming: FuturesUnordered<_> = pending
+        .into_iter()
+        .map(|(index, pending)| async move { (index, confirmations.confirm(rpc_client, pending).await) })
+        .collect();
+    let mut statuses = vec![];
+    while let Some((index, result)) = confirming.next().await {
+        match result {
+            Ok(slot) => {
+                statuses.push((index, LegStatus::Filled { slot, fill: None }));
+                // The remaining legs are left without a status and reported as superseded
+                return (statuses, true);
+            }
+            Err(err) => {
+                let executed = shares_nonce && err.get_transaction_error().is_some();
+                statuses.push((index, LegStatus::Failed(TxError::from_client_error(err, &[]))));
+                if executed {
+                    return (statuses, true);
+                }
+            }
+        }
+    }
+    (statuses, false)
+}
+
+enum NonceSettlement {
+    /// A leg advanced the nonce, landing or failing on chain, its siblings are superseded
+    Resolved(Box<(usize, LegStatus)>),
+    /// The nonce moved without any leg's status being found, the legs stay failed
+    Advanced,
+    /// The nonce is unchanged and could not be advanced
+    Unsettled,
+}
+
+// Advances the group's nonce unless a leg already did, then looks for the leg that moved it
+async fn settle_nonce_group(
+    rpc_client: &RpcClient,
+    confirmations: &ConfirmationTracker,
+    nonce: &DurableNonce,
+    nonce_authority: Option<&Keypair>,
+    signatures: &[(usize, Signature)],
+) -> NonceSettlement {
+    let advanced = match DurableNonce::fetch(rpc_client, &nonce.account).await {
+        Ok(current) if current.nonce != nonce.nonce => true,
+        Ok(_) => match nonce_authority {
+            Some(authority) => advance_nonce(rpc_client, confirmations, nonce, authority).await.is_ok(),
+            None => false,
+        },
+        Err(_) => false,
+    };
+
+    // A leg may also have landed right before the advance sent here, or long enough ago
+    // to have left the status cache that a plain lookup reads
+    let keys: Vec<Signature> = signatures.iter().map(|(_, signature)| *signature).collect();
+    if let Ok(statuses) = rpc_client.get_signature_statuses_with_history(&keys).await {
+        let found = signatures.iter().zip(statuses.value).find_map(|((index, _), status)| {
+            let status = status?;
+            Some(match status.err {
+                Some(err) => (*index, LegStatus::Failed(TxError::from_client_error(err.into(), &[]))),
+                None => (*index, LegStatus::Filled { slot: status.slot, fill: None }),
+            })
+        });
+        if let Some(found) = found {
+            return NonceSettlement::Resolved(Box::new(found));
+        }
+    }
+    if advanced {
+        NonceSettlement::Advanced
+    } else {
+        NonceSettlement::Unsettled
+    }
+}
+
+async fn advance_nonce(rpc_client: &RpcClient, confirmations: &ConfirmationTracker, nonce: &DurableNonce, authority: &Keypair) -> Result<u64> {
+    let (blockhash, last_valid_block_height) = rpc_client.get_latest_blockhash_with_commitment(rpc_client.commitment()).await?;
+    let advance = Transaction::new_signed_with_payer(&[nonce.advance_instruction()], Some(&authority.pubkey()), &[authority], blockhash);
+    let signature = rpc_client.send_transaction_with_config(&advance, send_config()).await?;
+    Ok(confirmations.confirm(rpc_client, PendingSignature { signature, last_valid_block_height }).await?)
+}
+
+/// Trade event of a confirmed transaction, `None` if it cannot be fetched or has none.
+pub async fn fetch_trade_event(rpc_client: &RpcClient, signature: &Signature) -> Option<TradeEvent> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Json),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let transaction = rpc_client.get_transaction_with_config(signature, config).await.ok()?;
+    let meta = transaction.transaction.meta?;
+    let inner_instructions: Option<Vec<UiInnerInstructions>> = meta.inner_instructions.into();
+    let logs: Option<Vec<String>> = meta.log_messages.into();
+    inner_instructions
+        .as_deref()
+        .and_then(TradeEvent::find_in_inner_instructions)
+        .or_else(|| logs.as_deref().and_then(TradeEvent::find_in_logs))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        mockito::{Matcher, Server},
+        serde_json::json,
+        solana_client::rpc_request::RpcRequest,
+        solana_sdk::{
+            compute_budget::ComputeBudgetInstruction,
+            nonce::state::DurableNonce as StoredNonce,
+            system_instruction,
+        },
+        std::collections::HashMap,
+        crate::core::fixtures::{bonding_curve, global_account, nonce_account_json},
+    };
+
+    fn wallets(weights: &[u64]) -> Vec<(Pubkey, u64)> {
+        weights.iter().map(|weight| (Pubkey::new_unique(), *weight)).collect()
+    }
+
+    fn nonce(authority: Pubkey) -> DurableNonce {
+        DurableNonce {
+            account: Pubkey::new_unique(),
+            authority,
+            nonce: Hash::new_unique(),
+            lamports_per_signature: 5_000,
+        }
+    }
+
+    fn swarm_buy() -> SwarmBuy {
+        let curve = bonding_curve();
+        SwarmBuy {
+            snapshot: TradeSnapshot {
+                addresses: TradeAddresses::derive(&Pubkey::new_unique(), &Pubkey::new_unique()),
+                slot: 1,
+                curve,
+                global: global_account(Pubkey::new_unique()),
+                token_balance: None,
+                wallet_lamports: 0,
+            },
+            creator_vault: curve.creator_vault(),
+            compute_budget: vec![ComputeBudgetInstruction::set_compute_unit_limit(100_000)],
+        }
+    }
+
+    #[test]
+    fn test_plan_without_nonces_splits_the_budget() {
+        let plan = SwarmPlan::new(1_000, 0, &wallets(&[1, 1, 2]), vec![], None).unwrap();
+        assert_eq!(plan.max_fills, 3);
+        assert_eq!(plan.legs.iter().map(|leg| leg.amount_in_lamports).collect::<Vec<_>>(), vec![250, 250, 500]);
+        assert_eq!(plan.group_count(), 3);
+
+        // Independent legs can all land, so fewer fills than wallets cannot be enforced
+        let err = SwarmPlan::new(1_000, 0, &wallets(&[1, 1, 1]), vec![], Some(2)).unwrap_err();
+        assert!(matches!(err.downcast_ref::<SwarmError>(), Some(SwarmError::NeedsNonces { max_fills: 2, wallets: 3 })));
+        assert!(SwarmPlan::new(1_000, 0, &wallets(&[1]), vec![], Some(2)).is_err());
+        assert!(SwarmPlan::new(1_000, 0, &[], vec![], None).is_err());
+    }
+
+    #[test]
+    fn test_plan_with_nonces_caps_fills() {
+        let authority = Pubkey::new_unique();
+        let plan = SwarmPlan::new(1_001, 0, &wallets(&[1, 5, 1, 1, 1]), vec![nonce(authority), nonce(authority)], None).unwrap();
+        assert_eq!(plan.max_fills, 2);
+        assert_eq!(plan.legs.iter().map(|leg| leg.group).collect::<Vec<_>>(), vec![0, 1, 0, 1, 0]);
+        // Each leg buys with its group's share, whatever its weight
+        assert_eq!(plan.legs.iter().map(|leg| leg.amount_in_lamports).collect::<Vec<_>>(), vec![501, 500, 501, 500, 501]);
+
+        let err = SwarmPlan::new(1_000, 0, &wallets(&[1, 1, 1]), vec![nonce(authority)], Some(2)).unwrap_err();
+        assert!(matches!(err.downcast_ref::<SwarmError>(), Some(SwarmError::NonceCountMismatch { nonces: 1, max_fills: 2 })));
+
+        // A single keypair co-signs every leg, so the nonces must share their authority
+        let mixed = vec![nonce(authority), nonce(Pubkey::new_unique())];
+        let err = shared_nonce_authority(&mixed).unwrap_err();
+        assert!(matches!(err.downcast_ref::<SwarmError>(), Some(SwarmError::MixedNonceAuthorities { account, .. }) if *account == mixed[1].account));
+        assert!(SwarmPlan::new(1_000, 0, &wallets(&[1, 1]), mixed, None).is_err());
+        assert_eq!(shared_nonce_authority(&[nonce(authority), nonce(authority)]).unwrap(), Some(authority));
+        assert_eq!(shared_nonce_authority(&[]).unwrap(), None);
+
+        let keypairs: Vec<Keypair> = (0..5).map(|_| Keypair::new()).collect();
+        let signers: Vec<&Keypair> = keypairs.iter().collect();
+        let weighted: Vec<(Pubkey, u64)> = keypairs.iter().map(|keypair| (keypair.pubkey(), 1)).collect();
+        let nonce_authority = &keypairs[0];
+        let nonces = vec![nonce(nonce_authority.pubkey()), nonce(nonce_authority.pubkey())];
+
+        // Each group's share of 0.5 SOL is quoted less the 25% tolerance
+        let plan = SwarmPlan::new(1_000_000_000, 2_500, &weighted, nonces, None).unwrap();
+        assert_eq!(plan.legs[0].amount_in_lamports, 400_000_000);
+        let legs = plan.sign_legs(&signers, Some(nonce_authority), &swarm_buy(), Hash::new_unique()).unwrap();
+        for signed in &legs {
+            let buy = signed.transaction.message.instructions.last().unwrap();
+            assert_eq!(buy.data[16..24], signed.max_sol_cost.to_le_bytes());
+            assert!(signed.max_sol_cost <= 500_000_000);
+        }
+        // At most one leg per group fills, and legs of a group sign the same max_sol_cost
+        let worst_case_spend = plan.worst_case_spend(&legs);
+        assert_eq!(worst_case_spend, legs[0].max_sol_cost + legs[1].max_sol_cost);
+        assert!(worst_case_spend <= plan.budget_lamports);
+
+        // Without nonces every leg can fill, all of them together stay within the budget
+        let plan = SwarmPlan::new(1_000_000_000, 2_500, &weighted, vec![], None).unwrap();
+        let legs = plan.sign_legs(&signers, None, &swarm_buy(), Hash::new_unique()).unwrap();
+        assert_eq!(plan.worst_case_spend(&legs), legs.iter().map(|signed| signed.max_sol_cost).sum::<u64>());
+        assert!(plan.worst_case_spend(&legs) <= plan.budget_lamports);
+    }
+
+    fn transfer(payer: &Keypair, nonce: Option<&DurableNonce>, signers: &[&Keypair]) -> Transaction {
+        let mut instructions = vec![system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1)];
+        let blockhash = match nonce {
+            Some(nonce) => {
+                instructions.insert(0, nonce.advance_instruction());
+                nonce.nonce
+            }
+            None => Hash::new_unique(),
+        };
+        Transaction::new_signed_with_payer(&instructions, Some(&payer.pubkey()), signers, blockhash)
+    }
+
+    #[tokio::test]
+    async fn test_execute_reports_one_fill_per_nonce() {
+        let rpc_client = RpcClient::new_mock("succeeds".to_string());
+        let confirmations = ConfirmationTracker::polling(CommitmentConfig::confirmed());
+        let authority = Keypair::new();
+        let keypairs = [Keypair::new(), Keypair::new(), Keypair::new()];
+        let nonce = nonce(authority.pubkey());
+        let plan = SwarmPlan::new(
+            900,
+            0,
+            &keypairs.iter().map(|keypair| (keypair.pubkey(), 1)).collect::<Vec<_>>(),
+            vec![nonce],
+            None,
+        )
+        .unwrap();
+        let transactions: Vec<Transaction> = keypairs.iter().map(|keypair| transfer(keypair, Some(&nonce), &[keypair, &authority])).collect();
+
+        let reports = execute_swarm(&rpc_client, &confirmations, None, &plan, Some(&authority), &transactions, 10_000).await;
+        assert_eq!(reports.len(), 3);
+        assert_eq!(reports.iter().filter(|report| report.is_filled()).count(), 1);
+        assert_eq!(reports.iter().filter(|report| matches!(report.status, LegStatus::Superseded)).count(), 2);
+        // Reports stay in leg order whichever leg filled
+        for (report, transaction) in reports.iter().zip(&transactions) {
+            assert_eq!(report.signature, transaction.signatures[0]);
+        }
+        let filled = reports.iter().find(|report| report.is_filled()).unwrap();
+        assert!(matches!(filled.status, LegStatus::Filled { slot: 1, .. }));
+
+        // Without nonces every leg is its own group and all of them fill
+        let plan = SwarmPlan::new(900, 0, &keypairs.iter().map(|keypair| (keypair.pubkey(), 1)).collect::<Vec<_>>(), vec![], None).unwrap();
+        let transactions: Vec<Transaction> = keypairs.iter().map(|keypair| transfer(keypair, None, &[keypair])).collect();
+        let reports = execute_swarm(&rpc_client, &confirmations, None, &plan, None, &transactions, 10_000).await;
+        assert!(reports.iter().all(LegReport::is_filled));
+    }
+
+    #[tokio::test]
+    async fn test_execute_settles_nonce_groups() {
+        let confirmations = ConfirmationTracker::polling(CommitmentConfig::confirmed());
+        let authority = Keypair::new();
+        let keypairs = [Keypair::new(), Keypair::new(), Keypair::new()];
+        let blockhash = Hash::new_unique();
+        let nonce = DurableNonce { nonce: *StoredNonce::from_blockhash(&blockhash).as_hash(), ..nonce(authority.pubkey()) };
+        let weighted: Vec<(Pubkey, u64)> = keypairs.iter().map(|keypair| (keypair.pubkey(), 1)).collect();
+        let plan = SwarmPlan::new(900, 0, &weighted, vec![nonce], None).unwrap();
+        let transactions: Vec<Transaction> = keypairs.iter().map(|keypair| transfer(keypair, Some(&nonce), &[keypair, &authority])).collect();
+
+        // A leg failing on chain advanced the nonce too, its siblings can no longer land
+        let rpc_client = RpcClient::new_mock("instruction_error".to_string());
+        let reports = execute_swarm(&rpc_client, &confirmations, None, &plan, Some(&authority), &transactions, 10_000).await;
+        assert_eq!(reports.iter().filter(|report| matches!(report.status, LegStatus::Failed(_))).count(), 1);
+        assert_eq!(reports.iter().filter(|report| matches!(report.status, LegStatus::Superseded)).count(), 2);
+
+        // Past the deadline (the mock is at block height 1234) with the nonce already moved,
+        // no leg can land any more
+        let mut mocks = HashMap::new();
+        mocks.insert(RpcRequest::GetAccountInfo, nonce_account_json(&authority.pubkey(), &Hash::new_unique()));
+        let rpc_client = RpcClient::new_mock_with_mocks("sig_not_found".to_string(), mocks);
+        let reports = execute_swarm(&rpc_client, &confirmations, None, &plan, Some(&authority), &transactions, 1_000).await;
+        assert!(reports.iter().all(|report| matches!(report.status, LegStatus::Failed(TxError::BlockhashExpired))));
+
+        // An unchanged nonce whose advance does not confirm leaves the legs unsettled
+        let mut mocks = HashMap::new();
+        mocks.insert(RpcRequest::GetAccountInfo, nonce_account_json(&authority.pubkey(), &blockhash));
+        mocks.insert(RpcRequest::GetLatestBlockhash, json!({"context": {"slot": 1}, "value": {
+            "blockhash": Hash::new_unique().to_string(),
+            "lastValidBlockHeight": 1_000,
+        }}));
+        let rpc_client = RpcClient::new_mock_with_mocks("sig_not_found".to_string(), mocks);
+        let reports = execute_swarm(&rpc_client, &confirmations, None, &plan, Some(&authority), &transactions, 1_000).await;
+        assert!(reports.iter().all(|report| matches!(report.status, LegStatus::Unsettled { nonce_account } if nonce_account == nonce.account)));
+    }
+
+    #[tokio::test]
+    async fn test_settle_finds_the_fill_in_history() {
+        let confirmations = ConfirmationTracker::polling(CommitmentConfig::confirmed());
+        let authority = Pubkey::new_unique();
+        let nonce = nonce(authority);
+        let signatures = [(0, Signature::new_unique()), (1, Signature::new_unique())];
+        let mut server = Server::new_async().await;
+        server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({"method": "getAccountInfo"})))
+            .with_body(
+                json!({"jsonrpc": "2.0", "id": 1, "result": nonce_account_json(&authority, &Hash::new_unique())})
+                    .to_string(),
+            )
+            .create_async()
+            .await;
+        // Only a lookup that searches the transaction history finds the leg that moved the nonce
+        let keys: Vec<String> = signatures.iter().map(|(_, signature)| signature.to_string()).collect();
+        let history = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({
+                "method": "getSignatureStatuses",
+                "params": [keys, {"searchTransactionHistory": true}],
+            })))
+            .with_body(
+                json!({"jsonrpc": "2.0", "id": 1, "result": {"context": {"slot": 50}, "value": [null, {
+                    "slot": 42,
+                    "confirmations": null,
+                    "err": null,
+                    "status": {"Ok": null},
+                    "confirmationStatus": "finalized",
+                }]}})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let rpc_client = RpcClient::new(server.url());
+        let settlement = settle_nonce_group(&rpc_client, &confirmations, &nonce, None, &signatures).await;
+        assert!(matches!(settlement, NonceSettlement::Resolved(found) if matches!(*found, (1, LegStatus::Filled { slot: 42, .. }))));
+        history.assert_async().await;
+    }
+
+    #[test]
+    fn test_fill_price() {
+        let fill = TradeEvent {
+            mint: Pubkey::new_unique(),
+            sol_amount: 1_000_000_000,
+            token_amount: 2_000_000_000,
+            is_buy: true,
+            user: Pubkey::new_unique(),
+            timestamp: 0,
+            virtual_sol_reserves: 0,
+            virtual_token_reserves: 0,
+            real_sol_reserves: 0,
+            real_token_reserves: 0,
+        };
+        // 1 SOL for 2,000 tokens
+        assert!((fill_price(&fill) - 0.0005).abs() < 1e-12);
+    }
+}
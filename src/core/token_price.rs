@@ -1,13 +1,111 @@
 use {
     anyhow::Result,
-    solana_client::rpc_client::RpcClient,
+    solana_client::nonblocking::rpc_client::RpcClient,
     solana_sdk::pubkey::Pubkey,
     num_bigint::BigUint,
-    num_traits::ToPrimitive,
+    crate::{
+        core::global::{compute_fee, GlobalAccount, BASIS_POINTS_DENOMINATOR},
+        utils::config::find_creator_vault,
+    },
 };
 
+// Anchor account discriminator: sha256("account:BondingCurve")[..8]
+pub const BONDING_CURVE_DISCRIMINATOR: [u8; 8] = [0x17, 0xb7, 0xf8, 0x37, 0x60, 0xd8, 0xac, 0x60];
+
+// discriminator + 5 x u64 + bool + creator pubkey
+pub const BONDING_CURVE_ACCOUNT_LEN: usize = 8 + 5 * 8 + 1 + 32;
+
+/// Errors returned when raw account data cannot be decoded into a pump.fun account.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AccountDecodeError {
+    #[error("account data too short: expected at least {expected} bytes, got {actual}")]
+    TooShort { expected: usize, actual: usize },
+    #[error("account discriminator mismatch: expected {expected:?}, got {actual:?}")]
+    DiscriminatorMismatch { expected: [u8; 8], actual: [u8; 8] },
+}
+
+/// Decoded pump.fun `BondingCurve` account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BondingCurveAccount {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub token_total_supply: u64,
+    pub complete: bool,
+    pub creator: Pubkey,
+}
+
+impl BondingCurveAccount {
+    /// Decodes the raw account data, checking the length and the Anchor discriminator.
+    /// Trailing bytes beyond the known layout are ignored.
+    pub fn from_account_data(data: &[u8]) -> Result<Self, AccountDecodeError> {
+        check_discriminator(data, BONDING_CURVE_DISCRIMINATOR, BONDING_CURVE_ACCOUNT_LEN)?;
+
+        Ok(Self {
+            virtual_token_reserves: read_u64(data, 8),
+            virtual_sol_reserves: read_u64(data, 16),
+            real_token_reserves: read_u64(data, 24),
+            real_sol_reserves: read_u64(data, 32),
+            token_total_supply: read_u64(data, 40),
+            complete: data[48] != 0,
+            creator: read_pubkey(data, 49),
+        })
+    }
+
+    /// Fetches and decodes the bonding curve account at `bonding_curve`.
+    pub async fn fetch(rpc_client: &RpcClient, bonding_curve: &Pubkey) -> Result<Self> {
+        let account = rpc_client.get_account(bonding_curve).await?;
+        Ok(Self::from_account_data(&account.data)?)
+    }
+
+    /// Creator vault the program expects in buy/sell instructions for this curve.
+    pub fn creator_vault(&self) -> Pubkey {
+        find_creator_vault(&self.creator)
+    }
+}
+
+/// Creator and creator vault for a trade, read from the curve unless overridden.
+/// A creator override without a vault override derives the vault from the override.
+pub fn resolve_creator_accounts(
+    curve: &BondingCurveAccount,
+    creator_override: Option<Pubkey>,
+    creator_vault_override: Option<Pubkey>,
+) -> (Pubkey, Pubkey) {
+    let creator = creator_override.unwrap_or(curve.creator);
+    let creator_vault = creator_vault_override.unwrap_or_else(|| find_creator_vault(&creator));
+    (creator, creator_vault)
+}
+
+/// Verifies that `data` is at least `min_len` bytes long and starts with `expected`.
+pub(crate) fn check_discriminator(
+    data: &[u8],
+    expected: [u8; 8],
+    min_len: usize,
+) -> Result<(), AccountDecodeError> {
+    if data.len() < min_len {
+        return Err(AccountDecodeError::TooShort { expected: min_len, actual: data.len() });
+    }
+    let actual: [u8; 8] = data[0..8].try_into().unwrap();
+    if actual != expected {
+        return Err(AccountDecodeError::DiscriminatorMismatch { expected, actual });
+    }
+    Ok(())
+}
+
+// Callers must have checked the length beforehand
+pub(crate) fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+pub(crate) fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    Pubkey::new_from_array(data[offset..offset + 32].try_into().unwrap())
+}
+
 pub struct TokenPriceInfo {
+    /// Virtual token reserves of the curve
     pub token_supply: u64,
+    /// Virtual SOL reserves of the curve, in lamports
     pub token_price: u64,
 }
 
@@ -17,114 +115,449 @@ pub struct BondingCurveData {
     pub virtual_sol_reserves: BigUint,
 }
 
-pub fn get_token_price_info(
+impl From<&BondingCurveAccount> for TokenPriceInfo {
+    fn from(curve: &BondingCurveAccount) -> Self {
+        Self {
+            token_supply: curve.virtual_token_reserves,
+            token_price: curve.virtual_sol_reserves,
+        }
+    }
+}
+
+impl From<&BondingCurveAccount> for BondingCurveData {
+    fn from(curve: &BondingCurveAccount) -> Self {
+        Self {
+            real_token_reserves: BigUint::from(curve.real_token_reserves),
+            virtual_token_reserves: BigUint::from(curve.virtual_token_reserves),
+            virtual_sol_reserves: BigUint::from(curve.virtual_sol_reserves),
+        }
+    }
+}
+
+pub async fn get_token_price_info(
     rpc_client: &RpcClient,
     bonding_curve: &Pubkey,
 ) -> Result<TokenPriceInfo> {
-    let bonding_curve_account = rpc_client.get_account(bonding_curve)?;
-    let bonding_curve_data = bonding_curve_account.data;
-    
-    // The bonding curve data structure is:
-    // - First 8 bytes: discriminator
-    // - Next 8 bytes: token supply
-    // - Next 8 bytes: price in lamports
-    let token_supply = u64::from_le_bytes(bonding_curve_data[8..16].try_into()?);
-    let token_price = u64::from_le_bytes(bonding_curve_data[16..24].try_into()?);
-    
-    Ok(TokenPriceInfo {
-        token_supply,
-        token_price,
-    })
-}
-
-pub fn get_bonding_curve_data(
+    let curve = BondingCurveAccount::fetch(rpc_client, bonding_curve).await?;
+    Ok(TokenPriceInfo::from(&curve))
+}
+
+pub async fn get_bonding_curve_data(
     rpc_client: &RpcClient,
     bonding_curve: &Pubkey,
 ) -> Result<BondingCurveData> {
-    let bonding_curve_account = rpc_client.get_account(bonding_curve)?;
-    let data = bonding_curve_account.data;
-    if data.len() < 24 {
-        anyhow::bail!("Bonding curve account data too short");
-    }
-    let real_token_reserves = BigUint::from(u64::from_le_bytes(data[0..8].try_into()?));
-    let virtual_token_reserves = BigUint::from(u64::from_le_bytes(data[8..16].try_into()?));
-    let virtual_sol_reserves = BigUint::from(u64::from_le_bytes(data[16..24].try_into()?));
-    Ok(BondingCurveData {
-        real_token_reserves,
-        virtual_token_reserves,
-        virtual_sol_reserves,
-    })
-}
-
-/// Accurate buy quote using the constant product formula
-pub fn calculate_tokens_to_get_bonding_curve(
-    sol_amount: u64,
-    bonding_curve: &BondingCurveData,
-    percentage: f64, // e.g. 0.99 for 1% slippage
-) -> u64 {
-    let sol_amount_big = BigUint::from(sol_amount);
-    let virtual_sol_reserves = &bonding_curve.virtual_sol_reserves;
-    let virtual_token_reserves = &bonding_curve.virtual_token_reserves;
-    let new_virtual_sol_reserves = virtual_sol_reserves + &sol_amount_big;
-    let invariant = virtual_sol_reserves * virtual_token_reserves;
-    let new_virtual_token_reserves = &invariant / &new_virtual_sol_reserves;
-    let tokens_to_buy = virtual_token_reserves - &new_virtual_token_reserves;
-    // Apply percentage reduction for slippage/protocol fee
-    let tokens_to_buy_f64 = tokens_to_buy.to_f64().unwrap_or(0.0);
-    let final_tokens = (tokens_to_buy_f64 * percentage).floor() as u64;
-    final_tokens
-}
-
-pub fn calculate_tokens_to_get(
-    sol_amount: f64,
-    token_supply: u64,
-    token_price: u64,
-) -> Result<u64, anyhow::Error> {
-    // Convert to u128 for precise integer math
-    let sol_amount_lamports = (sol_amount * 1_000_000_000.0) as u128;
-    let token_supply_128 = token_supply as u128;
-    let token_price_128 = token_price as u128;
-    
-    // Calculate tokens using integer math with overflow checks
-    let tokens = sol_amount_lamports
-        .checked_mul(token_supply_128)
-        .ok_or_else(|| anyhow::anyhow!("Overflow in token calculation: sol_amount * token_supply"))?
-        .checked_div(token_price_128)
-        .ok_or_else(|| anyhow::anyhow!("Division by zero in token calculation"))?
-        .checked_mul(99)
-        .ok_or_else(|| anyhow::anyhow!("Overflow in fee calculation"))?
-        .checked_div(100)
-        .ok_or_else(|| anyhow::anyhow!("Division by zero in fee calculation"))?;
-
-    // Convert back to u64, checking for overflow
-    if tokens > u64::MAX as u128 {
-        return Err(anyhow::anyhow!("Token amount exceeds u64::MAX"));
-    }
-    
-    Ok(tokens as u64)
-}
-
-pub fn calculate_sol_to_get(
-    token_amount: u64,
-    token_supply: u64,
-    token_price: u64,
-) -> Result<u64, anyhow::Error> {
-    // Convert to u128 for precise integer math
-    let token_amount_128 = token_amount as u128;
-    let token_supply_128 = token_supply as u128;
-    let token_price_128 = token_price as u128;
-    
-    // Calculate SOL using integer math with overflow checks
-    let sol_amount = token_amount_128
-        .checked_mul(token_price_128)
-        .ok_or_else(|| anyhow::anyhow!("Overflow in SOL calculation: token_amount * token_price"))?
-        .checked_div(token_supply_128)
-        .ok_or_else(|| anyhow::anyhow!("Division by zero in SOL calculation"))?;
-
-    // Convert back to u64, checking for overflow
-    if sol_amount > u64::MAX as u128 {
-        return Err(anyhow::anyhow!("SOL amount exceeds u64::MAX"));
-    }
-    
-    Ok(sol_amount as u64)
-}
\ No newline at end of file
+    let curve = BondingCurveAccount::fetch(rpc_client, bonding_curve).await?;
+    Ok(BondingCurveData::from(&curve))
+}
+
+/// Errors returned when a trade cannot be quoted against the curve.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum QuoteError {
+    #[error("bonding curve is complete, the token has migrated")]
+    CurveComplete,
+    #[error("trade amount must be greater than zero")]
+    ZeroAmount,
+    #[error("not enough liquidity in the bonding curve for this trade")]
+    InsufficientLiquidity,
+    #[error("arithmetic overflow while quoting")]
+    Overflow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeDirection {
+    Buy,
+    Sell,
+}
+
+/// Curve reserves, as stored in the bonding curve account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurveReserves {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub real_sol_reserves: u64,
+}
+
+impl From<&BondingCurveAccount> for CurveReserves {
+    fn from(curve: &BondingCurveAccount) -> Self {
+        Self {
+            virtual_token_reserves: curve.virtual_token_reserves,
+            virtual_sol_reserves: curve.virtual_sol_reserves,
+            real_token_reserves: curve.real_token_reserves,
+            real_sol_reserves: curve.real_sol_reserves,
+        }
+    }
+}
+
+impl CurveReserves {
+    /// Marginal price in lamports per token base unit.
+    pub fn spot_price(&self) -> f64 {
+        self.virtual_sol_reserves as f64 / self.virtual_token_reserves as f64
+    }
+}
+
+/// Result of quoting a trade against the curve, using the same integer math as the program.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quote {
+    pub direction: TradeDirection,
+    /// Tokens bought or sold, in base units
+    pub token_amount: u64,
+    /// SOL moving into (buy) or out of (sell) the curve, before fees
+    pub gross_sol: u64,
+    pub protocol_fee: u64,
+    pub creator_fee: u64,
+    /// Buy: total SOL debited including fees. Sell: SOL credited after fees.
+    pub net_sol: u64,
+    /// Execution price versus the spot price before the trade, in percent
+    pub price_impact_pct: f64,
+    pub new_reserves: CurveReserves,
+}
+
+impl Quote {
+    /// Largest token amount whose total cost, including fees, fits in `sol_amount` lamports.
+    pub fn buy_exact_sol_in(
+        curve: &BondingCurveAccount,
+        global: &GlobalAccount,
+        sol_amount: u64,
+    ) -> Result<Self, QuoteError> {
+        check_tradable(curve, sol_amount)?;
+        let (fee_bps, creator_fee_bps) = fee_basis_points(curve, global);
+        let total_fee_bps = fee_bps + creator_fee_bps;
+
+        // Closed-form estimate rounded up, then search down to the exact on-chain cost
+        let sol_into_curve = (sol_amount as u128 * BASIS_POINTS_DENOMINATOR as u128)
+            .div_ceil((BASIS_POINTS_DENOMINATOR + total_fee_bps) as u128);
+        let estimate = (sol_into_curve * curve.virtual_token_reserves as u128)
+            .div_ceil(curve.virtual_sol_reserves as u128 + sol_into_curve);
+        let mut hi = to_u64(estimate)?.min(curve.real_token_reserves);
+        let mut lo = 0u64;
+        while lo < hi {
+            let mid = lo + (hi - lo).div_ceil(2);
+            if Self::buy_exact_tokens_out(curve, global, mid)?.net_sol <= sol_amount {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+        if lo == 0 {
+            return Err(QuoteError::ZeroAmount);
+        }
+        Self::buy_exact_tokens_out(curve, global, lo)
+    }
+
+    /// Cost of buying exactly `token_amount` tokens.
+    pub fn buy_exact_tokens_out(
+        curve: &BondingCurveAccount,
+        global: &GlobalAccount,
+        token_amount: u64,
+    ) -> Result<Self, QuoteError> {
+        check_tradable(curve, token_amount)?;
+        if token_amount > curve.real_token_reserves {
+            return Err(QuoteError::InsufficientLiquidity);
+        }
+        let vtok = curve.virtual_token_reserves as u128;
+        let vsol = curve.virtual_sol_reserves as u128;
+        let tokens = token_amount as u128;
+        if tokens >= vtok {
+            return Err(QuoteError::InsufficientLiquidity);
+        }
+
+        // Program rounds the cost up by one lamport
+        let gross_sol = to_u64(tokens * vsol / (vtok - tokens) + 1)?;
+        let (fee_bps, creator_fee_bps) = fee_basis_points(curve, global);
+        let protocol_fee = compute_fee(gross_sol, fee_bps);
+        let creator_fee = compute_fee(gross_sol, creator_fee_bps);
+        let net_sol = gross_sol
+            .checked_add(protocol_fee)
+            .and_then(|sol| sol.checked_add(creator_fee))
+            .ok_or(QuoteError::Overflow)?;
+
+        Ok(Self {
+            direction: TradeDirection::Buy,
+            token_amount,
+            gross_sol,
+            protocol_fee,
+            creator_fee,
+            net_sol,
+            price_impact_pct: price_impact_pct(curve, token_amount, gross_sol, TradeDirection::Buy),
+            new_reserves: CurveReserves {
+                virtual_token_reserves: curve.virtual_token_reserves - token_amount,
+                virtual_sol_reserves: curve.virtual_sol_reserves.checked_add(gross_sol).ok_or(QuoteError::Overflow)?,
+                real_token_reserves: curve.real_token_reserves - token_amount,
+                real_sol_reserves: curve.real_sol_reserves.checked_add(gross_sol).ok_or(QuoteError::Overflow)?,
+            },
+        })
+    }
+
+    /// SOL received for selling exactly `token_amount` tokens.
+    pub fn sell_exact_tokens_in(
+        curve: &BondingCurveAccount,
+        global: &GlobalAccount,
+        token_amount: u64,
+    ) -> Result<Self, QuoteError> {
+        check_tradable(curve, token_amount)?;
+        let vtok = curve.virtual_token_reserves as u128;
+        let vsol = curve.virtual_sol_reserves as u128;
+        let tokens = token_amount as u128;
+
+        let gross_sol = to_u64(tokens * vsol / (vtok + tokens))?;
+        if gross_sol > curve.real_sol_reserves {
+            return Err(QuoteError::InsufficientLiquidity);
+        }
+        let (fee_bps, creator_fee_bps) = fee_basis_points(curve, global);
+        let protocol_fee = compute_fee(gross_sol, fee_bps);
+        let creator_fee = compute_fee(gross_sol, creator_fee_bps);
+        let net_sol = gross_sol.saturating_sub(protocol_fee + creator_fee);
+
+        Ok(Self {
+            direction: TradeDirection::Sell,
+            token_amount,
+            gross_sol,
+            protocol_fee,
+            creator_fee,
+            net_sol,
+            price_impact_pct: price_impact_pct(curve, token_amount, gross_sol, TradeDirection::Sell),
+            new_reserves: CurveReserves {
+                virtual_token_reserves: curve.virtual_token_reserves.checked_add(token_amount).ok_or(QuoteError::Overflow)?,
+                virtual_sol_reserves: curve.virtual_sol_reserves - gross_sol,
+                real_token_reserves: curve.real_token_reserves.checked_add(token_amount).ok_or(QuoteError::Overflow)?,
+                real_sol_reserves: curve.real_sol_reserves - gross_sol,
+            },
+        })
+    }
+
+    /// Smallest token amount whose sale credits at least `sol_amount` lamports after fees.
+    pub fn sell_for_sol_out(
+        curve: &BondingCurveAccount,
+        global: &GlobalAccount,
+        sol_amount: u64,
+    ) -> Result<Self, QuoteError> {
+        check_tradable(curve, sol_amount)?;
+        let vtok = curve.virtual_token_reserves as u128;
+        let vsol = curve.virtual_sol_reserves as u128;
+        let max_gross = curve.real_sol_reserves as u128;
+        if max_gross == 0 || max_gross >= vsol {
+            return Err(QuoteError::InsufficientLiquidity);
+        }
+
+        // Tokens that drain all real SOL bound the search from above
+        let mut hi = to_u64(max_gross * vtok / (vsol - max_gross))?;
+        if Self::sell_exact_tokens_in(curve, global, hi)?.net_sol < sol_amount {
+            return Err(QuoteError::InsufficientLiquidity);
+        }
+        let mut lo = 1u64;
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if Self::sell_exact_tokens_in(curve, global, mid)?.net_sol >= sol_amount {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        Self::sell_exact_tokens_in(curve, global, lo)
+    }
+
+    /// `max_sol_cost` for a buy instruction: the quoted total cost plus `slippage_bps` tolerance.
+    pub fn max_sol_cost(&self, slippage_bps: u64) -> u64 {
+        let max_cost = self.net_sol as u128 * (BASIS_POINTS_DENOMINATOR + slippage_bps) as u128
+            / BASIS_POINTS_DENOMINATOR as u128;
+        max_cost.min(u64::MAX as u128) as u64
+    }
+
+    /// `min_sol_output` for a sell instruction: the quoted proceeds minus `slippage_bps` tolerance.
+    pub fn min_sol_output(&self, slippage_bps: u64) -> u64 {
+        let min_output = self.net_sol as u128 * BASIS_POINTS_DENOMINATOR.saturating_sub(slippage_bps) as u128
+            / BASIS_POINTS_DENOMINATOR as u128;
+        min_output as u64
+    }
+
+    /// Average fill price in lamports per token base unit, excluding fees.
+    pub fn average_price(&self) -> f64 {
+        self.gross_sol as f64 / self.token_amount as f64
+    }
+}
+
+impl BondingCurveAccount {
+    /// Marginal price in lamports per token base unit.
+    pub fn spot_price(&self) -> f64 {
+        CurveReserves::from(self).spot_price()
+    }
+}
+
+fn check_tradable(curve: &BondingCurveAccount, amount: u64) -> Result<(), QuoteError> {
+    if curve.complete {
+        return Err(QuoteError::CurveComplete);
+    }
+    if amount == 0 {
+        return Err(QuoteError::ZeroAmount);
+    }
+    if curve.virtual_token_reserves == 0 || curve.virtual_sol_reserves == 0 {
+        return Err(QuoteError::InsufficientLiquidity);
+    }
+    Ok(())
+}
+
+// The program only charges the creator fee once a creator is set on the curve
+fn fee_basis_points(curve: &BondingCurveAccount, global: &GlobalAccount) -> (u64, u64) {
+    let creator_fee_bps = if curve.creator == Pubkey::default() { 0 } else { global.creator_fee_basis_points };
+    (global.fee_basis_points, creator_fee_bps)
+}
+
+fn price_impact_pct(curve: &BondingCurveAccount, token_amount: u64, gross_sol: u64, direction: TradeDirection) -> f64 {
+    let ratio = (gross_sol as f64 / token_amount as f64) / curve.spot_price();
+    match direction {
+        TradeDirection::Buy => (ratio - 1.0) * 100.0,
+        TradeDirection::Sell => (1.0 - ratio) * 100.0,
+    }
+}
+
+fn to_u64(value: u128) -> Result<u64, QuoteError> {
+    u64::try_from(value).map_err(|_| QuoteError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::core::fixtures::{bonding_curve, global_account},
+    };
+
+    fn encode_curve(curve: &BondingCurveAccount) -> Vec<u8> {
+        let mut data = vec![];
+        data.extend_from_slice(&BONDING_CURVE_DISCRIMINATOR);
+        data.extend_from_slice(&curve.virtual_token_reserves.to_le_bytes());
+        data.extend_from_slice(&curve.virtual_sol_reserves.to_le_bytes());
+        data.extend_from_slice(&curve.real_token_reserves.to_le_bytes());
+        data.extend_from_slice(&curve.real_sol_reserves.to_le_bytes());
+        data.extend_from_slice(&curve.token_total_supply.to_le_bytes());
+        data.push(curve.complete as u8);
+        data.extend_from_slice(curve.creator.as_ref());
+        data
+    }
+
+    #[test]
+    fn test_decode_bonding_curve_account() {
+        let curve = bonding_curve();
+        let mut data = encode_curve(&curve);
+        assert_eq!(data.len(), BONDING_CURVE_ACCOUNT_LEN);
+        assert_eq!(BondingCurveAccount::from_account_data(&data), Ok(curve));
+
+        // Live accounts carry extra trailing bytes, which must be ignored
+        data.extend_from_slice(&[0u8; 69]);
+        assert_eq!(BondingCurveAccount::from_account_data(&data), Ok(curve));
+
+        let complete = BondingCurveAccount { complete: true, ..curve };
+        assert!(BondingCurveAccount::from_account_data(&encode_curve(&complete)).unwrap().complete);
+    }
+
+    #[test]
+    fn test_decode_bonding_curve_rejects_bad_data() {
+        let data = encode_curve(&bonding_curve());
+
+        assert_eq!(
+            BondingCurveAccount::from_account_data(&data[..40]),
+            Err(AccountDecodeError::TooShort { expected: BONDING_CURVE_ACCOUNT_LEN, actual: 40 })
+        );
+
+        let mut wrong = data.clone();
+        wrong[0] ^= 0xff;
+        assert!(matches!(
+            BondingCurveAccount::from_account_data(&wrong),
+            Err(AccountDecodeError::DiscriminatorMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_legacy_views_use_decoded_reserves() {
+        let curve = bonding_curve();
+        let price_info = TokenPriceInfo::from(&curve);
+        assert_eq!(price_info.token_supply, curve.virtual_token_reserves);
+        assert_eq!(price_info.token_price, curve.virtual_sol_reserves);
+
+        let curve_data = BondingCurveData::from(&curve);
+        assert_eq!(curve_data.real_token_reserves, BigUint::from(curve.real_token_reserves));
+        assert_eq!(curve_data.virtual_sol_reserves, BigUint::from(curve.virtual_sol_reserves));
+    }
+
+    #[test]
+    fn test_buy_exact_tokens_matches_program_math() {
+        let quote = Quote::buy_exact_tokens_out(&bonding_curve(), &global_account(Pubkey::new_unique()), 1_000_000_000_000).unwrap();
+        // 1e12 * 30e9 / (1.073e15 - 1e12) + 1
+        assert_eq!(quote.gross_sol, 27_985_075);
+        assert_eq!(quote.protocol_fee, 265_859);
+        assert_eq!(quote.creator_fee, 13_993);
+        assert_eq!(quote.net_sol, 28_264_927);
+        assert_eq!(quote.new_reserves.virtual_token_reserves, 1_072_000_000_000_000);
+        assert_eq!(quote.new_reserves.real_sol_reserves, 27_985_075);
+        assert!(quote.price_impact_pct > 0.0);
+
+        let no_creator = BondingCurveAccount { creator: Pubkey::default(), ..bonding_curve() };
+        let quote = Quote::buy_exact_tokens_out(&no_creator, &global_account(Pubkey::new_unique()), 1_000_000_000_000).unwrap();
+        assert_eq!(quote.creator_fee, 0);
+    }
+
+    #[test]
+    fn test_buy_exact_sol_in_is_tight() {
+        let curve = bonding_curve();
+        let global = global_account(Pubkey::new_unique());
+        let sol_amount = 1_000_000_000;
+
+        let quote = Quote::buy_exact_sol_in(&curve, &global, sol_amount).unwrap();
+        assert!(quote.net_sol <= sol_amount);
+        let one_more = Quote::buy_exact_tokens_out(&curve, &global, quote.token_amount + 1).unwrap();
+        assert!(one_more.net_sol > sol_amount);
+    }
+
+    #[test]
+    fn test_sell_quotes() {
+        let global = global_account(Pubkey::new_unique());
+        let buy = Quote::buy_exact_sol_in(&bonding_curve(), &global, 2_000_000_000).unwrap();
+        let curve = BondingCurveAccount {
+            virtual_token_reserves: buy.new_reserves.virtual_token_reserves,
+            virtual_sol_reserves: buy.new_reserves.virtual_sol_reserves,
+            real_token_reserves: buy.new_reserves.real_token_reserves,
+            real_sol_reserves: buy.new_reserves.real_sol_reserves,
+            ..bonding_curve()
+        };
+
+        // Selling everything back returns at most what went into the curve
+        let sell = Quote::sell_exact_tokens_in(&curve, &global, buy.token_amount).unwrap();
+        assert!(sell.gross_sol <= buy.gross_sol);
+        assert_eq!(sell.net_sol, sell.gross_sol - sell.protocol_fee - sell.creator_fee);
+        assert!(sell.price_impact_pct > 0.0);
+
+        let target = 500_000_000;
+        let sell = Quote::sell_for_sol_out(&curve, &global, target).unwrap();
+        assert!(sell.net_sol >= target);
+        let one_less = Quote::sell_exact_tokens_in(&curve, &global, sell.token_amount - 1).unwrap();
+        assert!(one_less.net_sol < target);
+
+        assert_eq!(
+            Quote::sell_for_sol_out(&curve, &global, 3_000_000_000),
+            Err(QuoteError::InsufficientLiquidity)
+        );
+    }
+
+    #[test]
+    fn test_slippage_bounds() {
+        let quote = Quote::buy_exact_tokens_out(&bonding_curve(), &global_account(Pubkey::new_unique()), 1_000_000_000_000).unwrap();
+        assert_eq!(quote.max_sol_cost(0), quote.net_sol);
+        assert_eq!(quote.max_sol_cost(100), 28_264_927 * 101 / 100);
+        assert_eq!(quote.min_sol_output(100), 28_264_927 * 99 / 100);
+        assert_eq!(quote.min_sol_output(10_000), 0);
+        assert_eq!(quote.min_sol_output(20_000), 0);
+    }
+
+    #[test]
+    fn test_quote_rejects_untradable_curve() {
+        let global = global_account(Pubkey::new_unique());
+        let complete = BondingCurveAccount { complete: true, ..bonding_curve() };
+        assert_eq!(Quote::buy_exact_sol_in(&complete, &global, 1_000), Err(QuoteError::CurveComplete));
+        assert_eq!(Quote::sell_exact_tokens_in(&bonding_curve(), &global, 0), Err(QuoteError::ZeroAmount));
+        // A fresh curve has no real SOL to pay sellers
+        assert_eq!(
+            Quote::sell_exact_tokens_in(&bonding_curve(), &global, 1_000_000),
+            Err(QuoteError::InsufficientLiquidity)
+        );
+    }
+}
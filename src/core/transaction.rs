@@ -1,92 +1,1021 @@
 use {
-    anyhow::Result,
-    solana_client::rpc_client::RpcClient,
+    base64::{engine::general_purpose::STANDARD as BASE64, Engine},
+    serde_json::json,
+    solana_client::{
+        client_error::{ClientError, ClientErrorKind},
+        nonblocking::rpc_client::RpcClient,
+        rpc_config::RpcSimulateTransactionConfig,
+        rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
+        rpc_response::Response,
+    },
     solana_sdk::{
+        address_lookup_table::AddressLookupTableAccount,
         commitment_config::CommitmentConfig,
-        compute_budget::ComputeBudgetInstruction,
-        signature::{Keypair, Signer},
-        transaction::Transaction,
+        compute_budget::{self, ComputeBudgetInstruction},
+        hash::Hash,
+        instruction::{Instruction, InstructionError},
+        message::{v0, Message, VersionedMessage},
+        pubkey::Pubkey,
+        signature::{Signature, Signer},
+        system_program,
+        transaction::{Transaction, TransactionError, VersionedTransaction},
     },
-    std::time::Instant,
+    std::{collections::HashMap, sync::Arc, time::Instant},
     tokio::time::sleep,
     std::time::Duration,
+    crate::{
+        core::{
+            blockhash::BlockhashCache,
+            broadcast::{send_config, Broadcaster},
+            confirmation::{ConfirmationExpired, ConfirmationTracker, PendingSignature},
+            jito::{JitoClient, JitoError},
+            nonce::DurableNonce,
+            fees::{compute_unit_limit, set_compute_unit_price, FeeSchedule, MAX_COMPUTE_UNIT_LIMIT},
+            instructions::{BUY_DISCRIMINATOR, SELL_DISCRIMINATOR},
+            remote_signer::sign_blocking,
+        },
+        utils::config::pump_program_id,
+    },
 };
 
+// Anchor custom error codes of the pump program start at 6000
+const PUMP_ERROR_CODE_OFFSET: u32 = 6000;
+
+// System program custom error for a transfer from an account without enough lamports
+const SYSTEM_INSUFFICIENT_FUNDS_CODE: u32 = 1;
+
+/// Custom errors returned by the pump program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum PumpError {
+    #[error("not authorized")]
+    NotAuthorized,
+    #[error("program already initialized")]
+    AlreadyInitialized,
+    #[error("slippage exceeded: buy requires more SOL than max_sol_cost")]
+    TooMuchSolRequired,
+    #[error("slippage exceeded: sell returns less SOL than min_sol_output")]
+    TooLittleSolReceived,
+    #[error("mint does not match bonding curve")]
+    MintDoesNotMatchBondingCurve,
+    #[error("bonding curve is complete")]
+    BondingCurveComplete,
+    #[error("bonding curve is not complete")]
+    BondingCurveNotComplete,
+    #[error("program not initialized")]
+    NotInitialized,
+    #[error("withdraw too frequent")]
+    WithdrawTooFrequent,
+}
+
+impl PumpError {
+    pub fn from_code(code: u32) -> Option<Self> {
+        match code.checked_sub(PUMP_ERROR_CODE_OFFSET)? {
+            0 => Some(Self::NotAuthorized),
+            1 => Some(Self::AlreadyInitialized),
+            2 => Some(Self::TooMuchSolRequired),
+            3 => Some(Self::TooLittleSolReceived),
+            4 => Some(Self::MintDoesNotMatchBondingCurve),
+            5 => Some(Self::BondingCurveComplete),
+            6 => Some(Self::BondingCurveNotComplete),
+            7 => Some(Self::NotInitialized),
+            8 => Some(Self::WithdrawTooFrequent),
+            _ => None,
+        }
+    }
+}
+
+/// A transaction that landed and was confirmed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxOutcome {
+    pub signature: Signature,
+    pub slot: u64,
+    /// Which attempt (1-based) actually executed
+    pub attempt: usize,
+    /// Time from the first send until confirmation
+    pub confirmation_latency: Duration,
+    /// Lamports paid in transaction fees (base + priority)
+    pub fee: u64,
+}
+
+/// Classified failure of `send_transaction`.
+#[derive(Debug, thiserror::Error)]
+pub enum TxError {
+    #[error("pump program error {code}: {}", .error.map(|e| e.to_string()).unwrap_or_else(|| "unknown".to_string()))]
+    Program { code: u32, error: Option<PumpError>, logs: Vec<String> },
+    #[error("insufficient funds")]
+    InsufficientFunds,
+    #[error("blockhash expired before the transaction was confirmed")]
+    BlockhashExpired,
+    #[error("RPC transport error: {0}")]
+    Rpc(ClientError),
+    #[error("preflight failed: {message}")]
+    Preflight { message: String, logs: Vec<String> },
+    #[error("failed to build the transaction: {0}")]
+    Build(String),
+    #[error("block engine rejected the bundle: {0}")]
+    Bundle(JitoError),
+}
+
+impl TxError {
+    /// Classifies an RPC client error, using `instructions` to attribute instruction errors.
+    pub fn from_client_error(err: ClientError, instructions: &[Instruction]) -> Self {
+        match &err.kind {
+            ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                message,
+                data: RpcResponseErrorData::SendTransactionPreflightFailure(sim),
+                ..
+            }) => {
+                let logs = sim.logs.clone().unwrap_or_default();
+                match sim.err.as_ref().and_then(|tx_err| Self::from_transaction_error(tx_err, instructions, &logs)) {
+                    Some(classified) => classified,
+                    None => Self::Preflight { message: message.clone(), logs },
+                }
+            }
+            ClientErrorKind::TransactionError(tx_err) => {
+                Self::from_transaction_error(tx_err, instructions, &[])
+                    .unwrap_or_else(|| Self::Preflight { message: tx_err.to_string(), logs: vec![] })
+            }
+            _ if ConfirmationExpired::from_client_error(&err).is_some() => Self::BlockhashExpired,
+            // The RPC client's own confirmation spinner only reports expiry in its message
+            ClientErrorKind::RpcError(RpcError::ForUser(message)) if message.contains("unable to confirm transaction") => {
+                Self::BlockhashExpired
+            }
+            _ => Self::Rpc(err),
+        }
+    }
+
+    fn from_transaction_error(err: &TransactionError, instructions: &[Instruction], logs: &[String]) -> Option<Self> {
+        match err {
+            TransactionError::InsufficientFundsForFee | TransactionError::InsufficientFundsForRent { .. } => {
+                Some(Self::InsufficientFunds)
+            }
+            TransactionError::BlockhashNotFound => Some(Self::BlockhashExpired),
+            TransactionError::InstructionError(index, InstructionError::Custom(code)) => {
+                let program_id = instructions.get(*index as usize).map(|ix| ix.program_id);
+                if program_id == Some(pump_program_id()) {
+                    Some(Self::Program { code: *code, error: PumpError::from_code(*code), logs: logs.to_vec() })
+                } else if program_id == Some(solana_sdk::system_program::id()) && *code == SYSTEM_INSUFFICIENT_FUNDS_CODE {
+                    Some(Self::InsufficientFunds)
+                } else {
+                    None
+                }
+            }
+            TransactionError::InstructionError(_, InstructionError::InsufficientFunds) => Some(Self::InsufficientFunds),
+            _ => None,
+        }
+    }
+
+    /// Whether resending (with a fresh blockhash or a fresh quote) can succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::BlockhashExpired
+                | Self::Rpc(_)
+                | Self::Program { error: Some(PumpError::TooMuchSolRequired | PumpError::TooLittleSolReceived), .. }
+        )
+    }
+
+    /// Program logs captured from preflight simulation, if any.
+    pub fn logs(&self) -> &[String] {
+        match self {
+            Self::Program { logs, .. } | Self::Preflight { logs, .. } => logs,
+            _ => &[],
+        }
+    }
+}
+
+// How often to re-check earlier attempts whose blockhash has not expired yet
+const PENDING_ATTEMPT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A signed transaction that may have reached the network.
+#[derive(Debug, Clone)]
+pub struct BroadcastAttempt {
+    /// 1-based attempt number
+    pub attempt: usize,
+    pub signature: Signature,
+    /// Block height after which this attempt can no longer land
+    pub last_valid_block_height: u64,
+    message: VersionedMessage,
+}
+
+/// State of the earlier attempts tracked by an `AttemptTracker`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PriorAttempts {
+    /// An earlier attempt executed successfully
+    Landed { attempt: usize, signature: Signature, slot: u64 },
+    /// An earlier attempt could still land, so nothing new may be signed yet
+    Pending,
+    /// No earlier attempt landed and none can land anymore
+    Clear,
+}
+
+/// How signed attempts are submitted. Confirmation is always checked on the read endpoint.
+#[derive(Debug, Clone, Default)]
+pub enum SubmissionBackend {
+    /// `sendTransaction` on the read endpoint
+    #[default]
+    Rpc,
+    /// `sendTransaction` on every enabled send endpoint in parallel
+    Broadcast(Arc<Broadcaster>),
+    /// Jito bundle with a tip transfer appended, skipping the public mempool
+    Bundle(Arc<JitoClient>),
+}
+
+/// Tracks every signature broadcast for one logical trade, so retries never execute it twice.
+/// Share one tracker across all retry loops that resend the same trade.
+#[derive(Debug, Default)]
+pub struct AttemptTracker {
+    attempts: Vec<BroadcastAttempt>,
+    attempts_made: usize,
+    fee_schedule: Option<FeeSchedule>,
+    blockhash_cache: Option<BlockhashCache>,
+    backend: SubmissionBackend,
+    confirmations: Option<Arc<ConfirmationTracker>>,
+    durable_nonce: Option<DurableNonce>,
+    lookup_tables: Vec<AddressLookupTableAccount>,
+}
+
+impl AttemptTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reprices the compute unit price instruction of every attempt from `schedule`.
+    pub fn with_fee_schedule(mut self, schedule: FeeSchedule) -> Self {
+        self.fee_schedule = Some(schedule);
+        self
+    }
+
+    /// Signs attempts with blockhashes from `cache` instead of fetching one per attempt.
+    pub fn with_blockhash_cache(mut self, cache: BlockhashCache) -> Self {
+        self.blockhash_cache = Some(cache);
+        self
+    }
+
+    /// Submits attempts through `backend` instead of the read endpoint.
+    pub fn with_backend(mut self, backend: SubmissionBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Waits for attempts with `confirmations`, e.g. over a websocket, instead of polling.
+    pub fn with_confirmation_tracker(mut self, confirmations: Arc<ConfirmationTracker>) -> Self {
+        self.confirmations = Some(confirmations);
+        self
+    }
+
+    /// Signs attempts against `nonce` instead of a recent blockhash. Attempts sharing a nonce
+    /// are mutually exclusive, so a new one never waits for earlier ones to expire.
+    /// The nonce authority must be the payer.
+    pub fn with_durable_nonce(mut self, nonce: DurableNonce) -> Self {
+        self.durable_nonce = Some(nonce);
+        self
+    }
+
+    /// Compiles attempts into v0 messages that load accounts from `lookup_tables`,
+    /// instead of legacy messages listing every account in full.
+    pub fn with_lookup_tables(mut self, lookup_tables: Vec<AddressLookupTableAccount>) -> Self {
+        self.lookup_tables = lookup_tables;
+        self
+    }
+
+    /// Attempts that were broadcast and have not been ruled out yet.
+    pub fn attempts(&self) -> &[BroadcastAttempt] {
+        &self.attempts
+    }
+
+    fn record(&mut self, transaction: &VersionedTransaction, last_valid_block_height: u64) -> usize {
+        self.attempts_made += 1;
+        self.attempts.push(BroadcastAttempt {
+            attempt: self.attempts_made,
+            signature: transaction.signatures[0],
+            last_valid_block_height,
+            message: transaction.message.clone(),
+        });
+        self.attempts_made
+    }
+
+    // Preflight rejections never reach the network, so they cannot land later
+    fn forget(&mut self, signature: &Signature) {
+        self.attempts.retain(|attempt| attempt.signature != *signature);
+    }
+
+    /// Checks the status of every tracked attempt. Attempts that failed on-chain are dropped,
+    /// and so are attempts the cluster does not know once their blockhash expired. An attempt
+    /// that was seen without an error stays pending until it confirms or its status disappears.
+    pub async fn check(&mut self, rpc_client: &RpcClient) -> Result<PriorAttempts, ClientError> {
+        if self.attempts.is_empty() {
+            return Ok(PriorAttempts::Clear);
+        }
+
+        // The height is read first: an attempt missing from the statuses below was then also
+        // missing when its blockhash had already expired, so it can no longer land
+        let block_height = rpc_client.get_block_height().await?;
+        let signatures: Vec<Signature> = self.attempts.iter().map(|attempt| attempt.signature).collect();
+        let statuses = rpc_client.get_signature_statuses(&signatures).await?.value;
+        let mut unconfirmed = vec![];
+        for (attempt, status) in self.attempts.drain(..).zip(statuses) {
+            match status {
+                Some(status) if status.err.is_some() => {} // Landed but failed, nothing executed
+                Some(status) if status.satisfies_commitment(CommitmentConfig::confirmed()) => {
+                    return Ok(PriorAttempts::Landed {
+                        attempt: attempt.attempt,
+                        signature: attempt.signature,
+                        slot: status.slot,
+                    });
+                }
+                // Executed but not confirmed yet, it may still confirm whatever the block height
+                Some(_) => unconfirmed.push(attempt),
+                None if attempt.last_valid_block_height >= block_height => unconfirmed.push(attempt),
+                None => {}
+            }
+        }
+
+        self.attempts = unconfirmed;
+        if self.attempts.is_empty() {
+            Ok(PriorAttempts::Clear)
+        } else {
+            Ok(PriorAttempts::Pending)
+        }
+    }
+
+    // Once the nonce was refreshed, attempts signed against the old one must be waited for
+    fn attempts_share_nonce(&self) -> bool {
+        self.durable_nonce.is_some_and(|nonce| {
+            self.attempts.iter().all(|attempt| *attempt.message.recent_blockhash() == nonce.nonce)
+        })
+    }
+
+    fn message_for(&self, signature: &Signature) -> Option<&VersionedMessage> {
+        self.attempts
+            .iter()
+            .find(|attempt| attempt.signature == *signature)
+            .map(|attempt| &attempt.message)
+    }
+}
+
 pub async fn send_transaction(
     rpc_client: &RpcClient,
-    keypair: &Keypair,
-    instructions: Vec<solana_sdk::instruction::Instruction>,
-) -> Result<()> {
+    signer: &dyn Signer,
+    instructions: Vec<Instruction>,
+) -> Result<TxOutcome, TxError> {
+    send_transaction_tracked(rpc_client, signer, instructions, &mut AttemptTracker::new()).await
+}
+
+/// Like `send_transaction`, but records every broadcast in `tracker`. Before signing a new
+/// attempt it waits until no earlier attempt can still land, and returns the earlier
+/// attempt's outcome if one executed.
+pub async fn send_transaction_tracked(
+    rpc_client: &RpcClient,
+    signer: &dyn Signer,
+    mut instructions: Vec<Instruction>,
+    tracker: &mut AttemptTracker,
+) -> Result<TxOutcome, TxError> {
     let tx_start = Instant::now();
     let mut retries = 3;
-    let mut last_error = None;
-
-    while retries > 0 {
-        // Get fresh blockhash for each attempt
-        let recent_blockhash = rpc_client.get_latest_blockhash()?;
-        let transaction = Transaction::new_signed_with_payer(
-            &instructions,
-            Some(&keypair.pubkey()),
-            &[keypair],
-            recent_blockhash,
+    // Without a shared cache every attempt fetches its own blockhash
+    let blockhash_cache = tracker.blockhash_cache.clone().unwrap_or_else(|| BlockhashCache::new(Duration::ZERO));
+    let mut previous_blockhash = None;
+    let confirmations = tracker
+        .confirmations
+        .clone()
+        .unwrap_or_else(|| Arc::new(ConfirmationTracker::polling(CommitmentConfig::confirmed())));
+
+    loop {
+        // Never sign a new attempt while an earlier one could still land. The blockhash
+        // is read at the same time, and again if we had to wait.
+        let (prior, latest_blockhash) = tokio::join!(
+            wait_for_prior_attempts(rpc_client, tracker, &instructions, tx_start),
+            blockhash_cache.get(rpc_client, previous_blockhash),
         );
+        let latest_blockhash = match prior? {
+            PriorWait::Landed(outcome) => return Ok(outcome),
+            PriorWait::Clear { waited: false } => latest_blockhash,
+            PriorWait::Clear { waited: true } => blockhash_cache.get(rpc_client, previous_blockhash).await,
+        };
+        let (recent_blockhash, last_valid_block_height) =
+            latest_blockhash.map_err(|err| TxError::from_client_error(err, &instructions))?;
+        previous_blockhash = Some(recent_blockhash);
+        // With a durable nonce the blockhash only sets the confirmation deadline
+        let recent_blockhash = tracker.durable_nonce.map_or(recent_blockhash, |nonce| nonce.nonce);
 
-        match rpc_client.send_and_confirm_transaction_with_spinner_and_config(
-            &transaction,
-            CommitmentConfig::confirmed(),
-            solana_client::rpc_config::RpcSendTransactionConfig {
-                skip_preflight: false,
-                preflight_commitment: Some(CommitmentConfig::confirmed().commitment),
-                max_retries: Some(1), // We handle retries ourselves
-                min_context_slot: None,
-                encoding: None,
-            },
-        ) {
+        let tip = match &tracker.backend {
+            SubmissionBackend::Bundle(jito) => Some((jito, jito.tip_policy.tip_for_attempt(tracker.attempts_made + 1))),
+            _ => None,
+        };
+
+        // Escalate the priority fee with every attempt of this trade. Only the signer signs,
+        // and the tip counts against the same total fee cap.
+        if let Some(schedule) = tracker.fee_schedule {
+            let compute_units = compute_unit_limit(&instructions).unwrap_or(MAX_COMPUTE_UNIT_LIMIT);
+            let tip_lamports = tip.map_or(0, |(_, tip)| tip);
+            let price = schedule.price_for_attempt(tracker.attempts_made + 1, compute_units, 1, tip_lamports);
+            if set_compute_unit_price(&mut instructions, price) {
+                println!("💸 Priority fee: {} micro-lamports per CU", price);
+            }
+        }
+
+        // The tip escalates with the attempt and is never part of the caller's instructions
+        let mut attempt_instructions = instructions.clone();
+        if let Some(nonce) = &tracker.durable_nonce {
+            attempt_instructions.insert(0, nonce.advance_instruction());
+        }
+        if let Some((jito, tip)) = tip {
+            println!("📦 Jito tip: {} lamports to {}", tip, jito.tip_account());
+            attempt_instructions.push(jito.tip_instruction(&signer.pubkey(), tip));
+        }
+
+        let transaction = sign_blocking(|| sign_attempt(signer, &attempt_instructions, &tracker.lookup_tables, recent_blockhash))?;
+        let attempt = tracker.record(&transaction, last_valid_block_height);
+
+        let result = match &tracker.backend {
+            SubmissionBackend::Bundle(jito) => jito.send_and_confirm(rpc_client, &transaction, last_valid_block_height).await,
+            backend => {
+                let sent = match backend {
+                    // Fall back to the read endpoint once every send endpoint is disabled
+                    SubmissionBackend::Broadcast(broadcaster) if broadcaster.has_enabled_endpoints() => {
+                        broadcaster.send_and_confirm(rpc_client, &confirmations, &transaction, last_valid_block_height).await
+                    }
+                    _ => match rpc_client.send_transaction_with_config(&transaction, send_config()).await {
+                        Ok(signature) => confirmations
+                            .confirm(rpc_client, PendingSignature { signature, last_valid_block_height })
+                            .await
+                            .map(|_| signature),
+                        Err(err) => Err(err),
+                    },
+                };
+                // Instruction indices of the signed transaction, including the nonce advance
+                sent.map_err(|err| TxError::from_client_error(err, &attempt_instructions))
+            }
+        };
+        let err = match result {
             Ok(signature) => {
-                let confirmation_time = tx_start.elapsed();
+                let confirmation_latency = tx_start.elapsed();
                 println!("Transaction successful! Signature: {}", signature);
-                println!("Transaction confirmed in: {:.2?}", confirmation_time);
+                println!("Transaction confirmed in: {:.2?}", confirmation_latency);
                 println!("You can view the transaction at: https://solscan.io/tx/{}", signature);
-                return Ok(());
-            }
-            Err(err) => {
-                last_error = Some(err);
-                retries -= 1;
-                if retries > 0 {
-                    println!("Transaction failed, retrying... ({} attempts left)", retries);
-                    sleep(Duration::from_secs(1)).await; // Use tokio::time::sleep instead
-                }
+                tracker.attempts.clear();
+                return Ok(fetch_outcome(rpc_client, &transaction.message, signature, attempt, confirmation_latency).await);
+            }
+            Err(err) => err,
+        };
+        if !matches!(err, TxError::BlockhashExpired | TxError::Rpc(_)) {
+            tracker.forget(&transaction.signatures[0]);
+        }
+
+        // Slippage errors are retryable only with a fresh quote, which is up to the caller
+        retries -= 1;
+        if retries == 0 || !matches!(err, TxError::BlockhashExpired | TxError::Rpc(_)) {
+            // Confirmation may have failed even though the transaction landed
+            if let PriorWait::Landed(outcome) = wait_for_prior_attempts(rpc_client, tracker, &instructions, tx_start).await? {
+                return Ok(outcome);
             }
+            println!("Transaction failed with error: {}", err);
+            for log in err.logs() {
+                println!("{}", log);
+            }
+            return Err(err);
         }
+        println!("Transaction failed ({}), retrying... ({} attempts left)", err, retries);
+        // A nonce advanced without any attempt landing (e.g. one failed on-chain) is stale
+        if let (Some(nonce), TxError::BlockhashExpired) = (tracker.durable_nonce, &err) {
+            if let Ok(current) = DurableNonce::fetch(rpc_client, &nonce.account).await {
+                tracker.durable_nonce = Some(current);
+            }
+        }
+        sleep(Duration::from_secs(1)).await; // Use tokio::time::sleep instead
     }
+}
 
-    // If we get here, all retries failed
-    if let Some(err) = last_error {
-        println!("Transaction failed after all retries with error: {}", err);
-        // Try to get simulation logs
-        let recent_blockhash = rpc_client.get_latest_blockhash()?;
-        let transaction = Transaction::new_signed_with_payer(
-            &instructions,
-            Some(&keypair.pubkey()),
-            &[keypair],
-            recent_blockhash,
-        );
-        if let Ok(sim_result) = rpc_client.simulate_transaction(&transaction) {
-            println!("\nSimulation logs:");
-            if let Some(logs) = sim_result.value.logs {
-                for log in logs {
-                    println!("{}", log);
-                }
+// A legacy message without lookup tables, a v0 message compiled against them otherwise
+fn sign_attempt(
+    signer: &dyn Signer,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Result<VersionedTransaction, TxError> {
+    let message = if lookup_tables.is_empty() {
+        VersionedMessage::Legacy(Message::new_with_blockhash(instructions, Some(&signer.pubkey()), &recent_blockhash))
+    } else {
+        let message = v0::Message::try_compile(&signer.pubkey(), instructions, lookup_tables, recent_blockhash)
+            .map_err(|err| TxError::Build(err.to_string()))?;
+        VersionedMessage::V0(message)
+    };
+    VersionedTransaction::try_new(message, &[signer]).map_err(|err| TxError::Build(err.to_string()))
+}
+
+// `get_fee_for_message` serializes v0 messages without their version prefix
+async fn fee_for_message(rpc_client: &RpcClient, message: &VersionedMessage) -> Result<u64, ClientError> {
+    match message {
+        VersionedMessage::Legacy(message) => rpc_client.get_fee_for_message(message).await,
+        VersionedMessage::V0(_) => {
+            let encoded = BASE64.encode(message.serialize());
+            let response: Response<Option<u64>> = rpc_client
+                .send(RpcRequest::GetFeeForMessage, json!([encoded, rpc_client.commitment()]))
+                .await?;
+            response.value.ok_or_else(|| RpcError::ForUser("invalid blockhash".to_string()).into())
+        }
+    }
+}
+
+enum PriorWait {
+    Landed(TxOutcome),
+    Clear { waited: bool },
+}
+
+async fn wait_for_prior_attempts(
+    rpc_client: &RpcClient,
+    tracker: &mut AttemptTracker,
+    instructions: &[Instruction],
+    tx_start: Instant,
+) -> Result<PriorWait, TxError> {
+    let mut waited = false;
+    loop {
+        match tracker.check(rpc_client).await.map_err(|err| TxError::from_client_error(err, instructions))? {
+            PriorAttempts::Landed { attempt, signature, slot } => {
+                println!("Attempt {} already landed in slot {}: {}", attempt, slot, signature);
+                let fee = match tracker.message_for(&signature) {
+                    Some(message) => fee_for_message(rpc_client, message).await.unwrap_or_default(),
+                    None => 0,
+                };
+                tracker.attempts.clear();
+                let confirmation_latency = tx_start.elapsed();
+                return Ok(PriorWait::Landed(TxOutcome { signature, slot, attempt, confirmation_latency, fee }));
             }
+            // Attempts sharing a durable nonce cannot all land, the first one advances it
+            PriorAttempts::Pending if tracker.attempts_share_nonce() => return Ok(PriorWait::Clear { waited }),
+            PriorAttempts::Pending => {
+                println!("Waiting for {} earlier attempt(s) to land or expire...", tracker.attempts.len());
+                waited = true;
+                sleep(PENDING_ATTEMPT_POLL_INTERVAL).await;
+            }
+            PriorAttempts::Clear => return Ok(PriorWait::Clear { waited }),
         }
     }
+}
 
-    Ok(())
+// Slot and fee are informational, so lookups that fail fall back to zero
+async fn fetch_outcome(
+    rpc_client: &RpcClient,
+    message: &VersionedMessage,
+    signature: Signature,
+    attempt: usize,
+    confirmation_latency: Duration,
+) -> TxOutcome {
+    let signatures = [signature];
+    let (statuses, fee) = tokio::join!(
+        rpc_client.get_signature_statuses(&signatures),
+        fee_for_message(rpc_client, message),
+    );
+    let slot = statuses
+        .ok()
+        .and_then(|statuses| statuses.value.into_iter().next().flatten())
+        .map(|status| status.slot)
+        .unwrap_or_default();
+    TxOutcome { signature, slot, attempt, confirmation_latency, fee: fee.unwrap_or_default() }
 }
 
-pub fn create_compute_budget_instructions(compute_units: u32, priority_fee: u64) -> Vec<solana_sdk::instruction::Instruction> {
-    let mut instructions = vec![];
-    instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(compute_units));
-    instructions.push(ComputeBudgetInstruction::set_compute_unit_price(priority_fee));
-    instructions
-} 
\ No newline at end of file
+// Extra compute units on top of the simulated consumption
+pub const DEFAULT_COMPUTE_UNIT_MARGIN_BPS: u64 = 2_000;
+
+// Conservative limits used when simulation is unavailable
+const FALLBACK_BUY_COMPUTE_UNITS: u32 = 100_000;
+const FALLBACK_SELL_COMPUTE_UNITS: u32 = 80_000;
+const FALLBACK_ATA_CREATE_COMPUTE_UNITS: u32 = 30_000;
+const FALLBACK_OTHER_COMPUTE_UNITS: u32 = 10_000;
+const FALLBACK_UNKNOWN_COMPUTE_UNITS: u32 = 200_000;
+
+/// Programs and instruction tags of a transaction, ignoring compute budget instructions
+/// and amounts, e.g. "ATA create + pump buy" or "pump sell + token close".
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InstructionShape(Vec<(Pubkey, Vec<u8>)>);
+
+impl InstructionShape {
+    pub fn of(instructions: &[Instruction]) -> Self {
+        Self(
+            instructions
+                .iter()
+                .filter(|ix| ix.program_id != compute_budget::id())
+                .map(|ix| {
+                    // Anchor uses 8-byte discriminators, the system program a u32, SPL programs a u8
+                    let tag_len = if ix.program_id == pump_program_id() {
+                        8
+                    } else if ix.program_id == system_program::id() {
+                        4
+                    } else {
+                        1
+                    };
+                    (ix.program_id, ix.data[..tag_len.min(ix.data.len())].to_vec())
+                })
+                .collect(),
+        )
+    }
+
+    /// Conservative compute unit limit for this shape when it cannot be simulated.
+    pub fn fallback_compute_units(&self) -> u32 {
+        let mut units = 0;
+        for (program_id, tag) in &self.0 {
+            units += if *program_id == pump_program_id() && tag.as_slice() == BUY_DISCRIMINATOR {
+                FALLBACK_BUY_COMPUTE_UNITS
+            } else if *program_id == pump_program_id() && tag.as_slice() == SELL_DISCRIMINATOR {
+                FALLBACK_SELL_COMPUTE_UNITS
+            } else if *program_id == spl_associated_token_account::id() {
+                FALLBACK_ATA_CREATE_COMPUTE_UNITS
+            } else if *program_id == pump_program_id() {
+                FALLBACK_UNKNOWN_COMPUTE_UNITS
+            } else {
+                FALLBACK_OTHER_COMPUTE_UNITS
+            };
+        }
+        units.clamp(FALLBACK_OTHER_COMPUTE_UNITS, MAX_COMPUTE_UNIT_LIMIT)
+    }
+}
+
+/// Where a planned compute unit limit came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComputeUnitSource {
+    Simulated { units_consumed: u64 },
+    Cached,
+    Fallback,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeUnitPlan {
+    pub compute_units: u32,
+    pub source: ComputeUnitSource,
+}
+
+/// Sizes the compute unit limit by simulating each instruction shape once.
+#[derive(Debug)]
+pub struct ComputeUnitPlanner {
+    margin_bps: u64,
+    cache: HashMap<InstructionShape, u32>,
+}
+
+impl Default for ComputeUnitPlanner {
+    fn default() -> Self {
+        Self::new(DEFAULT_COMPUTE_UNIT_MARGIN_BPS)
+    }
+}
+
+impl ComputeUnitPlanner {
+    pub fn new(margin_bps: u64) -> Self {
+        Self { margin_bps, cache: HashMap::new() }
+    }
+
+    /// Compute unit limit for `instructions` (without compute budget instructions) paid by `payer`.
+    /// Simulates unseen shapes and falls back to conservative defaults when simulation fails.
+    pub async fn plan(&mut self, rpc_client: &RpcClient, payer: &Pubkey, instructions: &[Instruction]) -> ComputeUnitPlan {
+        let shape = InstructionShape::of(instructions);
+        if let Some(&compute_units) = self.cache.get(&shape) {
+            return ComputeUnitPlan { compute_units, source: ComputeUnitSource::Cached };
+        }
+
+        match simulate_compute_units(rpc_client, payer, instructions).await {
+            Some(units_consumed) => {
+                let with_margin = units_consumed as u128 * (10_000 + self.margin_bps) as u128 / 10_000;
+                let compute_units = with_margin.min(MAX_COMPUTE_UNIT_LIMIT as u128) as u32;
+                self.cache.insert(shape, compute_units);
+                ComputeUnitPlan { compute_units, source: ComputeUnitSource::Simulated { units_consumed } }
+            }
+            // Not cached, so the next plan tries simulating again
+            None => ComputeUnitPlan { compute_units: shape.fallback_compute_units(), source: ComputeUnitSource::Fallback },
+        }
+    }
+}
+
+// Units consumed by a successful simulation at the maximum limit, `None` if it failed
+async fn simulate_compute_units(rpc_client: &RpcClient, payer: &Pubkey, instructions: &[Instruction]) -> Option<u64> {
+    let mut simulated = vec![ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT)];
+    simulated.extend(instructions.iter().filter(|ix| ix.program_id != compute_budget::id()).cloned());
+    let transaction = Transaction::new_unsigned(Message::new(&simulated, Some(payer)));
+
+    let result = rpc_client
+        .simulate_transaction_with_config(
+            &transaction,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(CommitmentConfig::confirmed()),
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )
+        .await
+        .ok()?
+        .value;
+    match result.err {
+        None => result.units_consumed,
+        Some(_) => None,
+    }
+}
+
+pub fn create_compute_budget_instructions(compute_units: u32, priority_fee: u64) -> Vec<Instruction> {
+    vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(compute_units),
+        ComputeBudgetInstruction::set_compute_unit_price(priority_fee),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        serde_json::json,
+        solana_client::{rpc_request::RpcRequest, rpc_response::RpcSimulateTransactionResult},
+        solana_sdk::{hash::Hash, pubkey::Pubkey, signature::Keypair},
+        std::collections::HashMap,
+    };
+
+    fn preflight_failure(err: Option<TransactionError>) -> ClientError {
+        ClientError::from(ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            code: -32002,
+            message: "Transaction simulation failed".to_string(),
+            data: RpcResponseErrorData::SendTransactionPreflightFailure(RpcSimulateTransactionResult {
+                err,
+                logs: Some(vec!["Program log: Error".to_string()]),
+                accounts: None,
+                units_consumed: None,
+                return_data: None,
+                inner_instructions: None,
+            }),
+        }))
+    }
+
+    fn instructions() -> Vec<Instruction> {
+        let mut instructions = create_compute_budget_instructions(100_000, 1);
+        instructions.push(Instruction::new_with_bytes(pump_program_id(), &[], vec![]));
+        instructions
+    }
+
+    #[test]
+    fn test_classifies_pump_program_errors() {
+        let err = preflight_failure(Some(TransactionError::InstructionError(2, InstructionError::Custom(6002))));
+        match TxError::from_client_error(err, &instructions()) {
+            TxError::Program { code, error, logs } => {
+                assert_eq!(code, 6002);
+                assert_eq!(error, Some(PumpError::TooMuchSolRequired));
+                assert_eq!(logs.len(), 1);
+            }
+            other => panic!("Expected program error, got {:?}", other),
+        }
+
+        let err = preflight_failure(Some(TransactionError::InstructionError(2, InstructionError::Custom(6005))));
+        let err = TxError::from_client_error(err, &instructions());
+        assert!(matches!(err, TxError::Program { error: Some(PumpError::BondingCurveComplete), .. }));
+        assert!(!err.is_retryable());
+
+        // Custom errors from other programs are not attributed to pump
+        let err = preflight_failure(Some(TransactionError::InstructionError(0, InstructionError::Custom(6002))));
+        assert!(matches!(TxError::from_client_error(err, &instructions()), TxError::Preflight { .. }));
+    }
+
+    #[test]
+    fn test_classifies_funds_blockhash_and_transport_errors() {
+        let err = preflight_failure(Some(TransactionError::InsufficientFundsForFee));
+        assert!(matches!(TxError::from_client_error(err, &instructions()), TxError::InsufficientFunds));
+
+        let mut ixs = instructions();
+        ixs.push(solana_sdk::system_instruction::transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1));
+        let err = preflight_failure(Some(TransactionError::InstructionError(3, InstructionError::Custom(1))));
+        assert!(matches!(TxError::from_client_error(err, &ixs), TxError::InsufficientFunds));
+
+        let err = preflight_failure(Some(TransactionError::BlockhashNotFound));
+        let err = TxError::from_client_error(err, &instructions());
+        assert!(matches!(err, TxError::BlockhashExpired));
+        assert!(err.is_retryable());
+
+        // Expiry from our confirmation paths is typed, the RPC client's spinner only has its message
+        let err = ClientError::from(ConfirmationExpired(Signature::new_unique()));
+        assert!(matches!(TxError::from_client_error(err, &instructions()), TxError::BlockhashExpired));
+        let err = ClientError::from(RpcError::ForUser("unable to confirm transaction. This can happen in situations such as transaction expiration".to_string()));
+        assert!(matches!(TxError::from_client_error(err, &instructions()), TxError::BlockhashExpired));
+        let err = ClientError::from(std::io::Error::new(std::io::ErrorKind::TimedOut, "read timed out"));
+        assert!(matches!(TxError::from_client_error(err, &instructions()), TxError::Rpc(_)));
+
+        let err = ClientError::from(ClientErrorKind::Custom("connection reset".to_string()));
+        let err = TxError::from_client_error(err, &instructions());
+        assert!(matches!(err, TxError::Rpc(_)));
+        assert!(err.is_retryable());
+
+        let err = preflight_failure(None);
+        assert!(matches!(TxError::from_client_error(err, &instructions()), TxError::Preflight { .. }));
+    }
+
+    #[test]
+    fn test_pump_error_codes() {
+        assert_eq!(PumpError::from_code(6003), Some(PumpError::TooLittleSolReceived));
+        assert_eq!(PumpError::from_code(5999), None);
+        assert_eq!(PumpError::from_code(7000), None);
+    }
+
+    fn signed_transaction(keypair: &Keypair) -> VersionedTransaction {
+        let ix = solana_sdk::system_instruction::transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1);
+        Transaction::new_signed_with_payer(&[ix], Some(&keypair.pubkey()), &[keypair], Hash::new_unique()).into()
+    }
+
+    fn mock_rpc(statuses: serde_json::Value, block_height: u64) -> RpcClient {
+        let mut mocks = HashMap::new();
+        mocks.insert(RpcRequest::GetSignatureStatuses, json!({"context": {"slot": 100}, "value": statuses}));
+        mocks.insert(RpcRequest::GetBlockHeight, json!(block_height));
+        RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks)
+    }
+
+    fn status(slot: u64, err: Option<serde_json::Value>) -> serde_json::Value {
+        status_at(slot, err, "confirmed")
+    }
+
+    fn status_at(slot: u64, err: Option<serde_json::Value>, confirmation_status: &str) -> serde_json::Value {
+        json!({
+            "slot": slot,
+            "confirmations": null,
+            "err": err,
+            "status": match &err { Some(err) => json!({"Err": err}), None => json!({"Ok": null}) },
+            "confirmationStatus": confirmation_status,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_tracker_reports_landed_attempt() {
+        let keypair = Keypair::new();
+        let mut tracker = AttemptTracker::new();
+        let first = signed_transaction(&keypair);
+        let second = signed_transaction(&keypair);
+        assert_eq!(tracker.record(&first, 200), 1);
+        assert_eq!(tracker.record(&second, 300), 2);
+
+        // The first attempt failed on-chain, the second executed
+        let rpc = mock_rpc(json!([status(90, Some(json!("AccountInUse"))), status(95, None)]), 150);
+        assert_eq!(
+            tracker.check(&rpc).await.unwrap(),
+            PriorAttempts::Landed { attempt: 2, signature: second.signatures[0], slot: 95 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tracker_waits_until_blockhash_expires() {
+        let keypair = Keypair::new();
+        let mut tracker = AttemptTracker::new();
+        tracker.record(&signed_transaction(&keypair), 200);
+        tracker.record(&signed_transaction(&keypair), 300);
+
+        // Unknown signatures with live blockhashes could still land
+        let rpc = mock_rpc(json!([null, null]), 250);
+        assert_eq!(tracker.check(&rpc).await.unwrap(), PriorAttempts::Pending);
+        assert_eq!(tracker.attempts().len(), 1);
+        assert_eq!(tracker.attempts()[0].attempt, 2);
+
+        let rpc = mock_rpc(json!([null]), 301);
+        assert_eq!(tracker.check(&rpc).await.unwrap(), PriorAttempts::Clear);
+        assert!(tracker.attempts().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tracker_keeps_executed_attempts_past_expiry() {
+        let keypair = Keypair::new();
+        let mut tracker = AttemptTracker::new();
+        let landed = signed_transaction(&keypair);
+        tracker.record(&landed, 200);
+
+        // Executed in the last slots of its blockhash window, only processed so far
+        let rpc = mock_rpc(json!([status_at(199, None, "processed")]), 250);
+        assert_eq!(tracker.check(&rpc).await.unwrap(), PriorAttempts::Pending);
+        assert_eq!(tracker.attempts()[0].signature, landed.signatures[0]);
+
+        let rpc = mock_rpc(json!([status(199, None)]), 260);
+        assert_eq!(
+            tracker.check(&rpc).await.unwrap(),
+            PriorAttempts::Landed { attempt: 1, signature: landed.signatures[0], slot: 199 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tracker_forgets_rejected_attempts() {
+        let keypair = Keypair::new();
+        let mut tracker = AttemptTracker::new();
+        let rejected = signed_transaction(&keypair);
+        tracker.record(&rejected, 200);
+        tracker.forget(&rejected.signatures[0]);
+
+        // No RPC call needed when nothing can land
+        let rpc = RpcClient::new_mock("fails".to_string());
+        assert_eq!(tracker.check(&rpc).await.unwrap(), PriorAttempts::Clear);
+        // Attempt numbers keep counting across forgotten attempts
+        assert_eq!(tracker.record(&signed_transaction(&keypair), 200), 2);
+    }
+
+    #[test]
+    fn test_attempts_compile_against_lookup_tables() {
+        let keypair = Keypair::new();
+        let destination = Pubkey::new_unique();
+        let ix = solana_sdk::system_instruction::transfer(&keypair.pubkey(), &destination, 1);
+        let blockhash = Hash::new_unique();
+
+        let legacy = sign_attempt(&keypair, std::slice::from_ref(&ix), &[], blockhash).unwrap();
+        assert!(matches!(legacy.message, VersionedMessage::Legacy(_)));
+
+        let table = AddressLookupTableAccount { key: Pubkey::new_unique(), addresses: vec![destination] };
+        let compiled = sign_attempt(&keypair, &[ix], &[table], blockhash).unwrap();
+        let VersionedMessage::V0(message) = &compiled.message else { panic!("expected a v0 message") };
+        assert_eq!(message.address_table_lookups.len(), 1);
+        assert!(!message.account_keys.contains(&destination));
+        assert!(compiled.verify_with_results().iter().all(|verified| *verified));
+        assert_eq!(*compiled.message.recent_blockhash(), blockhash);
+    }
+
+    #[tokio::test]
+    async fn test_attempts_sharing_a_nonce_do_not_wait() {
+        let keypair = Keypair::new();
+        let nonce = DurableNonce {
+            account: Pubkey::new_unique(),
+            authority: keypair.pubkey(),
+            nonce: Hash::new_unique(),
+            lamports_per_signature: 5_000,
+        };
+        let mut tracker = AttemptTracker::new().with_durable_nonce(nonce);
+        let transfer = solana_sdk::system_instruction::transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1);
+        tracker.record(&nonce.sign(&keypair, &[transfer]).unwrap().into(), 300);
+
+        // Still pending, but a new attempt with the same nonce cannot execute alongside it
+        let rpc = mock_rpc(json!([null]), 250);
+        let prior = wait_for_prior_attempts(&rpc, &mut tracker, &[], Instant::now()).await.unwrap();
+        assert!(matches!(prior, PriorWait::Clear { waited: false }));
+        assert_eq!(tracker.attempts().len(), 1);
+
+        // After the nonce was refreshed, the attempt signed against the old one must be waited for
+        tracker.durable_nonce = Some(DurableNonce { nonce: Hash::new_unique(), ..nonce });
+        assert!(!tracker.attempts_share_nonce());
+    }
+
+    fn buy_instructions(payer: &Pubkey, token_amount: u64) -> Vec<Instruction> {
+        let mint = Pubkey::new_unique();
+        vec![
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                payer,
+                payer,
+                &mint,
+                &spl_token::id(),
+            ),
+            crate::core::instructions::create_buy_instruction(
+                payer,
+                &mint,
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                token_amount,
+                1_000_000,
+            )
+            .unwrap(),
+        ]
+    }
+
+    #[test]
+    fn test_instruction_shape_ignores_amounts_and_budget() {
+        let payer = Pubkey::new_unique();
+        let buy = buy_instructions(&payer, 1_000);
+        let mut budgeted = create_compute_budget_instructions(63_665, 2);
+        budgeted.extend(buy_instructions(&payer, 2_000));
+        assert_eq!(InstructionShape::of(&buy), InstructionShape::of(&budgeted));
+        assert_ne!(InstructionShape::of(&buy), InstructionShape::of(&buy[1..]));
+
+        assert_eq!(InstructionShape::of(&buy).fallback_compute_units(), 130_000);
+        assert_eq!(InstructionShape::of(&buy[1..]).fallback_compute_units(), 100_000);
+    }
+
+    #[tokio::test]
+    async fn test_planner_simulates_once_per_shape() {
+        let payer = Pubkey::new_unique();
+        let mut mocks = HashMap::new();
+        mocks.insert(
+            RpcRequest::SimulateTransaction,
+            json!({"context": {"slot": 1}, "value": {
+                "err": null, "logs": [], "accounts": null, "unitsConsumed": 50_000, "returnData": null,
+            }}),
+        );
+        let rpc = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+        let mut planner = ComputeUnitPlanner::default();
+
+        let plan = planner.plan(&rpc, &payer, &buy_instructions(&payer, 1_000)).await;
+        assert_eq!(plan, ComputeUnitPlan {
+            compute_units: 60_000,
+            source: ComputeUnitSource::Simulated { units_consumed: 50_000 },
+        });
+        let plan = planner.plan(&rpc, &payer, &buy_instructions(&payer, 5_000)).await;
+        assert_eq!(plan, ComputeUnitPlan { compute_units: 60_000, source: ComputeUnitSource::Cached });
+
+        // The default mock response has no units consumed, so a new shape falls back
+        let plan = planner.plan(&rpc, &payer, &buy_instructions(&payer, 1_000)[1..]).await;
+        assert_eq!(plan, ComputeUnitPlan { compute_units: 100_000, source: ComputeUnitSource::Fallback });
+    }
+
+    #[tokio::test]
+    async fn test_planner_falls_back_without_rpc() {
+        let payer = Pubkey::new_unique();
+        let rpc = RpcClient::new_mock("fails".to_string());
+        let mut planner = ComputeUnitPlanner::new(1_000);
+        let plan = planner.plan(&rpc, &payer, &buy_instructions(&payer, 1_000)).await;
+        assert_eq!(plan.source, ComputeUnitSource::Fallback);
+        assert_eq!(plan.compute_units, 130_000);
+    }
+}
@@ -0,0 +1,169 @@
+use {
+    anyhow::Result,
+    solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        hash::Hash,
+        instruction::Instruction,
+        pubkey::Pubkey,
+        signature::Keypair,
+        signer::Signer,
+        system_instruction,
+        transaction::Transaction,
+    },
+    std::{env, path::PathBuf},
+    crate::core::{
+        fees::{priority_fee_lamports, BASE_FEE_LAMPORTS_PER_SIGNATURE},
+        wallet::WalletSource,
+    },
+};
+
+// Transfers that fit a 1232 byte legacy transaction next to the compute budget instructions.
+// Funding needs one key per recipient, sweeping also a signature per source wallet.
+pub const FUND_TRANSFERS_PER_TRANSACTION: usize = 20;
+pub const SWEEP_TRANSFERS_PER_TRANSACTION: usize = 8;
+
+// Rent-exempt minimum of an account without data, a wallet must stay at 0 or above it
+pub const RENT_EXEMPT_MINIMUM_LAMPORTS: u64 = 890_880;
+
+// A system transfer uses 150 CU, the rest is headroom for the compute budget instructions
+const COMPUTE_UNITS_PER_TRANSFER: u32 = 300;
+const BATCH_BASE_COMPUTE_UNITS: u32 = 1_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TreasuryError {
+    #[error("no treasury configured, set TREASURY_KEYSTORE, TREASURY_KEYPAIR_FILE or TREASURY_PRIVATE_KEY")]
+    NotConfigured,
+    #[error("{0} lamports is below the rent-exempt minimum of {RENT_EXEMPT_MINIMUM_LAMPORTS}, use 0 or at least that")]
+    BelowRentExemption(u64),
+    #[error("the treasury holds {available} lamports, {required} are needed including fees")]
+    InsufficientFunds { available: u64, required: u64 },
+}
+
+/// `TREASURY_KEYSTORE`, then `TREASURY_KEYPAIR_FILE`, then `TREASURY_PRIVATE_KEY`.
+pub fn treasury_source_from_env() -> Result<WalletSource> {
+    if let Ok(path) = env::var("TREASURY_KEYSTORE") {
+        return Ok(WalletSource::Keystore(PathBuf::from(path)));
+    }
+    if let Ok(path) = env::var("TREASURY_KEYPAIR_FILE") {
+        return Ok(WalletSource::KeypairFile(PathBuf::from(path)));
+    }
+    if env::var_os("TREASURY_PRIVATE_KEY").is_some() {
+        return Ok(WalletSource::Env("TREASURY_PRIVATE_KEY".to_string()));
+    }
+    Err(TreasuryError::NotConfigured.into())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transfer {
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub lamports: u64,
+}
+
+// Below the minimum the runtime rejects the transfer that leaves the account there
+fn check_rent_exemption(lamports: u64) -> Result<()> {
+    if lamports > 0 && lamports < RENT_EXEMPT_MINIMUM_LAMPORTS {
+        return Err(TreasuryError::BelowRentExemption(lamports).into());
+    }
+    Ok(())
+}
+
+/// Transfers from the treasury topping each of `balances` up to `target`. Wallets already
+/// at or above it, and the treasury itself, get nothing.
+pub fn plan_fund(treasury: &Pubkey, balances: &[(Pubkey, u64)], target: u64) -> Result<Vec<Transfer>> {
+    check_rent_exemption(target)?;
+    Ok(balances
+        .iter()
+        .filter(|(wallet, balance)| wallet != treasury && *balance < target)
+        .map(|&(wallet, balance)| Transfer { from: *treasury, to: wallet, lamports: target - balance })
+        .collect())
+}
+
+/// Transfers to the treasury of everything each of `balances` holds above `floor`. The
+/// treasury pays the fees, so a wallet ends at exactly the floor; excesses that would not
+/// cover their own signature fee are left where they are.
+pub fn plan_sweep(treasury: &Pubkey, balances: &[(Pubkey, u64)], floor: u64) -> Result<Vec<Transfer>> {
+    check_rent_exemption(floor)?;
+    Ok(balances
+        .iter()
+        .filter(|(wallet, balance)| wallet != treasury && balance.saturating_sub(floor) > BASE_FEE_LAMPORTS_PER_SIGNATURE)
+        .map(|&(wallet, balance)| Transfer { from: wallet, to: *treasury, lamports: balance - floor })
+        .collect())
+}
+
+/// Instructions of one batch: the compute budget sized for the transfers, then the transfers.
+pub fn batch_instructions(transfers: &[Transfer], compute_unit_price: u64) -> Vec<Instruction> {
+    let mut instructions = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(batch_compute_units(transfers.len())),
+        ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price),
+    ];
+    instructions.extend(transfers.iter().map(|transfer| system_instruction::transfer(&transfer.from, &transfer.to, transfer.lamports)));
+    instructions
+}
+
+fn batch_compute_units(transfers: usize) -> u32 {
+    BATCH_BASE_COMPUTE_UNITS + COMPUTE_UNITS_PER_TRANSFER * transfers as u32
+}
+
+/// Most the fee payer is charged for a batch of `transfers` with `signatures` signatures.
+pub fn batch_fee(transfers: usize, signatures: usize, compute_unit_price: u64) -> u64 {
+    signatures as u64 * BASE_FEE_LAMPORTS_PER_SIGNATURE + priority_fee_lamports(batch_compute_units(transfers), compute_unit_price)
+}
+
+/// Signs a batch paid for by the treasury, with every source wallet in `signers` co-signing.
+pub fn sign_batch(instructions: &[Instruction], treasury: &Keypair, signers: &[&Keypair], recent_blockhash: Hash) -> Result<Transaction> {
+    let mut all_signers = vec![treasury];
+    all_signers.extend(signers.iter().copied().filter(|signer| signer.pubkey() != treasury.pubkey()));
+    let mut transaction = Transaction::new_with_payer(instructions, Some(&treasury.pubkey()));
+    transaction.try_sign(&all_signers, recent_blockhash)?;
+    Ok(transaction)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::packet::PACKET_DATA_SIZE};
+
+    #[test]
+    fn test_plans() {
+        let treasury = Pubkey::new_unique();
+        let (low, high) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let balances = [(low, 100_000_000), (high, 600_000_000), (treasury, 0)];
+
+        // Only wallets below the target are topped up, never the treasury
+        let fund = plan_fund(&treasury, &balances, 500_000_000).unwrap();
+        assert_eq!(fund, vec![Transfer { from: treasury, to: low, lamports: 400_000_000 }]);
+
+        let sweep = plan_sweep(&treasury, &balances, 200_000_000).unwrap();
+        assert_eq!(sweep, vec![Transfer { from: high, to: treasury, lamports: 400_000_000 }]);
+        // A zero floor empties wallets, dust that would not pay its own signature stays
+        let sweep = plan_sweep(&treasury, &[(low, 100_000_000), (high, 4_000)], 0).unwrap();
+        assert_eq!(sweep, vec![Transfer { from: low, to: treasury, lamports: 100_000_000 }]);
+
+        // Leaving a wallet funded but below rent exemption would fail on chain
+        assert!(plan_fund(&treasury, &balances, 1_000).is_err());
+        assert!(plan_sweep(&treasury, &balances, 1_000).is_err());
+    }
+
+    #[test]
+    fn test_full_batches_fit_a_transaction() {
+        let treasury = Keypair::new();
+        let fund: Vec<Transfer> = (0..FUND_TRANSFERS_PER_TRANSACTION)
+            .map(|_| Transfer { from: treasury.pubkey(), to: Pubkey::new_unique(), lamports: 1_000_000_000 })
+            .collect();
+        let transaction = sign_batch(&batch_instructions(&fund, 1_000), &treasury, &[], Hash::new_unique()).unwrap();
+        assert!(bincode::serialize(&transaction).unwrap().len() <= PACKET_DATA_SIZE);
+
+        let wallets: Vec<Keypair> = (0..SWEEP_TRANSFERS_PER_TRANSACTION).map(|_| Keypair::new()).collect();
+        let sweep: Vec<Transfer> = wallets
+            .iter()
+            .map(|wallet| Transfer { from: wallet.pubkey(), to: treasury.pubkey(), lamports: 1_000_000_000 })
+            .collect();
+        let signers: Vec<&Keypair> = wallets.iter().collect();
+        let transaction = sign_batch(&batch_instructions(&sweep, 1_000), &treasury, &signers, Hash::new_unique()).unwrap();
+        assert_eq!(transaction.signatures.len(), SWEEP_TRANSFERS_PER_TRANSACTION + 1);
+        transaction.verify().unwrap();
+        assert!(bincode::serialize(&transaction).unwrap().len() <= PACKET_DATA_SIZE);
+
+        assert_eq!(batch_fee(SWEEP_TRANSFERS_PER_TRANSACTION, 9, 1_000_000), 9 * 5_000 + 3_400);
+    }
+}
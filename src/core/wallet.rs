@@ -1,20 +1,154 @@
 use {
     anyhow::Result,
-    solana_client::rpc_client::RpcClient,
-    solana_sdk::signature::{Keypair, Signer},
-    std::env,
-    crate::utils::utils::format_sol_amount,
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+    },
+    serde::{Deserialize, Serialize},
+    std::{env, fs, path::PathBuf},
+    zeroize::Zeroizing,
+    crate::{core::keystore::Keystore, utils::utils::format_sol_amount},
 };
 
+/// Errors loading or unlocking a wallet.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WalletError {
+    #[error("no wallet configured, set WALLET_KEYSTORE, WALLET_KEYPAIR_FILE or PRIVATE_KEY")]
+    NotConfigured,
+    #[error("invalid secret key: {0}")]
+    InvalidKey(String),
+    #[error("wrong password or corrupted keystore")]
+    Decrypt,
+    #[error("unsupported keystore: {0}")]
+    UnsupportedKeystore(String),
+}
+
+/// Where the trading keypair is read from. Secret key material read along the way is
+/// zeroized once the keypair is built.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WalletSource {
+    /// Base58 encoded secret key in an environment variable
+    Env(String),
+    /// Solana CLI JSON keypair file, an array of 64 bytes
+    KeypairFile(PathBuf),
+    /// Encrypted keystore written by `wallet import`, unlocked with a password
+    Keystore(PathBuf),
+}
+
+impl WalletSource {
+    /// `WALLET_KEYSTORE`, then `WALLET_KEYPAIR_FILE`, then `PRIVATE_KEY`.
+    pub fn from_env() -> Result<Self> {
+        if let Ok(path) = env::var("WALLET_KEYSTORE") {
+            return Ok(Self::Keystore(PathBuf::from(path)));
+        }
+        if let Ok(path) = env::var("WALLET_KEYPAIR_FILE") {
+            return Ok(Self::KeypairFile(PathBuf::from(path)));
+        }
+        if env::var_os("PRIVATE_KEY").is_some() {
+            return Ok(Self::Env("PRIVATE_KEY".to_string()));
+        }
+        Err(WalletError::NotConfigured.into())
+    }
+
+    /// Loads the keypair. A keystore password comes from `KEYSTORE_PASSWORD` or a prompt.
+    pub fn load(&self) -> Result<Keypair> {
+        match self {
+            Self::Env(name) => {
+                let encoded = Zeroizing::new(env::var(name).map_err(|_| WalletError::NotConfigured)?);
+                keypair_from_base58(&encoded)
+            }
+            Self::KeypairFile(path) => {
+                let contents = Zeroizing::new(fs::read_to_string(path)?);
+                keypair_from_json(&contents)
+            }
+            Self::Keystore(path) => Keystore::load(path)?.decrypt(read_password("🔑 Keystore password: ")?.as_bytes()),
+        }
+    }
+
+    /// The wallet's public key. Keystores store it in the clear, so no password is needed.
+    pub fn pubkey(&self) -> Result<Pubkey> {
+        match self {
+            Self::Keystore(path) => Keystore::load(path)?.pubkey(),
+            _ => Ok(self.load()?.pubkey()),
+        }
+    }
+}
+
+/// Keypair from a base58 encoded 64 byte secret key, as exported by Phantom.
+pub fn keypair_from_base58(encoded: &str) -> Result<Keypair> {
+    let bytes = Zeroizing::new(bs58::decode(encoded.trim()).into_vec().map_err(|err| WalletError::InvalidKey(err.to_string()))?);
+    Ok(Keypair::from_bytes(&bytes).map_err(|err| WalletError::InvalidKey(err.to_string()))?)
+}
+
+/// Keypair from the contents of a Solana CLI keypair file.
+pub fn keypair_from_json(contents: &str) -> Result<Keypair> {
+    let bytes: Zeroizing<Vec<u8>> = Zeroizing::new(serde_json::from_str(contents).map_err(|err| WalletError::InvalidKey(err.to_string()))?);
+    Ok(Keypair::from_bytes(&bytes).map_err(|err| WalletError::InvalidKey(err.to_string()))?)
+}
+
+/// `KEYSTORE_PASSWORD` when set (for unattended boxes), otherwise an interactive prompt.
+pub fn read_password(prompt: &str) -> Result<Zeroizing<String>> {
+    if let Ok(password) = env::var("KEYSTORE_PASSWORD") {
+        return Ok(Zeroizing::new(password));
+    }
+    Ok(Zeroizing::new(rpassword::prompt_password(prompt)?))
+}
+
 pub fn load_wallet() -> Result<Keypair> {
-    let private_key = env::var("PRIVATE_KEY").expect("PRIVATE_KEY must be set");
-    let keypair = Keypair::from_base58_string(&private_key);
-    Ok(keypair)
+    WalletSource::from_env()?.load()
+}
+
+/// The signer daemon at `SIGNER_SOCKET` when one is configured, so this process never
+/// holds the key, otherwise the wallet from `load_wallet`.
+pub fn load_signer() -> Result<Box<dyn Signer>> {
+    #[cfg(unix)]
+    if let Some(signer) = crate::core::remote_signer::RemoteSigner::from_env()? {
+        return Ok(Box::new(signer));
+    }
+    Ok(Box::new(load_wallet()?))
 }
 
-pub fn print_wallet_info(rpc_client: &RpcClient, keypair: &Keypair) -> Result<()> {
-    let balance = rpc_client.get_balance(&keypair.pubkey())?;
-    println!("Wallet: {}", keypair.pubkey());
-    println!("Balance: {}", format_sol_amount(balance));
+pub async fn print_wallet_info(rpc_client: &RpcClient, wallet: &Pubkey) -> Result<()> {
+    let balance = rpc_client.get_balance(wallet).await?;
+    print_wallet_balance(wallet, balance);
     Ok(())
-} 
\ No newline at end of file
+}
+
+/// Prints a wallet balance that was already fetched, e.g. as part of a `TradeSnapshot`.
+pub fn print_wallet_balance(wallet: &Pubkey, lamports: u64) {
+    println!("Wallet: {}", wallet);
+    println!("Balance: {}", format_sol_amount(lamports));
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::core::keystore::KdfParams,
+    };
+
+    #[test]
+    fn test_keypair_sources() {
+        let keypair = Keypair::new();
+        assert_eq!(keypair_from_base58(&keypair.to_base58_string()).unwrap().pubkey(), keypair.pubkey());
+        let json = serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap();
+        assert_eq!(keypair_from_json(&json).unwrap().pubkey(), keypair.pubkey());
+
+        // Malformed keys are errors rather than panics
+        assert!(keypair_from_base58("not a key").is_err());
+        assert!(keypair_from_json("[1, 2, 3]").is_err());
+
+        let directory = tempfile::tempdir().unwrap();
+        let file = directory.path().join("id.json");
+        fs::write(&file, json).unwrap();
+        assert_eq!(WalletSource::KeypairFile(file.clone()).pubkey().unwrap(), keypair.pubkey());
+
+        // The keystore public key is read without a password
+        let keystore = directory.path().join("wallet.json");
+        let params = KdfParams { memory_kib: 64, iterations: 1, parallelism: 1 };
+        Keystore::encrypt_with_params(&keypair, b"pw", params).unwrap().save(&keystore).unwrap();
+        assert_eq!(WalletSource::Keystore(keystore).pubkey().unwrap(), keypair.pubkey());
+    }
+}
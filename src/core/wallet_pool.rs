@@ -0,0 +1,237 @@
+use {
+    anyhow::{Context, Result},
+    serde::{Deserialize, Serialize},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_program::program_pack::Pack,
+    solana_sdk::{pubkey::Pubkey, signature::Keypair},
+    std::{env, fs, path::{Path, PathBuf}},
+    crate::{core::wallet::WalletSource, utils::utils::get_token_account},
+};
+
+pub const DEFAULT_WALLET_POOL_FILE: &str = "wallets.json";
+
+// Accounts per getMultipleAccounts request
+const MULTIPLE_ACCOUNTS_CHUNK_SIZE: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PoolError {
+    #[error("no wallet labeled {0} in the pool")]
+    UnknownLabel(String),
+    #[error("a wallet labeled {0} is already in the pool")]
+    DuplicateLabel(String),
+    #[error("the wallet pool is empty, add wallets with `pool add`")]
+    Empty,
+    #[error("the selected wallets all have a zero weight")]
+    ZeroWeight,
+}
+
+/// A labeled wallet in the pool. Only where its key lives is stored, never the key itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolEntry {
+    pub label: String,
+    #[serde(flatten)]
+    pub source: WalletSource,
+    /// Share of pool buys relative to the other selected wallets, 0 keeps the wallet out of buys
+    #[serde(default = "default_weight")]
+    pub weight: u64,
+}
+
+fn default_weight() -> u64 {
+    1
+}
+
+/// The wallets the team trades from, stored as JSON at `WALLET_POOL_FILE`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletRegistry {
+    pub wallets: Vec<PoolEntry>,
+}
+
+impl WalletRegistry {
+    /// `WALLET_POOL_FILE`, or `wallets.json` in the working directory.
+    pub fn path_from_env() -> PathBuf {
+        env::var("WALLET_POOL_FILE").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from(DEFAULT_WALLET_POOL_FILE))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path).with_context(|| format!("reading wallet pool {}", path.display()))?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// An empty registry when the file does not exist yet.
+    pub fn load_or_default(path: &Path) -> Result<Self> {
+        if path.exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn add(&mut self, entry: PoolEntry) -> Result<()> {
+        if self.wallets.iter().any(|wallet| wallet.label == entry.label) {
+            return Err(PoolError::DuplicateLabel(entry.label).into());
+        }
+        self.wallets.push(entry);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, label: &str) -> Result<PoolEntry> {
+        let index = self
+            .wallets
+            .iter()
+            .position(|wallet| wallet.label == label)
+            .ok_or_else(|| PoolError::UnknownLabel(label.to_string()))?;
+        Ok(self.wallets.remove(index))
+    }
+
+    /// The wallets with the given labels in that order, or every wallet when `labels` is empty.
+    pub fn select(&self, labels: &[String]) -> Result<Vec<&PoolEntry>> {
+        if self.wallets.is_empty() {
+            return Err(PoolError::Empty.into());
+        }
+        if labels.is_empty() {
+            return Ok(self.wallets.iter().collect());
+        }
+        labels
+            .iter()
+            .map(|label| {
+                self.wallets
+                    .iter()
+                    .find(|wallet| &wallet.label == label)
+                    .ok_or_else(|| PoolError::UnknownLabel(label.clone()).into())
+            })
+            .collect()
+    }
+}
+
+/// A pool wallet with its keypair loaded.
+#[derive(Debug)]
+pub struct PoolWallet {
+    pub label: String,
+    pub weight: u64,
+    pub keypair: Keypair,
+}
+
+/// Loads the keypairs of `entries`. Each keystore asks for its own password unless
+/// `KEYSTORE_PASSWORD` is set.
+pub fn load_pool_wallets(entries: &[&PoolEntry]) -> Result<Vec<PoolWallet>> {
+    entries
+        .iter()
+        .map(|entry| {
+            if matches!(entry.source, WalletSource::Keystore(_)) {
+                println!("🔓 Unlocking {}", entry.label);
+            }
+            let keypair = entry.source.load().with_context(|| format!("loading wallet {}", entry.label))?;
+            Ok(PoolWallet { label: entry.label.clone(), weight: entry.weight, keypair })
+        })
+        .collect()
+}
+
+/// Splits `total` proportionally to `weights`, handing the rounding remainder to the largest
+/// fractional shares so the parts add up to `total` exactly.
+pub fn split_by_weight(total: u64, weights: &[u64]) -> Result<Vec<u64>> {
+    let weight_sum: u128 = weights.iter().map(|&weight| weight as u128).sum();
+    if weight_sum == 0 {
+        return Err(PoolError::ZeroWeight.into());
+    }
+    let mut parts: Vec<u64> = weights.iter().map(|&weight| (total as u128 * weight as u128 / weight_sum) as u64).collect();
+    let mut remainders: Vec<(u128, usize)> = weights
+        .iter()
+        .enumerate()
+        .map(|(index, &weight)| (total as u128 * weight as u128 % weight_sum, index))
+        .collect();
+    // Largest remainder first, earlier wallets win ties
+    remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    let left_over = total - parts.iter().sum::<u64>();
+    for &(_, index) in remainders.iter().take(left_over as usize) {
+        parts[index] += 1;
+    }
+    Ok(parts)
+}
+
+/// Token balance of `token_mint` held by each wallet, 0 where the token account does not exist.
+pub async fn fetch_token_holdings(rpc_client: &RpcClient, wallets: &[Pubkey], token_mint: &Pubkey) -> Result<Vec<u64>> {
+    let token_accounts: Vec<Pubkey> = wallets.iter().map(|wallet| get_token_account(wallet, token_mint)).collect();
+    let mut holdings = Vec::with_capacity(wallets.len());
+    for chunk in token_accounts.chunks(MULTIPLE_ACCOUNTS_CHUNK_SIZE) {
+        for account in rpc_client.get_multiple_accounts(chunk).await? {
+            let amount = match account {
+                Some(account) => spl_token::state::Account::unpack(&account.data)?.amount,
+                None => 0,
+            };
+            holdings.push(amount);
+        }
+    }
+    Ok(holdings)
+}
+
+/// Lamports held by each wallet, 0 where the account does not exist.
+pub async fn fetch_balances(rpc_client: &RpcClient, wallets: &[Pubkey]) -> Result<Vec<u64>> {
+    let mut balances = Vec::with_capacity(wallets.len());
+    for chunk in wallets.chunks(MULTIPLE_ACCOUNTS_CHUNK_SIZE) {
+        let accounts = rpc_client.get_multiple_accounts(chunk).await?;
+        balances.extend(accounts.into_iter().map(|account| account.map_or(0, |account| account.lamports)));
+    }
+    Ok(balances)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(label: &str, weight: u64) -> PoolEntry {
+        PoolEntry { label: label.to_string(), source: WalletSource::Env(format!("{}_KEY", label)), weight }
+    }
+
+    #[test]
+    fn test_split_by_weight() {
+        assert_eq!(split_by_weight(1_000, &[1, 1, 2]).unwrap(), vec![250, 250, 500]);
+        // Remainders go to the largest fractional shares, the parts always add up
+        assert_eq!(split_by_weight(10, &[1, 1, 1]).unwrap(), vec![4, 3, 3]);
+        assert_eq!(split_by_weight(100, &[3, 0, 7]).unwrap(), vec![30, 0, 70]);
+        let parts = split_by_weight(u64::MAX, &[u64::MAX, 3, 1]).unwrap();
+        assert_eq!(parts.iter().map(|&part| part as u128).sum::<u128>(), u64::MAX as u128);
+        assert!(split_by_weight(100, &[0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = WalletRegistry::default();
+        assert!(matches!(registry.select(&[]).unwrap_err().downcast_ref::<PoolError>(), Some(PoolError::Empty)));
+        registry.add(entry("alpha", 2)).unwrap();
+        registry.add(entry("beta", 1)).unwrap();
+        assert!(registry.add(entry("alpha", 1)).is_err());
+
+        assert_eq!(registry.select(&[]).unwrap().len(), 2);
+        let selected = registry.select(&["beta".to_string()]).unwrap();
+        assert_eq!(selected[0].label, "beta");
+        assert!(registry.select(&["gamma".to_string()]).is_err());
+
+        assert_eq!(registry.remove("alpha").unwrap().weight, 2);
+        assert!(registry.remove("alpha").is_err());
+    }
+
+    #[test]
+    fn test_registry_file_format() {
+        let json = r#"{"wallets": [
+            {"label": "hot-1", "keystore": "keys/hot-1.json", "weight": 3},
+            {"label": "hot-2", "keypair_file": "keys/hot-2.json"},
+            {"label": "hot-3", "env": "HOT_3_PRIVATE_KEY"}
+        ]}"#;
+        let registry: WalletRegistry = serde_json::from_str(json).unwrap();
+        assert_eq!(registry.wallets[0].source, WalletSource::Keystore(PathBuf::from("keys/hot-1.json")));
+        // Weight defaults to an equal share
+        assert_eq!(registry.wallets[1].weight, 1);
+        assert_eq!(registry.wallets[2].source, WalletSource::Env("HOT_3_PRIVATE_KEY".to_string()));
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("wallets.json");
+        assert_eq!(WalletRegistry::load_or_default(&path).unwrap(), WalletRegistry::default());
+        registry.save(&path).unwrap();
+        assert_eq!(WalletRegistry::load(&path).unwrap(), registry);
+    }
+}
@@ -1,24 +1,66 @@
+// `ClientError`, and the `TxError` wrapping it, are large enough to trip `result_large_err`.
+// They are returned unboxed on purpose: callers match on them to classify failures, once
+// per RPC round trip, so boxing would cost more in readability than it saves.
+#[allow(clippy::result_large_err)]
 pub mod core {
+    pub mod blockhash;
+    pub mod broadcast;
+    pub mod confirmation;
     pub mod transaction;
     pub mod wallet;
+    pub mod wallet_pool;
     pub mod token_price;
     pub mod instructions;
+    pub mod global;
+    pub mod fees;
+    pub mod simulation;
+    pub mod snapshot;
+    pub mod swarm;
+    pub mod treasury;
+    pub mod jito;
+    pub mod keystore;
+    pub mod lookup_table;
+    pub mod nonce;
+    pub mod offline;
+    #[cfg(test)]
+    mod fixtures;
+    #[cfg(unix)]
+    pub mod remote_signer;
 }
 
 pub mod cli {
+    #[allow(clippy::module_inception)]
     pub mod cli;
 }
 
 pub mod utils {
+    #[allow(clippy::module_inception)]
     pub mod utils;
     pub mod config;
 }
 
 // Re-export commonly used items
 pub use core::{
+    blockhash::*,
+    broadcast::*,
+    confirmation::*,
+    fees::*,
+    global::*,
     instructions::*,
+    jito::*,
+    keystore::*,
+    lookup_table::*,
+    nonce::*,
+    offline::*,
+    simulation::*,
+    snapshot::*,
+    swarm::*,
     token_price::*,
     transaction::*,
+    treasury::*,
     wallet::*,
+    wallet_pool::*,
 };
+#[cfg(unix)]
+pub use core::remote_signer::*;
 pub use utils::config::*; 
\ No newline at end of file
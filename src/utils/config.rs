@@ -1,6 +1,5 @@
 use solana_program::pubkey::Pubkey;
 use std::str::FromStr;
-use spl_associated_token_account::get_associated_token_address_with_program_id;
 
 // Program IDs
 pub const PUMP_PROGRAM_ID: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
@@ -10,7 +9,7 @@ pub const ASSOCIATED_TOKEN_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25e
 
 // PDAs and Accounts
 pub const GLOBAL_PDA: &str = "4wTV1YmiEkRvAtNtsSGPtUrqRYQMe5SKy2uB4Jjaxnjf";
-pub const FEE_ACCOUNT: &str = "CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM";
+pub const FEE_ACCOUNT: &str = "CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM"; // Historical default, trades use GlobalAccount::fee_recipient
 pub const EVENT_AUTHORITY: &str = "Ce6TQqeHC9p8KetsN6JsjHK7UTZk7nasjjnr7XxXp9F1";
 pub const SYSVAR_RENT: &str = "SysvarRent111111111111111111111111111111111";
 pub const WSOL_MINT: &str = "So11111111111111111111111111111111111111112"; // Native SOL wrapped as SPL token
@@ -69,53 +68,23 @@ pub fn find_associated_bonding_curve_pda(token_mint: &Pubkey, bonding_curve: &Pu
     )
 }
 
-// Updated function to derive the creator_vault_authority PDA
+// `["creator_vault", creator]` under the pump program, the seed PumpSwap uses for its creator
+// vault authority. Not an account of the bonding curve program, see find_creator_vault_pda
+// for the vault buy/sell expect.
 pub fn find_creator_vault_authority(coin_creator: &Pubkey) -> (Pubkey, u8) {
-    println!("\n🔍 Step 1: Deriving creator vault authority for: {}", coin_creator);
-    
-    // This matches JS exactly:
-    // const creatorVaultSeed = Buffer.from('creator_vault');  // UTF-8 encoded
-    // const [coinCreatorVaultAuthority] = PublicKey.findProgramAddressSync(
-    //   [creatorVaultSeed, coinCreator.toBuffer()],
-    //   PUMP_AMM_PROGRAM_ID
-    // );
-    
-    // Use explicit UTF-8 encoding to match JavaScript's Buffer.from()
-    let creator_vault_utf8 = "creator_vault".as_bytes(); // UTF-8 encoded, same as JS Buffer.from()
-    
-    let creator_vault_pda = Pubkey::find_program_address(
-        &[creator_vault_utf8, coin_creator.as_ref()],
+    Pubkey::find_program_address(&[b"creator_vault", coin_creator.as_ref()], &pump_program_id())
+}
+
+// Creator vault the bonding curve program pays creator fees into:
+// seeds = ["creator-vault", bonding_curve.creator], owned by the pump program
+pub fn find_creator_vault_pda(coin_creator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"creator-vault", coin_creator.as_ref()],
         &pump_program_id(),
-    );
-    
-    println!("Creator vault authority: {} (bump: {})", creator_vault_pda.0, creator_vault_pda.1);
-    creator_vault_pda
+    )
 }
 
-// Updated helper to find the creator vault ATA
+// Helper to find the creator vault passed to buy/sell instructions
 pub fn find_creator_vault(coin_creator: &Pubkey) -> Pubkey {
-    // Step 1: Get the creator vault authority PDA
-    let (authority, bump) = find_creator_vault_authority(coin_creator);
-    println!("🔍 Step 2: Deriving creator vault ATA using authority: {} (bump: {})", authority, bump);
-    
-    // Step 2: This matches the JS exactly:
-    // JS: getAssociatedTokenAddressSync(
-    //   WSOL_TOKEN_ACCOUNT,           // mint is FIRST in JS
-    //   coinCreatorVaultAuthority,    // owner is SECOND in JS
-    //   true                          // allowOwnerOffCurve 
-    // )
-    let wsol = wsol_mint();
-    println!("Using WSOL mint: {}", wsol);
-    
-    // Use the proper SPL associated token address function with the correct parameter order
-    // In JavaScript: (mint, owner, allowOwnerOffCurve)
-    // In Rust: get_associated_token_address_with_program_id(wallet_address, token_mint_address, token_program_id)
-    let ata = get_associated_token_address_with_program_id(
-        &authority,     // wallet owner (the PDA from step 1)
-        &wsol,          // token mint (WSOL)
-        &token_program_id() // token program ID
-    );
-    
-    println!("Final creator vault ATA: {}", ata);
-    ata
-} 
\ No newline at end of file
+    find_creator_vault_pda(coin_creator).0
+}
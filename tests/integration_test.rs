@@ -1 +1,90 @@
- 
\ No newline at end of file
+use {
+    solana_sdk::pubkey::Pubkey,
+    std::str::FromStr,
+    solana_pump_bot::{
+        core::token_price::{resolve_creator_accounts, BondingCurveAccount},
+        utils::config::{find_associated_bonding_curve_pda, find_bonding_curve_pda, find_creator_vault, find_creator_vault_pda},
+    },
+};
+
+fn pubkey(address: &str) -> Pubkey {
+    Pubkey::from_str(address).unwrap()
+}
+
+// Fixture pairs for the pump program seeds: mint -> curve PDAs.
+//
+// There is no creator -> creator vault pair yet. An expected vault computed from
+// `["creator-vault", creator]` would only restate the derivation under test, so the pair has
+// to be read off a mainnet pump buy of a token by CREATOR (creator vault is account #10),
+// with that transaction's signature cited next to it.
+//
+// The README used to give 73Lrc9pHLuN59pBsBGL2oScGM2SHkbTZwZqTdVLPHqdf as this creator's vault,
+// read off a Solscan transaction. It does not derive from the bonding curve seeds, so it was not
+// a bonding curve buy's creator vault; test_former_readme_vault_is_not_a_curve_vault keeps it
+// from being reintroduced as one.
+const MINT: &str = "E5UbfmHh8sMVKBc1kSAHXQeFDyVJEZw7Tyd3o8FCpump";
+const BONDING_CURVE: &str = "FqMa4ipfB2ZjBmYBPG95oAApxTp6mW3iY6f6AzT6A22T";
+const ASSOCIATED_BONDING_CURVE: &str = "HwusFzrTLytF3UdVEmK9TwMjiJrciEtvdxq1JRNQ1wbs";
+const CREATOR: &str = "t9QUGC7BnUKbD2SwqkAuk4qL76QoySyYwR6bPzFrPFZ";
+
+fn curve_with_creator(creator: Pubkey) -> BondingCurveAccount {
+    BondingCurveAccount {
+        virtual_token_reserves: 1_073_000_000_000_000,
+        virtual_sol_reserves: 30_000_000_000,
+        real_token_reserves: 793_100_000_000_000,
+        real_sol_reserves: 0,
+        token_total_supply: 1_000_000_000_000_000,
+        complete: false,
+        creator,
+    }
+}
+
+#[test]
+fn test_bonding_curve_pda_fixtures() {
+    let (bonding_curve, bump) = find_bonding_curve_pda(&pubkey(MINT));
+    assert_eq!(bonding_curve, pubkey(BONDING_CURVE));
+    assert_eq!(bump, 253);
+
+    let (associated_bonding_curve, _) = find_associated_bonding_curve_pda(&pubkey(MINT), &bonding_curve);
+    assert_eq!(associated_bonding_curve, pubkey(ASSOCIATED_BONDING_CURVE));
+}
+
+#[test]
+fn test_creator_vault_paths_agree() {
+    // Every way the bot resolves a creator vault ends at the same account
+    let (creator_vault, _) = find_creator_vault_pda(&pubkey(CREATOR));
+    assert_eq!(find_creator_vault(&pubkey(CREATOR)), creator_vault);
+    assert_eq!(curve_with_creator(pubkey(CREATOR)).creator_vault(), creator_vault);
+    assert_ne!(find_creator_vault(&Pubkey::new_unique()), creator_vault);
+}
+
+#[test]
+fn test_former_readme_vault_is_not_a_curve_vault() {
+    let former = pubkey("73Lrc9pHLuN59pBsBGL2oScGM2SHkbTZwZqTdVLPHqdf");
+    assert_ne!(find_creator_vault(&pubkey(CREATOR)), former);
+}
+
+#[test]
+fn test_resolve_creator_accounts_overrides() {
+    let curve = curve_with_creator(pubkey(CREATOR));
+
+    // Defaults come from the curve
+    assert_eq!(
+        resolve_creator_accounts(&curve, None, None),
+        (pubkey(CREATOR), find_creator_vault(&pubkey(CREATOR)))
+    );
+
+    // A creator override derives its own vault
+    let other_creator = Pubkey::new_unique();
+    assert_eq!(
+        resolve_creator_accounts(&curve, Some(other_creator), None),
+        (other_creator, find_creator_vault(&other_creator))
+    );
+
+    // A vault override is used as-is
+    let vault = Pubkey::new_unique();
+    assert_eq!(
+        resolve_creator_accounts(&curve, None, Some(vault)),
+        (pubkey(CREATOR), vault)
+    );
+}